@@ -63,6 +63,8 @@ mod setup;
 mod compile;
 mod std_args;
 mod std_archive;
+mod std_test;
+mod repl;
 
 use err::display_error_and_exit;
 use sealconfig::SealConfig;
@@ -111,11 +113,33 @@ enum SealCommand {
     CommandHelp(Box<SealCommand>),
     HelpCommandHelp,
     SealConfigHelp,
-    /// `seal test` (runs test_path from config.luau)
-    Test,
+    /**
+    Run the project's tests: every `*.test.luau`/`*.spec.luau` file in `test_path` (or the cwd), each in its own VM.
+    If `test_path` is a regular script (like a custom test runner), `seal test` without args runs it instead.
+
+    ## Examples:
+    * `seal test`
+    * `seal test ./tests/http --filter "retries" --parallel`
+    * `seal test --watch`
+    */
+    Test(Args),
     Version,
-    /// not yet implemented
+    /**
+    Start an interactive Luau session that keeps its state (globals, required modules) between inputs.
+
+    ## Examples:
+    * `seal repl`
+    * `echo 'print(1 + 1)' | seal repl` (reads lines from stdin without prompts)
+    */
     Repl,
+    /**
+    Serve a directory over HTTP with listings, index.html resolution, and caching headers.
+
+    ## Examples:
+    * `seal serve` (serves your cwd at http://127.0.0.1:8000)
+    * `seal serve ./dist --port 3000 --address 0.0.0.0`
+    */
+    Serve(Args),
     ExecStandalone(Vec<u8>),
     /// Compiles project codebase to standalone executable (or bundles to a .luau file)
     /// seal compile ./myfile.luau sets ./myfile.luau as the entry point file (otherwise defaults to .seal/config.luau entry_path)
@@ -138,8 +162,9 @@ impl SealCommand {
             "bundle" => Self::Bundle(args),
             "eval" | "e" => Self::Eval(args.clone()),
             "run" | "r" => Self::Run,
-            "test" | "t" => Self::Test,
+            "test" | "t" => Self::Test(args),
             "repl" | "i" => Self::Repl,
+            "serve" => Self::Serve(args),
             "help" | "h" => Self::figure_out_which_command_we_need_help_with(args)?,
             // default case `seal ./myfile.luau`
             filename => Self::Default { filename: filename.to_owned() },
@@ -190,7 +215,7 @@ fn main() -> LuaResult<()> {
         SealCommand::Run => seal_run(),
         SealCommand::Setup(options) => seal_setup(options),
         SealCommand::Regen => seal_regen(),
-        SealCommand::Test => seal_test(),
+        SealCommand::Test(args) => seal_test(args),
         SealCommand::Version => {
             puts!("{}", SEAL_VERSION)?;
             Ok(None)
@@ -199,9 +224,8 @@ fn main() -> LuaResult<()> {
         help @ SealCommand::DefaultHelp |
         help @ SealCommand::HelpCommandHelp |
         help @ SealCommand::SealConfigHelp => help.help(),
-        SealCommand::Repl => {
-            wrap_err!("seal repl coming SOON (tm)")
-        },
+        SealCommand::Repl => seal_repl(),
+        SealCommand::Serve(args) => seal_serve(args),
         SealCommand::Compile(args) => compile::run(args, false),
         SealCommand::Bundle(args) => compile::run(args, true),
         SealCommand::ExecStandalone(bytecode) => seal_standalone(bytecode),
@@ -327,22 +351,32 @@ fn seal_run() -> LuauLoadResult {
     resolve_file(entry_path, function_name)
 }
 
-fn seal_test() -> LuauLoadResult {
+fn seal_test(args: Args) -> LuauLoadResult {
     let function_name = "seal test";
     let luau = Lua::default();
-    let test_path = match SealConfig::read(&luau, None, function_name)? {
-        Some(config) => config.test_path,
-        None => {
-            return wrap_err!("{}: no .seal/config.luau located upwards of your cwd; \
-            use seal ./filename.luau to run a specific file", function_name);
-        },
-    };
-    if let Some(test_path) = test_path {
-        globals::set_globals(&luau, test_path.clone())?;
-        resolve_file(test_path, function_name)
-    } else {
-        wrap_err!("{}: attempt to test a project without a 'test_path' field set in .seal/config.luau", function_name)
+    let test_path = SealConfig::read(&luau, None, function_name)?.and_then(|config| config.test_path);
+
+    // projects whose test_path is their own test runner script keep working like they used to
+    if args.is_empty()
+        && let Some(test_path) = &test_path
+        && std::path::Path::new(test_path).is_file()
+        && !std_test::runner::is_test_file(std::path::Path::new(test_path))
+    {
+        return resolve_file(test_path.clone(), function_name);
     }
+
+    std_test::runner::run(args, test_path)?;
+    Ok(None)
+}
+
+fn seal_repl() -> LuauLoadResult {
+    repl::run()?;
+    Ok(None)
+}
+
+fn seal_serve(args: Args) -> LuauLoadResult {
+    std_net::serve::static_files::run(args)?;
+    Ok(None)
 }
 
 fn seal_setup(options: SetupOptions) -> LuauLoadResult {
@@ -431,7 +465,9 @@ impl SealCommand {
             Self::Run => "run",
             Self::Setup(_) => "setup",
             Self::Regen => "regen",
-            Self::Test => "test",
+            Self::Test(_) => "test",
+            Self::Repl => "repl",
+            Self::Serve(_) => "serve",
             Self::HelpCommandHelp => "help",
             Self::SealConfigHelp => "config",
             Self::Compile(_) => "compile",
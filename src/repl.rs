@@ -0,0 +1,216 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+use crate::{globals, std_env, std_fs, std_io, std_net, std_process};
+use crate::err::parse_traceback;
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = ">> ";
+const HISTORY_FILE_NAME: &str = ".seal_repl_history";
+
+/// What happened when we tried to evaluate whatever the user has typed in so far.
+enum Evaluated {
+    /// the chunk ran; these are its return values (empty for plain statements)
+    Values(LuaMultiValue),
+    /// the chunk is syntactically incomplete (an unclosed `do`, `function`, string, etc.)
+    /// so we should keep reading lines before trying again
+    Incomplete,
+    Error(LuaError),
+}
+
+/// Persistent Luau session behind `seal repl`; globals, required modules and locals assigned
+/// to globals all survive between inputs because every input runs against the same `Lua`.
+struct Repl {
+    luau: Lua,
+    /// absolute path of a (nonexistent) file in the cwd, so `require("./mod")` and
+    /// .luaurc aliases resolve relative to where the user started the repl
+    chunk_name: String,
+}
+
+impl Repl {
+    fn new(function_name: &'static str) -> LuaResult<Self> {
+        let cwd = std_env::get_cwd(function_name)?;
+        #[allow(clippy::disallowed_methods, reason = "child is a literal that doesn't start with an absolute path separator")]
+        let chunk_name = cwd.join("repl").to_string_lossy().into_owned();
+
+        // not sandboxed (same as seal eval) so that assigning globals persists between inputs
+        let luau = Lua::default();
+        crate::set_jit(&luau);
+        globals::set_globals(&luau, chunk_name.clone())?;
+
+        // like seal eval, the repl comes with a few libs builtin
+        let globals = luau.globals();
+        globals.raw_set("fs", ok_table(std_fs::create(&luau))?)?;
+        globals.raw_set("process", ok_table(std_process::create(&luau))?)?;
+        globals.raw_set("http", ok_table(std_net::http::create(&luau))?)?;
+
+        Ok(Self { luau, chunk_name })
+    }
+
+    fn evaluate(&self, src: &str) -> Evaluated {
+        // try it as an expression first so `1 + 1` or `fs.readfile("x")` shows its result,
+        // falling back to a regular chunk of statements if that doesn't parse
+        let as_expression = format!("return {}", src);
+        let function = match self.luau.load(as_expression).set_name(&self.chunk_name).into_function() {
+            Ok(function) => function,
+            Err(expression_err) => match self.luau.load(src).set_name(&self.chunk_name).into_function() {
+                Ok(function) => function,
+                // an unfinished expression like `1 +` only runs out of input when parsed as one
+                Err(LuaError::SyntaxError { incomplete_input: true, .. }) => {
+                    return Evaluated::Incomplete;
+                },
+                Err(_) if matches!(expression_err, LuaError::SyntaxError { incomplete_input: true, .. }) => {
+                    return Evaluated::Incomplete;
+                },
+                Err(err) => {
+                    return Evaluated::Error(err);
+                }
+            }
+        };
+        match function.call::<LuaMultiValue>(()) {
+            Ok(values) => Evaluated::Values(values),
+            Err(err) => Evaluated::Error(err),
+        }
+    }
+
+    fn display_values(&self, values: LuaMultiValue) -> LuaEmptyResult {
+        if values.is_empty() {
+            return Ok(());
+        }
+        let mut formatted = Vec::with_capacity(values.len());
+        for value in values {
+            formatted.push(std_io::format::pretty(&self.luau, LuaMultiValue::from_vec(vec![value]))?);
+        }
+        puts!("{}", formatted.join(", "))
+    }
+
+    fn display_error(&self, err: LuaError) -> LuaEmptyResult {
+        let nc = colors::are_disabled();
+        let (bold_red, reset) = if nc { ("", "") } else { (colors::BOLD_RED, colors::RESET) };
+        eputs!("{}[ERR]{} {}", bold_red, reset, parse_traceback(err.to_string()))
+    }
+
+    /// Feeds one line of input to the repl, returning whether we're waiting on more lines
+    /// to complete the current chunk.
+    fn feed(&self, pending: &mut String, line: &str) -> LuaResult<bool> {
+        if !pending.is_empty() {
+            pending.push('\n');
+        }
+        pending.push_str(line);
+
+        if pending.trim().is_empty() {
+            pending.clear();
+            return Ok(false);
+        }
+
+        match self.evaluate(pending) {
+            Evaluated::Incomplete => Ok(true),
+            Evaluated::Values(values) => {
+                pending.clear();
+                self.display_values(values)?;
+                Ok(false)
+            },
+            Evaluated::Error(err) => {
+                pending.clear();
+                self.display_error(err)?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::home_dir().map(|home| {
+        #[allow(clippy::disallowed_methods, reason = "child is a literal that doesn't start with an absolute path separator")]
+        home.join(HISTORY_FILE_NAME)
+    })
+}
+
+/// `seal repl`; reads lines with rustyline (history, line editing) when attached to a terminal,
+/// otherwise reads plain lines from stdin without prompts so the repl can be piped into.
+pub fn run() -> LuaEmptyResult {
+    let function_name = "seal repl";
+    let repl = Repl::new(function_name)?;
+
+    if atty::isnt(atty::Stream::Stdin) || atty::isnt(atty::Stream::Stdout) {
+        return run_without_tty(&repl, function_name);
+    }
+
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            return wrap_err!("{}: unable to make rustyline DefaultEditor due to ReadlineError: {}", function_name, err);
+        }
+    };
+
+    let history_path = history_path();
+    if let Some(path) = &history_path && path.exists() {
+        // a corrupted or unreadable history file shouldn't stop the repl from starting
+        let _ = editor.load_history(path);
+    }
+
+    let (dim, reset) = if colors::are_disabled() { ("", "") } else { (colors::DIM, colors::RESET) };
+    puts!("{}seal {} repl; ctrl+d to exit, ctrl+c to discard current input{}", dim, globals::SEAL_VERSION, reset)?;
+
+    let mut pending = String::new();
+    let mut waiting_on_more = false;
+    loop {
+        let prompt = if waiting_on_more { CONTINUATION_PROMPT } else { PROMPT };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                waiting_on_more = repl.feed(&mut pending, &line)?;
+            },
+            Err(ReadlineError::Interrupted) => {
+                pending.clear();
+                waiting_on_more = false;
+            },
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                return wrap_err!("{}: unable to read line due to err: {}", function_name, err);
+            }
+        }
+    }
+
+    if let Some(path) = &history_path && let Err(err) = editor.save_history(path) {
+        eputs!("[WARN] {}: unable to save history to '{}': {}", function_name, path.display(), err)?;
+    }
+
+    Ok(())
+}
+
+fn run_without_tty(repl: &Repl, function_name: &'static str) -> LuaEmptyResult {
+    let mut stdin = io::stdin().lock();
+    let mut pending = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match stdin.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {
+                let trimmed = line.trim_end_matches(['\n', '\r']);
+                repl.feed(&mut pending, trimmed)?;
+                if let Err(err) = io::stdout().flush() {
+                    return wrap_err!("{}: unable to flush stdout due to err: {}", function_name, err);
+                }
+            },
+            Err(err) => {
+                return wrap_err!("{}: unable to read line from stdin due to err: {}", function_name, err);
+            }
+        }
+    }
+
+    // EOF in the middle of an unfinished chunk; evaluate it anyway so the user sees the syntax error
+    if !pending.is_empty() && let Err(err) = repl.luau.load(pending.as_str()).set_name(&repl.chunk_name).into_function() {
+        repl.display_error(err)?;
+    }
+
+    Ok(())
+}
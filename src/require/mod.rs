@@ -101,8 +101,13 @@ fn get_standard_library(luau: &Lua, path: String) -> LuaValueResult {
         "@std/net" => ok_table(std_net::create(luau)),
         "@std/net/http" => ok_table(std_net::http::create(luau)),
         "@std/net/http/server" => ok_table(std_net::serve::create(luau)),
+        "@std/net/http/mock" => ok_table(std_net::http::mock::create(luau)),
         "@std/net/request" => ok_function_multi_returns_value(std_net::http::http_request, luau),
         "@std/net/websocket" => ok_table(std_net::websocket::create(luau)),
+        "@std/net/tcp" => ok_table(std_net::tcp::create(luau)),
+        "@std/net/udp" => ok_table(std_net::udp::create(luau)),
+        "@std/net/dns" => ok_table(std_net::dns::create(luau)),
+        "@std/net/ip" => ok_table(std_net::ip::create(luau)),
 
         "@std/archive" => ok_table(std_archive::create(luau)),
         "@std/archive/entry" => ok_table(std_archive::entry::create(luau)),
@@ -129,6 +134,8 @@ fn get_standard_library(luau: &Lua, path: String) -> LuaValueResult {
 
         "@std/args" => ok_table(std_args::create(luau)),
 
+        "@std/test" => ok_table(std_test::create(luau)),
+
         "@std" => {
             ok_table(TableBuilder::create(luau)?
                 .with_value("fs", std_fs::create(luau)?)?
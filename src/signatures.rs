@@ -345,11 +345,48 @@ pub const STD_LUAU_EVAL: &std::ffi::CStr = c"luau.eval(src: string, options: Eva
 pub const STD_LUAU_EVAL_UNSAFE: &std::ffi::CStr = c"luau.eval_unsafe(src: string | buffer, options: EvalOptions?) -> unknown | error";
 pub const STD_LUAU_REQUIRE_RESOLVER: &std::ffi::CStr = c"luau.require_resolver() -> { resolve: (requested_path: string, requiring_file_path: string) -> { err: string, path: nil } | { path: string, err: nil }, get_aliases: (requiring_file_path: string) -> ({ [number]: LuaurcAliases }?, string?), expand_aliases: (requested_path: string, aliases_by_luaurc: { [number]: LuaurcAliases }) -> (string?, string?) }";
 
+// net
+pub const STD_NET_INTERFACES: &std::ffi::CStr = c"net.interfaces() -> { NetworkInterface }";
+pub const STD_NET_LOCAL_IP: &std::ffi::CStr = c"net.local_ip(family: (\"IPv4\" | \"IPv6\")?) -> string?";
+
+// net.dns
+pub const STD_NET_DNS_LOOKUP: &std::ffi::CStr = c"net.dns.lookup(host: string) -> { string }";
+pub const STD_NET_DNS_REVERSE: &std::ffi::CStr = c"net.dns.reverse(ip: string) -> string?";
+
 // net.http
+pub const STD_NET_HTTP_CLIENT: &std::ffi::CStr = c"net.http.client(options: HttpClientOptions?) -> HttpClient";
+pub const STD_NET_HTTP_DOWNLOAD: &std::ffi::CStr = c"net.http.download(options: DownloadOptions) -> DownloadResult";
+pub const STD_NET_HTTP_EVENTS: &std::ffi::CStr = c"net.http.events(url: string, options: EventStreamOptions?) -> EventStream";
 pub const STD_NET_HTTP_GET: &std::ffi::CStr = c"net.http.get(options: HttpRequestWithoutBody) -> HttpResponse";
 pub const STD_NET_HTTP_POST: &std::ffi::CStr = c"net.http.post(options: HttpRequestWithBody) -> HttpResponse";
 pub const STD_NET_HTTP_REQUEST: &std::ffi::CStr = c"net.http.request(method: HttpMethod, options: HttpRequestWithoutBody | HttpRequestWithBody) -> HttpResponseResult";
 
+// net.http.mock
+pub const STD_NET_HTTP_MOCK_ALLOW_NETWORK: &std::ffi::CStr = c"net.http.mock.allow_network(allowed: boolean)";
+pub const STD_NET_HTTP_MOCK_ON: &std::ffi::CStr = c"net.http.mock.on(route: MockRoute)";
+pub const STD_NET_HTTP_MOCK_RECORD: &std::ffi::CStr = c"net.http.mock.record(path: string)";
+pub const STD_NET_HTTP_MOCK_REPLAY: &std::ffi::CStr = c"net.http.mock.replay(path: string)";
+pub const STD_NET_HTTP_MOCK_REQUESTS: &std::ffi::CStr = c"net.http.mock.requests() -> { MockedRequest }";
+pub const STD_NET_HTTP_MOCK_RESET: &std::ffi::CStr = c"net.http.mock.reset()";
+
+// EventStream
+pub const STD_NET_HTTP_EVENT_STREAM_CLOSE: &std::ffi::CStr = c"EventStream:close()";
+pub const STD_NET_HTTP_EVENT_STREAM_NEXT: &std::ffi::CStr = c"EventStream:next() -> ServerSentEvent?";
+
+// HttpBodyStream
+pub const STD_NET_HTTP_HTTP_BODY_STREAM_CLOSE: &std::ffi::CStr = c"HttpBodyStream:close()";
+pub const STD_NET_HTTP_HTTP_BODY_STREAM_ITER: &std::ffi::CStr = c"HttpBodyStream:iter() -> () -> buffer";
+pub const STD_NET_HTTP_HTTP_BODY_STREAM_LINES: &std::ffi::CStr = c"HttpBodyStream:lines() -> () -> string";
+pub const STD_NET_HTTP_HTTP_BODY_STREAM_READ: &std::ffi::CStr = c"HttpBodyStream:read(count: number?) -> buffer?";
+pub const STD_NET_HTTP_HTTP_BODY_STREAM_READ_TO: &std::ffi::CStr = c"HttpBodyStream:read_to(term: string, inclusive: boolean?, allow_partial: boolean?) -> string?";
+
+// HttpClient
+pub const STD_NET_HTTP_HTTP_CLIENT_CLEAR_COOKIES: &std::ffi::CStr = c"HttpClient:clear_cookies()";
+pub const STD_NET_HTTP_HTTP_CLIENT_COOKIES: &std::ffi::CStr = c"HttpClient:cookies() -> { [string]: string }";
+pub const STD_NET_HTTP_HTTP_CLIENT_GET: &std::ffi::CStr = c"HttpClient:get(options: HttpRequestWithoutBody | string) -> HttpResponse";
+pub const STD_NET_HTTP_HTTP_CLIENT_POST: &std::ffi::CStr = c"HttpClient:post(options: HttpRequestWithBody) -> HttpResponse";
+pub const STD_NET_HTTP_HTTP_CLIENT_REQUEST: &std::ffi::CStr = c"HttpClient:request(method: HttpMethod, options: HttpRequestWithoutBody | HttpRequestWithBody) -> HttpResponseResult";
+
 // HttpResponse
 pub const STD_NET_HTTP_HTTP_RESPONSE_EXPECT_JSON: &std::ffi::CStr = c"HttpResponse:expect_json() -> T";
 pub const STD_NET_HTTP_HTTP_RESPONSE_TRY_JSON: &std::ffi::CStr = c"HttpResponse:try_json() -> T?";
@@ -357,14 +394,51 @@ pub const STD_NET_HTTP_HTTP_RESPONSE_TRY_JSON: &std::ffi::CStr = c"HttpResponse:
 // net.http.server
 pub const STD_NET_HTTP_SERVER_HANDLER: &std::ffi::CStr = c"net.http.server.handler(ServeRequest) -> ServeResponse";
 
+// net.ip
+pub const STD_NET_IP_CIDR: &std::ffi::CStr = c"net.ip.cidr(range: string) -> Cidr";
+pub const STD_NET_IP_PARSE: &std::ffi::CStr = c"net.ip.parse(ip: string) -> ParsedIp?";
+pub const STD_NET_IP_VALID: &std::ffi::CStr = c"net.ip.valid(ip: string) -> boolean";
+
+// Cidr
+pub const STD_NET_IP_CIDR_ADDRESSES: &std::ffi::CStr = c"Cidr:addresses() -> () -> string";
+pub const STD_NET_IP_CIDR_CONTAINS: &std::ffi::CStr = c"Cidr:contains(ip: string) -> boolean";
+pub const STD_NET_IP_CIDR_SUBNETS: &std::ffi::CStr = c"Cidr:subnets(prefix: number) -> () -> Cidr";
+
+// net.tcp
+pub const STD_NET_TCP_CONNECT: &std::ffi::CStr = c"net.tcp.connect(address: string, timeout: (number | Duration)?) -> TcpStream";
+pub const STD_NET_TCP_LISTEN: &std::ffi::CStr = c"net.tcp.listen(address: string) -> TcpListener";
+
+// TcpListener
+pub const STD_NET_TCP_TCP_LISTENER_ACCEPT: &std::ffi::CStr = c"TcpListener:accept(timeout: (number | Duration)?) -> TcpStream?";
+pub const STD_NET_TCP_TCP_LISTENER_CLOSE: &std::ffi::CStr = c"TcpListener:close()";
+
+// TcpStream
+pub const STD_NET_TCP_TCP_STREAM_CLOSE: &std::ffi::CStr = c"TcpStream:close()";
+pub const STD_NET_TCP_TCP_STREAM_LINES: &std::ffi::CStr = c"TcpStream:lines() -> () -> string";
+pub const STD_NET_TCP_TCP_STREAM_READ: &std::ffi::CStr = c"TcpStream:read(count: number?, timeout: (number | Duration)?) -> string?";
+pub const STD_NET_TCP_TCP_STREAM_READ_EXACT: &std::ffi::CStr = c"TcpStream:read_exact(count: number, timeout: (number | Duration)?) -> string?";
+pub const STD_NET_TCP_TCP_STREAM_READ_TO: &std::ffi::CStr = c"TcpStream:read_to(term: string, inclusive: boolean?, timeout: (number | Duration)?, allow_partial: boolean?) -> string?";
+pub const STD_NET_TCP_TCP_STREAM_SET_NODELAY: &std::ffi::CStr = c"TcpStream:set_nodelay(nodelay: boolean)";
+pub const STD_NET_TCP_TCP_STREAM_WRITE: &std::ffi::CStr = c"TcpStream:write(data: string | buffer, timeout: (number | Duration)?)";
+
+// net.udp
+pub const STD_NET_UDP_BIND: &std::ffi::CStr = c"net.udp.bind(address: string) -> UdpSocket";
+
+// UdpSocket
+pub const STD_NET_UDP_UDP_SOCKET_CLOSE: &std::ffi::CStr = c"UdpSocket:close()";
+pub const STD_NET_UDP_UDP_SOCKET_RECV_FROM: &std::ffi::CStr = c"UdpSocket:recv_from(timeout: (number | Duration)?) -> UdpPacket?";
+pub const STD_NET_UDP_UDP_SOCKET_SEND_TO: &std::ffi::CStr = c"UdpSocket:send_to(data: string | buffer, address: string) -> number";
+
 // net.websocket
-pub const STD_NET_WEBSOCKET_CONNECT: &std::ffi::CStr = c"net.websocket.connect(url: string) -> Websocket";
+pub const STD_NET_WEBSOCKET_CONNECT: &std::ffi::CStr = c"net.websocket.connect(url: string, options: WebsocketOptions?) -> Websocket";
 
 // process
 pub const STD_PROCESS_EXIT: &std::ffi::CStr = c"process.exit(code: number?) -> never";
+pub const STD_PROCESS_PIPELINE: &std::ffi::CStr = c"process.pipeline(stages: { RunOptions }) -> PipelineResult";
 pub const STD_PROCESS_RUN: &std::ffi::CStr = c"process.run(options: RunOptions) -> RunResult";
 pub const STD_PROCESS_SHELL: &std::ffi::CStr = c"process.shell(command: string) -> RunResult";
 pub const STD_PROCESS_SPAWN: &std::ffi::CStr = c"process.spawn(options: SpawnOptions) -> ChildProcess";
+pub const STD_PROCESS_SPAWN_PIPELINE: &std::ffi::CStr = c"process.spawn_pipeline(stages: { SpawnOptions }) -> PipelineChild";
 
 // RunResult
 pub const STD_PROCESS_RUN_RESULT_UNWRAP: &std::ffi::CStr = c"RunResult:unwrap() -> string";
@@ -383,11 +457,26 @@ pub const STD_PROCESS_CHILD_PROCESS_STREAM_READ_TO: &std::ffi::CStr = c"ChildPro
 
 // ChildProcess
 pub const STD_PROCESS_CHILD_PROCESS_ALIVE: &std::ffi::CStr = c"ChildProcess:alive() -> boolean";
+pub const STD_PROCESS_CHILD_PROCESS_EXPECT: &std::ffi::CStr = c"ChildProcess:expect(pattern: string | { regex: string }, timeout: Duration | number?) -> ExpectMatch?";
 pub const STD_PROCESS_CHILD_PROCESS_KILL: &std::ffi::CStr = c"ChildProcess:kill()";
+pub const STD_PROCESS_CHILD_PROCESS_SENDLINE: &std::ffi::CStr = c"ChildProcess:sendline(line: string?) -> error?";
+pub const STD_PROCESS_CHILD_PROCESS_SIGNAL: &std::ffi::CStr = c"ChildProcess:signal(signal: Signal | number)";
+pub const STD_PROCESS_CHILD_PROCESS_TERMINATE: &std::ffi::CStr = c"ChildProcess:terminate(grace: Duration | number?) -> ExitStatus";
+pub const STD_PROCESS_CHILD_PROCESS_TRY_WAIT: &std::ffi::CStr = c"ChildProcess:try_wait() -> ExitStatus?";
+pub const STD_PROCESS_CHILD_PROCESS_WAIT: &std::ffi::CStr = c"ChildProcess:wait(timeout: Duration | number?) -> ExitStatus?";
 
 // PipedChild
 pub const STD_PROCESS_PIPED_CHILD_ALIVE: &std::ffi::CStr = c"PipedChild:alive() -> boolean";
+pub const STD_PROCESS_PIPED_CHILD_EXPECT: &std::ffi::CStr = c"PipedChild:expect(pattern: string | { regex: string }, timeout: Duration | number?) -> ExpectMatch?";
 pub const STD_PROCESS_PIPED_CHILD_KILL: &std::ffi::CStr = c"PipedChild:kill()";
+pub const STD_PROCESS_PIPED_CHILD_SENDLINE: &std::ffi::CStr = c"PipedChild:sendline(line: string?) -> error?";
+pub const STD_PROCESS_PIPED_CHILD_SIGNAL: &std::ffi::CStr = c"PipedChild:signal(signal: Signal | number)";
+pub const STD_PROCESS_PIPED_CHILD_TERMINATE: &std::ffi::CStr = c"PipedChild:terminate(grace: Duration | number?) -> ExitStatus";
+pub const STD_PROCESS_PIPED_CHILD_TRY_WAIT: &std::ffi::CStr = c"PipedChild:try_wait() -> ExitStatus?";
+pub const STD_PROCESS_PIPED_CHILD_WAIT: &std::ffi::CStr = c"PipedChild:wait(timeout: Duration | number?) -> ExitStatus?";
+
+// PtyChild
+pub const STD_PROCESS_PTY_CHILD_RESIZE: &std::ffi::CStr = c"PtyChild:resize(rows: number, cols: number)";
 
 // semver
 pub const STD_SEMVER_DEFAULT: &std::ffi::CStr = c"semver.default() -> Semver";
@@ -16,6 +16,15 @@ use crate::std_fs::{
     entry::wrap_io_read_errors_empty
 };
 
+/// Returns the offending path if joining `path` onto a directory would escape it (absolute paths, `..`, etc.);
+/// used when extracting archives and by `seal serve` to check request paths
+pub(crate) fn unsafe_path_traversal(path: &Path) -> Option<String> {
+    match archive::path_safety::validate_path(path.to_string_lossy().as_ref(), false) {
+        Err(ArchiveError::UnsafePath(bad_path)) => Some(bad_path),
+        _ => None,
+    }
+}
+
 // permission bits on unix system, noop on windows
 fn set_mode_raw(path: &Path, mode: Option<u32>) -> io::Result<()> {
     #[cfg(unix)]
@@ -169,8 +178,7 @@ pub fn stream_to_disk<P: AsRef<Path>>(
     // called from inside extract_streaming's callback, whose Result is archive::Result
     fn validate_path_safety(path: &Path, options: &ArchiveOptions) -> io::Result<()> {
         use std::io::Write;
-        let validation = archive::path_safety::validate_path(path.to_string_lossy().as_ref(), false);
-        if let Err(ArchiveError::UnsafePath(bad_path)) = validation {
+        if let Some(bad_path) = unsafe_path_traversal(path) {
             if options.allow_unsafe_path_traversals {
                 let mut stderr = std::io::stderr().lock();
                 if !colors::are_disabled() {
@@ -340,8 +348,7 @@ pub fn write_to_disk<P: AsRef<Path>>(
     }
 
     fn validate_path_safety(path: &Path, options: &ArchiveOptions, function_name: &'static str) -> LuaEmptyResult {
-        let validation = archive::path_safety::validate_path(path.to_string_lossy().as_ref(), false);
-        if let Err(ArchiveError::UnsafePath(path)) = validation {
+        if let Some(path) = unsafe_path_traversal(path) {
             if options.allow_unsafe_path_traversals {
                 if !colors::are_disabled() {
                     eputs!("{}[WARN]{}{} writing to '{}' (ArchiveOptions.allow_unsafe_path_traversals enabled){}", colors::BOLD_YELLOW, colors::RESET, colors::YELLOW, &path, colors::RESET)?;
@@ -0,0 +1,151 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::io;
+use std::net::{IpAddr, ToSocketAddrs};
+
+fn dns_lookup(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "dns.lookup(host: string)";
+    let host = match value {
+        LuaValue::String(host) => host.to_string_lossy(),
+        LuaNil => {
+            return wrap_err!("{} called without required argument 'host' (expected string)", function_name);
+        },
+        other => {
+            return wrap_err!("{}: expected host to be a string, got: {:?}", function_name, other);
+        }
+    };
+
+    // the port doesn't matter, getaddrinfo just needs one
+    let addresses = match (host.as_str(), 0).to_socket_addrs() {
+        Ok(addresses) => addresses,
+        Err(err) => {
+            return wrap_err!("{}: unable to resolve '{}' due to err: {}", function_name, host, err);
+        }
+    };
+
+    // getaddrinfo returns an entry per socket type, so the same ip usually shows up more than once
+    let mut ips: Vec<String> = Vec::new();
+    for address in addresses {
+        let ip = address.ip().to_string();
+        if !ips.contains(&ip) {
+            ips.push(ip);
+        }
+    }
+
+    ok_table(luau.create_sequence_from(ips))
+}
+
+fn dns_reverse(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "dns.reverse(ip: string)";
+    let ip = match value {
+        LuaValue::String(ip) => {
+            let ip = ip.to_string_lossy();
+            match ip.parse::<IpAddr>() {
+                Ok(ip) => ip,
+                Err(err) => {
+                    return wrap_err!("{}: '{}' isn't a valid ip address: {}", function_name, ip, err);
+                }
+            }
+        },
+        LuaNil => {
+            return wrap_err!("{} called without required argument 'ip' (expected string)", function_name);
+        },
+        other => {
+            return wrap_err!("{}: expected ip to be a string, got: {:?}", function_name, other);
+        }
+    };
+
+    match reverse_lookup(ip) {
+        Ok(Some(host)) => ok_string(host, luau),
+        Ok(None) => Ok(LuaNil),
+        Err(err) => {
+            wrap_err!("{}: unable to look up '{}' due to err: {}", function_name, ip, err)
+        }
+    }
+}
+
+/// `getnameinfo` with `NI_NAMEREQD`, so addresses without a PTR record come back as `None`
+/// instead of getting formatted back into the same ip
+#[cfg(unix)]
+fn reverse_lookup(ip: IpAddr) -> io::Result<Option<String>> {
+    use std::ffi::CStr;
+
+    // NI_MAXHOST
+    let mut host = [0 as libc::c_char; 1025];
+
+    let code = match ip {
+        IpAddr::V4(ip) => {
+            // SAFETY: sockaddr_in is plain old data, so all zeroes is a valid (if meaningless) value we fill in below
+            let mut address: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+            address.sin_family = libc::AF_INET as libc::sa_family_t;
+            address.sin_addr = libc::in_addr { s_addr: u32::from(ip).to_be() };
+            #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+            {
+                address.sin_len = size_of::<libc::sockaddr_in>() as u8;
+            }
+            // SAFETY: `address` is a fully initialized sockaddr_in and we pass its exact size; `host` is a buffer we own
+            // of the length we pass, which getnameinfo nul-terminates on success. The service buffer's null with length 0.
+            unsafe {
+                libc::getnameinfo(
+                    &address as *const libc::sockaddr_in as *const libc::sockaddr,
+                    size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+        },
+        IpAddr::V6(ip) => {
+            // SAFETY: sockaddr_in6 is plain old data, so all zeroes is a valid (if meaningless) value we fill in below
+            let mut address: libc::sockaddr_in6 = unsafe { std::mem::zeroed() };
+            address.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            address.sin6_addr = libc::in6_addr { s6_addr: ip.octets() };
+            #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+            {
+                address.sin6_len = size_of::<libc::sockaddr_in6>() as u8;
+            }
+            // SAFETY: same as above, with a fully initialized sockaddr_in6 and its exact size
+            unsafe {
+                libc::getnameinfo(
+                    &address as *const libc::sockaddr_in6 as *const libc::sockaddr,
+                    size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+        }
+    };
+
+    match code {
+        0 => {
+            // SAFETY: getnameinfo succeeded, so `host` holds a nul-terminated string within its bounds
+            let host = unsafe { CStr::from_ptr(host.as_ptr()) };
+            Ok(Some(host.to_string_lossy().to_string()))
+        },
+        libc::EAI_NONAME => Ok(None),
+        libc::EAI_SYSTEM => Err(io::Error::last_os_error()),
+        code => {
+            // SAFETY: gai_strerror returns a pointer to a static nul-terminated message for any error code
+            let message = unsafe { CStr::from_ptr(libc::gai_strerror(code)) };
+            Err(io::Error::other(message.to_string_lossy().to_string()))
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn reverse_lookup(_ip: IpAddr) -> io::Result<Option<String>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "reverse dns lookups aren't supported on this platform yet"))
+}
+
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::create(luau)?
+        .with_function_and_signature("lookup", dns_lookup, signatures::STD_NET_DNS_LOOKUP)?
+        .with_function_and_signature("reverse", dns_reverse, signatures::STD_NET_DNS_REVERSE)?
+        .build_readonly()
+}
@@ -0,0 +1,285 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use ureq::http::Method;
+
+use super::{HttpRequest, TimeoutInfo};
+
+use crate::std_net::proxy::ProxySetting;
+use crate::std_net::tls_config::TlsOptions;
+
+/// What an `http.client` session does with cookies the server sets
+enum CookieMode {
+    /// `cookies = false`: the jar's emptied after every request, so nothing's sent back
+    Off,
+    /// `cookies = true` (default): kept for as long as the session's alive
+    Memory,
+    /// `cookies = "path.json"`: loaded when the session's created and saved after every request
+    File(PathBuf),
+}
+
+/// An `http.client` session; every request made through it shares one `ureq::Agent`,
+/// so keep-alive connections get reused and cookies persist between requests.
+pub struct HttpClient {
+    pub agent: ureq::Agent,
+    pub base_url: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub params: Vec<(String, String)>,
+    pub timeout: Option<TimeoutInfo>,
+    /// applies to every request in the session; requests can't override it since they share pooled connections
+    pub tls: Option<TlsOptions>,
+    pub proxy: Option<ProxySetting>,
+    cookies: CookieMode,
+}
+
+impl HttpClient {
+    fn from_table(options: Option<LuaTable>, function_name: &'static str) -> LuaResult<Self> {
+        let Some(options) = options else {
+            return Ok(Self {
+                agent: ureq::Agent::new_with_defaults(),
+                base_url: None,
+                headers: Vec::new(),
+                params: Vec::new(),
+                timeout: None,
+                tls: None,
+                proxy: None,
+                cookies: CookieMode::Memory,
+            });
+        };
+
+        let base_url = match options.raw_get("base_url")? {
+            LuaValue::String(base_url) => {
+                let base_url = match base_url.to_str() {
+                    Ok(base_url) => base_url.to_string(),
+                    Err(err) => {
+                        return wrap_err!("{}: HttpClientOptions.base_url must be valid utf-8: {}", function_name, err);
+                    }
+                };
+                if let Err(err) = base_url.parse::<ureq::http::Uri>() {
+                    return wrap_err!("{}: invalid base_url {:?}: {}", function_name, base_url, err);
+                }
+                Some(base_url)
+            },
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected HttpClientOptions.base_url to be a string or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let headers = match options.raw_get("headers")? {
+            LuaValue::Table(t) => HttpRequest::map_to_vec(t, "headers", function_name)?,
+            LuaNil => Vec::new(),
+            other => {
+                return wrap_err!("{}: expected HttpClientOptions.headers to be {{ [string]: string }} or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let params = match options.raw_get("params")? {
+            LuaValue::Table(t) => HttpRequest::map_to_vec(t, "params", function_name)?,
+            LuaNil => Vec::new(),
+            other => {
+                return wrap_err!("{}: expected HttpClientOptions.params to be {{ [string]: string }} or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let cookies = match options.raw_get("cookies")? {
+            LuaValue::Boolean(true) | LuaNil => CookieMode::Memory,
+            LuaValue::Boolean(false) => CookieMode::Off,
+            LuaValue::String(path) => CookieMode::File(PathBuf::from(path.to_string_lossy())),
+            other => {
+                return wrap_err!("{}: expected HttpClientOptions.cookies to be a boolean, a path to a cookie file (string), or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let tls = TlsOptions::from_value(options.raw_get("tls")?, "HttpClientOptions.tls", function_name)?;
+        let proxy = ProxySetting::from_value(options.raw_get("proxy")?, "HttpClientOptions.proxy", function_name)?;
+
+        let timeout = TimeoutInfo::from_config(options, function_name)?;
+
+        // seal's usual request config (status codes aren't errors, our root certs, timeouts)
+        // is applied to each request by `Sender::configure`, same as one-off requests
+        let agent = ureq::Agent::new_with_defaults();
+
+        if let CookieMode::File(path) = &cookies {
+            match File::open(path) {
+                Ok(file) => {
+                    if let Err(err) = agent.cookie_jar_lock().load_json(BufReader::new(file)) {
+                        return wrap_err!("{}: unable to load cookies from '{}' due to err: {}", function_name, path.display(), err);
+                    }
+                },
+                // first run; it'll get created after the first request
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {},
+                Err(err) => {
+                    return wrap_err!("{}: unable to open cookie file '{}' due to err: {}", function_name, path.display(), err);
+                }
+            }
+        }
+
+        Ok(Self { agent, base_url, headers, params, timeout, tls, proxy, cookies })
+    }
+
+    /// Called after every request so `cookies = false` doesn't carry anything over
+    /// and `cookies = "path.json"` stays up to date on disk
+    fn after_request(&self, function_name: &'static str) -> LuaEmptyResult {
+        match &self.cookies {
+            CookieMode::Memory => Ok(()),
+            CookieMode::Off => {
+                self.agent.cookie_jar_lock().clear();
+                Ok(())
+            },
+            CookieMode::File(path) => {
+                let mut file = match File::create(path) {
+                    Ok(file) => file,
+                    Err(err) => {
+                        return wrap_err!("{}: unable to open cookie file '{}' for writing due to err: {}", function_name, path.display(), err);
+                    }
+                };
+                if let Err(err) = self.agent.cookie_jar_lock().save_json(&mut file) {
+                    return wrap_err!("{}: unable to save cookies to '{}' due to err: {}", function_name, path.display(), err);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn get(&self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "HttpClient:get(options: HttpRequestWithoutBody | string)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let config = match multivalue.pop_front() {
+            Some(LuaValue::Table(t)) => t,
+            Some(LuaValue::String(url)) => {
+                TableBuilder::create(luau)?
+                    .with_value("url", LuaValue::String(url))?
+                    .build()?
+            },
+            Some(other) => {
+                return wrap_err!("{}: expected options to be a HttpRequestWithoutBody table or string (url), got: {:?}", function_name, other);
+            },
+            None => {
+                return wrap_err!("{}: expected options to be a HttpRequestWithoutBody table or string (url), got nothing", function_name);
+            }
+        };
+
+        let request = HttpRequest::from_config(Method::GET, config, luau, function_name)?
+            .with_client(self, function_name)?;
+        let response = request.send(function_name)?;
+        self.after_request(function_name)?;
+
+        ok_table(response.into_table(luau, function_name))
+    }
+
+    fn post(&self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "HttpClient:post(options: HttpRequestWithBody)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let config = match multivalue.pop_front() {
+            Some(LuaValue::Table(t)) => t,
+            Some(other) => {
+                return wrap_err!("{}: expected options to be an HttpRequestWithBody table, got: {:?}", function_name, other);
+            },
+            None => {
+                return wrap_err!("{}: expected options to be an HttpRequestWithBody table, got nothing", function_name);
+            }
+        };
+
+        let request = HttpRequest::from_config(Method::POST, config, luau, function_name)?
+            .with_client(self, function_name)?;
+        let response = request.send(function_name)?;
+        self.after_request(function_name)?;
+
+        ok_table(response.into_table(luau, function_name))
+    }
+
+    fn request(&self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "HttpClient:request(method: HttpMethod, options: HttpRequestWithoutBody | HttpRequestWithBody)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let http_method = super::method_from_value(multivalue.pop_front(), function_name)?;
+
+        let config = match multivalue.pop_front() {
+            Some(LuaValue::Table(t)) => t,
+            Some(LuaNil) | None => {
+                return wrap_err!("{} called without required argument 'options' (expected table, got nothing or nil)", function_name);
+            },
+            Some(other) => {
+                return wrap_err!("{}: expected options to be an HttpRequestWithBody table, got: {:?}", function_name, other);
+            }
+        };
+
+        let request = HttpRequest::from_config(http_method, config, luau, function_name)?
+            .with_client(self, function_name)?;
+        let result = super::try_request(luau, request, function_name)?;
+        self.after_request(function_name)?;
+
+        Ok(result)
+    }
+
+    fn cookies(&self, luau: &Lua) -> LuaValueResult {
+        let jar = self.agent.cookie_jar_lock();
+        let cookies = luau.create_table()?;
+        for cookie in jar.iter() {
+            cookies.raw_set(cookie.name(), cookie.value())?;
+        }
+        jar.release();
+        Ok(LuaValue::Table(cookies))
+    }
+
+    fn create_handle(self, luau: &Lua) -> LuaResult<LuaTable> {
+        let client = Rc::new(self);
+        TableBuilder::create(luau)?
+            .with_function_and_signature("get", {
+                let client = Rc::clone(&client);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    client.get(luau, multivalue)
+                }
+            }, signatures::STD_NET_HTTP_HTTP_CLIENT_GET)?
+            .with_function_and_signature("post", {
+                let client = Rc::clone(&client);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    client.post(luau, multivalue)
+                }
+            }, signatures::STD_NET_HTTP_HTTP_CLIENT_POST)?
+            .with_function_and_signature("request", {
+                let client = Rc::clone(&client);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    client.request(luau, multivalue)
+                }
+            }, signatures::STD_NET_HTTP_HTTP_CLIENT_REQUEST)?
+            .with_function_and_signature("cookies", {
+                let client = Rc::clone(&client);
+                move | luau: &Lua, mut multivalue: LuaMultiValue | -> LuaValueResult {
+                    pop_self(&mut multivalue, "HttpClient:cookies()")?;
+                    client.cookies(luau)
+                }
+            }, signatures::STD_NET_HTTP_HTTP_CLIENT_COOKIES)?
+            .with_function_and_signature("clear_cookies", {
+                let client = Rc::clone(&client);
+                move | _luau: &Lua, mut multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "HttpClient:clear_cookies()";
+                    pop_self(&mut multivalue, function_name)?;
+                    client.agent.cookie_jar_lock().clear();
+                    client.after_request(function_name)
+                }
+            }, signatures::STD_NET_HTTP_HTTP_CLIENT_CLEAR_COOKIES)?
+            .build_readonly()
+    }
+}
+
+pub fn http_client(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "http.client(options: HttpClientOptions?)";
+    let options = match value {
+        LuaValue::Table(options) => Some(options),
+        LuaNil => None,
+        other => {
+            return wrap_err!("{}: expected options to be an HttpClientOptions table or nil, got: {:?}", function_name, other);
+        }
+    };
+
+    ok_table(HttpClient::from_table(options, function_name)?.create_handle(luau))
+}
@@ -0,0 +1,263 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ring::digest::{Context, SHA256, SHA256_OUTPUT_LEN};
+use ureq::http::Method;
+use ureq::http::header::{CONTENT_LENGTH, CONTENT_RANGE};
+
+use super::HttpRequest;
+
+/// How much of the body we read (and write to disk) at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+/// `on_progress` gets called at most this often (plus once when the download finishes)
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+struct DownloadOptions {
+    request: HttpRequest,
+    path: PathBuf,
+    resume: bool,
+    on_progress: Option<LuaFunction>,
+    sha256: Option<[u8; SHA256_OUTPUT_LEN]>,
+}
+
+impl DownloadOptions {
+    fn from_table(luau: &Lua, config: LuaTable, function_name: &'static str) -> LuaResult<Self> {
+        let path = match config.raw_get("path")? {
+            LuaValue::String(path) => PathBuf::from(path.to_string_lossy()),
+            LuaNil => {
+                return wrap_err!("{}: DownloadOptions missing field path: expected string, got nil", function_name);
+            },
+            other => {
+                return wrap_err!("{}: expected DownloadOptions.path to be a string, got: {:?}", function_name, other);
+            }
+        };
+
+        let resume = match config.raw_get("resume")? {
+            LuaValue::Boolean(resume) => resume,
+            LuaNil => false,
+            other => {
+                return wrap_err!("{}: expected DownloadOptions.resume to be a boolean or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let on_progress = match config.raw_get("on_progress")? {
+            LuaValue::Function(f) => Some(f),
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected DownloadOptions.on_progress to be a function (downloaded: number, total: number?) -> () or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let sha256 = match config.raw_get("sha256")? {
+            // hex, like `sha256sum` prints
+            LuaValue::String(digest) => {
+                let digest = digest.to_string_lossy();
+                let mut decoded = [0u8; SHA256_OUTPUT_LEN];
+                if let Err(err) = hex::decode_to_slice(digest.trim(), &mut decoded) {
+                    return wrap_err!("{}: DownloadOptions.sha256 should be a 64 character hex string, got '{}' ({})", function_name, digest, err);
+                }
+                Some(decoded)
+            },
+            // what crypt.hash.sha2 returns
+            LuaValue::Buffer(digest) => {
+                match <[u8; SHA256_OUTPUT_LEN]>::try_from(digest.to_vec()) {
+                    Ok(decoded) => Some(decoded),
+                    Err(_) => {
+                        return wrap_err!("{}: DownloadOptions.sha256 buffer should be 32 bytes long, got {} bytes", function_name, digest.len());
+                    }
+                }
+            },
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected DownloadOptions.sha256 to be a hex string, a buffer (from crypt.hash.sha2), or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let mut request = HttpRequest::from_config(Method::GET, config, luau, function_name)?;
+        request.set_streams_body();
+        // compressed responses would make Content-Length and Range offsets refer to different bytes than we write
+        if !request.has_header("accept-encoding") {
+            request.set_header("Accept-Encoding", String::from("identity"));
+        }
+
+        Ok(Self { request, path, resume, on_progress, sha256 })
+    }
+}
+
+/// Partially downloaded files live next to `path` until they're complete (and verified),
+/// so `path` itself never contains a truncated download
+fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_os_string();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Feeds what's already on disk into `context` so a resumed download's digest covers the whole file
+fn hash_existing(context: &mut Context, path: &Path) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        match file.read(&mut chunk)? {
+            0 => return Ok(()),
+            bytes_read => context.update(&chunk[..bytes_read]),
+        }
+    }
+}
+
+/// `Content-Range: bytes start-end/total` → (start, total)
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+fn header_str<'a>(response: &'a ureq::http::Response<ureq::Body>, name: ureq::http::header::HeaderName) -> Option<&'a str> {
+    response.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+pub fn http_download(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "http.download(options: DownloadOptions)";
+
+    let config = match value {
+        LuaValue::Table(config) => config,
+        other => {
+            return wrap_err!("{}: expected options to be a DownloadOptions table, got: {:?}", function_name, other);
+        }
+    };
+    let options = DownloadOptions::from_table(luau, config, function_name)?;
+    let part_path = part_path(&options.path);
+
+    let existing_len = if options.resume {
+        fs::metadata(&part_path).map(|metadata| metadata.len()).unwrap_or(0)
+    } else {
+        0
+    };
+
+    let mut request = options.request.clone();
+    if existing_len > 0 {
+        request.set_header("Range", format!("bytes={}-", existing_len));
+    }
+    let mut response = request.send_without_body(function_name)?.into_inner();
+
+    if existing_len > 0 && response.status().as_u16() == 416 {
+        // the part file's bigger than (or as big as) what the server has now; the file probably changed, so start over
+        response = options.request.clone().send_without_body(function_name)?.into_inner();
+    }
+
+    let status = response.status();
+    let (offset, total) = match status.as_u16() {
+        206 if existing_len > 0 => {
+            match header_str(&response, CONTENT_RANGE).and_then(parse_content_range) {
+                Some((start, total)) if start == existing_len => (existing_len, total),
+                other => {
+                    return wrap_err!("{}: server sent a partial response for the wrong range (expected to resume from byte {}, got {:?})", function_name, existing_len, other);
+                }
+            }
+        },
+        // the server doesn't support ranges (or we didn't ask), so we're getting the whole file
+        200 => (0, header_str(&response, CONTENT_LENGTH).and_then(|len| len.trim().parse::<u64>().ok())),
+        _ => {
+            return wrap_err!(
+                "{}: unable to download to '{}' because the server responded with {} {}",
+                function_name, options.path.display(), status.as_u16(), status.canonical_reason().unwrap_or_default()
+            );
+        }
+    };
+
+    let mut context = options.sha256.map(|_| Context::new(&SHA256));
+    if offset > 0
+        && let Some(context) = context.as_mut()
+        && let Err(err) = hash_existing(context, &part_path)
+    {
+        return wrap_err!("{}: unable to read partially downloaded file '{}' due to err: {}", function_name, part_path.display(), err);
+    }
+
+    let file_result = if offset > 0 {
+        OpenOptions::new().append(true).open(&part_path)
+    } else {
+        File::create(&part_path)
+    };
+    let mut file = match file_result {
+        Ok(file) => file,
+        Err(err) => {
+            return wrap_err!("{}: unable to open '{}' for writing due to err: {}", function_name, part_path.display(), err);
+        }
+    };
+
+    let report_progress = |downloaded: u64| -> LuaEmptyResult {
+        if let Some(on_progress) = &options.on_progress
+            && let Err(err) = on_progress.call::<()>((downloaded, total))
+        {
+            return wrap_err!("{}: on_progress callback errored: {}", function_name, err);
+        }
+        Ok(())
+    };
+
+    let mut reader = response.into_body().into_reader();
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut downloaded = offset;
+    let mut last_progress = Instant::now();
+    report_progress(downloaded)?;
+    loop {
+        let bytes_read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(bytes_read) => bytes_read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                // whatever we got so far stays in the part file so `resume = true` can pick up from there
+                return wrap_err!("{}: error downloading {} after {} bytes: {}", function_name, options.path.display(), downloaded, err);
+            }
+        };
+        if let Err(err) = file.write_all(&chunk[..bytes_read]) {
+            return wrap_err!("{}: unable to write to '{}' due to err: {}", function_name, part_path.display(), err);
+        }
+        if let Some(context) = context.as_mut() {
+            context.update(&chunk[..bytes_read]);
+        }
+        downloaded += bytes_read as u64;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            report_progress(downloaded)?;
+            last_progress = Instant::now();
+        }
+    }
+    if let Err(err) = file.flush().and_then(|_| file.sync_all()) {
+        return wrap_err!("{}: unable to finish writing '{}' due to err: {}", function_name, part_path.display(), err);
+    }
+    drop(file);
+    report_progress(downloaded)?;
+
+    if let Some(total) = total
+        && downloaded != total
+    {
+        return wrap_err!("{}: download of {} ended early ({} of {} bytes)", function_name, options.path.display(), downloaded, total);
+    }
+
+    if let Some(expected) = options.sha256
+        && let Some(context) = context
+    {
+        let actual = context.finish();
+        if actual.as_ref() != expected {
+            // a resumed download can't fix a corrupted file, so don't leave it around to be resumed
+            let _ = fs::remove_file(&part_path);
+            return wrap_err!(
+                "{}: sha256 of {} doesn't match; expected {}, got {}",
+                function_name, options.path.display(), hex::encode(expected), hex::encode(actual.as_ref())
+            );
+        }
+    }
+
+    if let Err(err) = fs::rename(&part_path, &options.path) {
+        return wrap_err!("{}: unable to move '{}' to '{}' due to err: {}", function_name, part_path.display(), options.path.display(), err);
+    }
+
+    ok_table(TableBuilder::create(luau)?
+        .with_value("path", options.path.to_string_lossy().to_string())?
+        .with_value("size", downloaded)?
+        .with_value("resumed", offset > 0)?
+        .build_readonly())
+}
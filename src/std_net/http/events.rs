@@ -0,0 +1,454 @@
+//! `http.events`: a client for Server-Sent Events (the `text/event-stream` format from the HTML spec's
+//! `EventSource`), which reconnects with `Last-Event-ID` when the connection drops, like browsers do.
+
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::cell::RefCell;
+use std::io::Read;
+use std::rc::Rc;
+use std::time::Duration;
+
+use ureq::http::Method;
+use ureq::http::header::CONTENT_TYPE;
+
+use crate::std_time::duration::TimeDuration;
+
+use super::HttpRequest;
+use super::HttpResponseResult;
+use super::http_request::RetryPolicy;
+
+/// How much we read off the connection at a time
+const CHUNK_SIZE: usize = 8 * 1024;
+/// How long we wait before reconnecting until the server tells us otherwise with `retry:`;
+/// browsers use about the same
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(3);
+/// utf-8's, which the spec says to skip if the stream starts with one
+const BYTE_ORDER_MARK: &[u8] = b"\xEF\xBB\xBF";
+
+struct ServerSentEvent {
+    event: String,
+    data: String,
+    id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl ServerSentEvent {
+    fn into_table(self, luau: &Lua) -> LuaResult<LuaTable> {
+        let mut event = TableBuilder::create(luau)?
+            .with_value("event", self.event)?
+            .with_value("data", self.data)?;
+        if let Some(id) = self.id {
+            event = event.with_value("id", id)?;
+        }
+        if let Some(retry) = self.retry {
+            event = event.with_value("retry", retry)?;
+        }
+        event.build_readonly()
+    }
+}
+
+/// Splits the stream into lines and lines into events, following the HTML spec's
+/// "interpreting an event stream" rules
+#[derive(Default)]
+struct EventParser {
+    /// bytes read off the connection that don't make up a whole line yet
+    pending: Vec<u8>,
+    /// the last chunk ended in a `\r`, so a `\n` at the start of the next one belongs to the same line break
+    skip_newline: bool,
+    /// the stream's first line hasn't been read yet, so it might start with a byte order mark
+    at_start: bool,
+    event_type: String,
+    data: String,
+    /// `data` doesn't tell us whether there were any `data:` lines, since they can be empty
+    has_data: bool,
+    /// the current event's `retry:`, so it shows up on the event
+    retry: Option<u64>,
+    /// the latest `retry:` the stream's sent, whether or not an event came with it; the spec applies it
+    /// as soon as it's parsed, so it's kept apart from (and outlives) the event being built
+    reconnect_delay: Option<Duration>,
+    /// kept across events (and reconnections) until the server sends a new one
+    last_event_id: String,
+}
+
+impl EventParser {
+    fn new(last_event_id: String) -> Self {
+        Self {
+            at_start: true,
+            last_event_id,
+            ..Default::default()
+        }
+    }
+
+    fn feed(&mut self, mut bytes: &[u8]) {
+        if self.skip_newline && let Some(rest) = bytes.strip_prefix(b"\n") {
+            bytes = rest;
+        }
+        self.skip_newline = false;
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// Takes the next whole line out of `pending`, if there is one
+    fn next_line(&mut self) -> Option<String> {
+        let end = self.pending.iter().position(|byte| *byte == b'\r' || *byte == b'\n')?;
+        let mut line_break = 1;
+        if self.pending[end] == b'\r' {
+            match self.pending.get(end + 1) {
+                Some(b'\n') => line_break = 2,
+                Some(_) => {},
+                // can't tell yet whether this is a `\r\n`
+                None => self.skip_newline = true,
+            }
+        }
+        let mut line: Vec<u8> = self.pending.drain(..end + line_break).collect();
+        line.truncate(end);
+
+        if self.at_start {
+            self.at_start = false;
+            if line.starts_with(BYTE_ORDER_MARK) {
+                line.drain(..BYTE_ORDER_MARK.len());
+            }
+        }
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    /// Processes whole lines until one finishes an event
+    fn next_event(&mut self) -> Option<ServerSentEvent> {
+        while let Some(line) = self.next_line() {
+            if line.is_empty() {
+                if let Some(event) = self.dispatch() {
+                    return Some(event);
+                }
+                continue;
+            }
+            if line.starts_with(':') {
+                continue; // comment, usually a keepalive
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line.as_str(), ""),
+            };
+            match field {
+                "event" => self.event_type = value.to_string(),
+                "data" => {
+                    self.data.push_str(value);
+                    self.data.push('\n');
+                    self.has_data = true;
+                },
+                "id" if !value.contains('\0') => self.last_event_id = value.to_string(),
+                "retry" if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+                    self.retry = value.parse().ok();
+                    if let Some(retry) = self.retry {
+                        self.reconnect_delay = Some(Duration::from_millis(retry));
+                    }
+                },
+                _ => {}, // unknown fields are ignored
+            }
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<ServerSentEvent> {
+        let event_type = std::mem::take(&mut self.event_type);
+        let mut data = std::mem::take(&mut self.data);
+        let retry = self.retry.take();
+        if !std::mem::take(&mut self.has_data) {
+            return None;
+        }
+        data.pop(); // the last data line's '\n'
+        Some(ServerSentEvent {
+            event: if event_type.is_empty() { String::from("message") } else { event_type },
+            data,
+            id: if self.last_event_id.is_empty() { None } else { Some(self.last_event_id.clone()) },
+            retry,
+        })
+    }
+
+    /// The connection dropped, so whatever event we were in the middle of never finished
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.skip_newline = false;
+        self.at_start = true;
+        self.event_type.clear();
+        self.data.clear();
+        self.has_data = false;
+        self.retry = None;
+    }
+}
+
+enum Connected {
+    Yes(ureq::BodyReader<'static>),
+    /// `204 No Content`, the server's way of telling us to stop reconnecting
+    Stop,
+}
+
+/// The `EventStream` returned by `http.events`
+struct EventStream {
+    /// sent again (with `Last-Event-ID`) every time we reconnect
+    request: HttpRequest,
+    /// `None` between connections, and once the stream's over
+    reader: Option<ureq::BodyReader<'static>>,
+    parser: EventParser,
+    reconnect: bool,
+    reconnect_delay: Duration,
+    max_reconnects: Option<u32>,
+    /// reconnection attempts since the last event; reset whenever one comes in
+    failed_reconnects: u32,
+    /// `close()` was called or the server said to stop
+    done: bool,
+}
+
+impl EventStream {
+    fn connect(&self, function_name: &'static str) -> LuaResult<Result<Connected, String>> {
+        let mut request = self.request.clone();
+        if !self.parser.last_event_id.is_empty() {
+            request.set_header("Last-Event-ID", self.parser.last_event_id.clone());
+        }
+
+        let response = match request.try_send(function_name)? {
+            HttpResponseResult::Ok(response) => response.into_inner(),
+            failed => return Ok(Err(RetryPolicy::describe(&failed))),
+        };
+
+        let status = response.status();
+        if status.as_u16() == 204 {
+            return Ok(Ok(Connected::Stop));
+        }
+        if !status.is_success() {
+            return wrap_err!(
+                "{}: server responded with {} {} instead of an event stream",
+                function_name, status.as_u16(), status.canonical_reason().unwrap_or_default()
+            );
+        }
+        let content_type = response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if !content_type.trim_start().to_ascii_lowercase().starts_with("text/event-stream") {
+            return wrap_err!("{}: expected the server to respond with Content-Type text/event-stream, got '{}'", function_name, content_type);
+        }
+
+        Ok(Ok(Connected::Yes(response.into_body().into_reader())))
+    }
+
+    fn next(&mut self, luau: &Lua, function_name: &'static str) -> LuaValueResult {
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            if let Some(event) = self.parser.next_event() {
+                self.failed_reconnects = 0;
+                return ok_table(event.into_table(luau));
+            }
+            if self.done {
+                return Ok(LuaNil);
+            }
+
+            let Some(reader) = self.reader.as_mut() else {
+                if !self.reconnect {
+                    self.done = true;
+                    return Ok(LuaNil);
+                }
+                if let Some(reconnect_delay) = self.parser.reconnect_delay {
+                    self.reconnect_delay = reconnect_delay;
+                }
+                std::thread::sleep(self.reconnect_delay);
+                let connected = self.connect(function_name);
+                // error statuses mean the server doesn't want us back, so we don't keep trying
+                if connected.is_err() {
+                    self.done = true;
+                }
+                match connected? {
+                    Ok(Connected::Yes(reader)) => self.reader = Some(reader),
+                    Ok(Connected::Stop) => self.done = true,
+                    Err(err) => {
+                        self.failed_reconnects += 1;
+                        if let Some(max_reconnects) = self.max_reconnects
+                            && self.failed_reconnects >= max_reconnects
+                        {
+                            self.done = true;
+                            return wrap_err!("{}: lost connection to the event stream and couldn't reconnect after {} attempts; last error: {}", function_name, self.failed_reconnects, err);
+                        }
+                    }
+                }
+                continue;
+            };
+
+            match reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.reader = None;
+                    self.parser.reset();
+                },
+                Ok(bytes_read) => self.parser.feed(&chunk[..bytes_read]),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {},
+                Err(err) => {
+                    self.reader = None;
+                    self.parser.reset();
+                    if !self.reconnect {
+                        self.done = true;
+                        return wrap_err!("{}: error reading event stream: {}", function_name, err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn create_handle(self, luau: &Lua) -> LuaResult<LuaTable> {
+        let stream_cell = Rc::new(RefCell::new(self));
+        TableBuilder::create(luau)?
+            .with_function_and_signature("next", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, mut multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "EventStream:next()";
+                    pop_self(&mut multivalue, function_name)?;
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.next(luau, function_name),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+                    }
+                }
+            }, signatures::STD_NET_HTTP_EVENT_STREAM_NEXT)?
+            .with_function_and_signature("close", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | _luau: &Lua, mut multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "EventStream:close()";
+                    pop_self(&mut multivalue, function_name)?;
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => {
+                            // dropping the reader mid-body closes the connection
+                            stream.reader = None;
+                            stream.parser.reset();
+                            stream.done = true;
+                            Ok(())
+                        },
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+                    }
+                }
+            }, signatures::STD_NET_HTTP_EVENT_STREAM_CLOSE)?
+            .with_metatable(TableBuilder::create(luau)?
+                .with_function_and_signature("__iter", {
+                    let stream_cell = Rc::clone(&stream_cell);
+                    move | luau: &Lua, _multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                        let stream_cell = Rc::clone(&stream_cell);
+                        luau.create_function(move | luau: &Lua, _value: LuaMultiValue | -> LuaValueResult {
+                            let function_name = "EventStream:__iter() iterator function";
+                            match stream_cell.try_borrow_mut() {
+                                Ok(ref mut stream) => stream.next(luau, function_name),
+                                Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+                            }
+                        })
+                    }
+                }, c"EventStream:__iter() -> () -> ServerSentEvent")?
+                .build_readonly()?
+            )?
+            .build_readonly()
+    }
+}
+
+fn get_reconnect_delay(value: LuaValue, function_name: &'static str) -> LuaResult<Option<Duration>> {
+    match value {
+        LuaValue::UserData(ud) if let Ok(duration) = ud.borrow::<TimeDuration>() => {
+            let duration = duration.inner;
+            if duration.is_negative() {
+                return wrap_err!("{}: EventStreamOptions.reconnect_delay can't be negative, got: {:#?}", function_name, duration);
+            }
+            Ok(Some(duration.unsigned_abs()))
+        },
+        LuaValue::Number(_) | LuaValue::Integer(_) => {
+            wrap_err!("{}: EventStreamOptions.reconnect_delay should be a Duration (from @std/time), not a regular number", function_name)
+        },
+        LuaNil => Ok(None),
+        other => {
+            wrap_err!("{}: expected EventStreamOptions.reconnect_delay to be a Duration or nil, got: {:?}", function_name, other)
+        }
+    }
+}
+
+pub fn http_events(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+    let function_name = "http.events(url: string, options: EventStreamOptions?)";
+
+    let url = match multivalue.pop_front() {
+        Some(LuaValue::String(url)) => url,
+        Some(LuaNil) | None => {
+            return wrap_err!("{} called without required argument 'url' (expected string)", function_name);
+        },
+        Some(other) => {
+            return wrap_err!("{}: expected url to be a string, got: {:?}", function_name, other);
+        }
+    };
+
+    // the request options go through HttpRequest like any other request, so copy them next to the url
+    let config = luau.create_table()?;
+    let options = match multivalue.pop_front() {
+        Some(LuaValue::Table(options)) => {
+            for pair in options.pairs::<LuaValue, LuaValue>() {
+                let (key, value) = pair?;
+                config.raw_set(key, value)?;
+            }
+            Some(options)
+        },
+        Some(LuaNil) | None => None,
+        Some(other) => {
+            return wrap_err!("{}: expected options to be an EventStreamOptions table or nil, got: {:?}", function_name, other);
+        }
+    };
+    config.raw_set("url", url)?;
+
+    let (last_event_id, reconnect, reconnect_delay, max_reconnects) = match options {
+        Some(options) => {
+            let last_event_id = match options.raw_get("last_event_id")? {
+                LuaValue::String(id) => id.to_string_lossy(),
+                LuaNil => String::new(),
+                other => {
+                    return wrap_err!("{}: expected EventStreamOptions.last_event_id to be a string or nil, got: {:?}", function_name, other);
+                }
+            };
+            let reconnect = match options.raw_get("reconnect")? {
+                LuaValue::Boolean(reconnect) => reconnect,
+                LuaNil => true,
+                other => {
+                    return wrap_err!("{}: expected EventStreamOptions.reconnect to be a boolean or nil, got: {:?}", function_name, other);
+                }
+            };
+            let max_reconnects = match options.raw_get("max_reconnects")? {
+                LuaValue::Integer(i) => Some(int_to_u32(i, function_name, "max_reconnects")?),
+                LuaValue::Number(f) => Some(float_to_u32(f, function_name, "max_reconnects")?),
+                LuaNil => None,
+                other => {
+                    return wrap_err!("{}: expected EventStreamOptions.max_reconnects to be a number or nil, got: {:?}", function_name, other);
+                }
+            };
+            let reconnect_delay = get_reconnect_delay(options.raw_get("reconnect_delay")?, function_name)?;
+            (last_event_id, reconnect, reconnect_delay, max_reconnects)
+        },
+        None => (String::new(), true, None, None),
+    };
+
+    let mut request = HttpRequest::from_config(Method::GET, config, luau, function_name)?;
+    request.set_streams_body();
+    if !request.has_header("accept") {
+        request.set_header("Accept", String::from("text/event-stream"));
+    }
+    if !request.has_header("cache-control") {
+        request.set_header("Cache-Control", String::from("no-cache"));
+    }
+
+    let mut stream = EventStream {
+        request,
+        reader: None,
+        parser: EventParser::new(last_event_id),
+        reconnect,
+        reconnect_delay: reconnect_delay.unwrap_or(DEFAULT_RECONNECT_DELAY),
+        max_reconnects,
+        failed_reconnects: 0,
+        done: false,
+    };
+
+    // the first connection's made right away so bad urls and error statuses show up here instead of mid-loop
+    match stream.connect(function_name)? {
+        Ok(Connected::Yes(reader)) => stream.reader = Some(reader),
+        Ok(Connected::Stop) => stream.done = true,
+        Err(err) => {
+            return wrap_err!("{}: unable to connect to event stream due to err: {}", function_name, err);
+        }
+    }
+
+    ok_table(stream.create_handle(luau))
+}
@@ -4,6 +4,7 @@ use mluau::prelude::*;
 use ureq::Error as UreqError;
 use ureq::Timeout as UreqTimeout;
 use super::timeout_info::TimeoutInfo;
+use super::HttpResponseResult;
 
 /// Represents both HttpIoError and HttpTimeoutError tables
 pub struct HttpError {
@@ -61,6 +62,18 @@ impl HttpError {
             },
         }
     }
+    /// Keeps the last attempt's kind (and phase), but the reason lists every attempt
+    pub fn from_retried(attempts: Vec<String>, last: HttpResponseResult) -> Self {
+        let mut error = match last {
+            HttpResponseResult::Timeout(which, info) => Self::from_timeout(which, info),
+            HttpResponseResult::Err(err) => Self::from_error(err, None),
+            HttpResponseResult::Ok(_) | HttpResponseResult::Retried { .. } => {
+                unreachable!("successful responses and nested retries are never retried errors")
+            }
+        };
+        error.reason = format!("request failed after {} attempts: {}", attempts.len(), attempts.join("; "));
+        error
+    }
     pub fn into_table(self, luau: &Lua) -> LuaResult<LuaTable> {
         let mut builder = TableBuilder::create(luau)?
             .with_value("ok", false)?
@@ -8,9 +8,20 @@ use ureq::http::Method;
 mod sender;
 use sender::Sender;
 
+mod multipart;
+use multipart::Multipart;
+
+mod retry;
+pub(super) use retry::RetryPolicy;
+
 use super::TimeoutInfo;
 use super::HttpResponse;
 use super::HttpResponseResult;
+use super::client::HttpClient;
+use super::mock::{Intercepted, MockHandle};
+
+use crate::std_net::proxy::ProxySetting;
+use crate::std_net::tls_config::TlsOptions;
 
 use crate::std_fs::file_size::FileSize;
 
@@ -23,12 +34,29 @@ use ureq::Error as UreqError;
 /// This stupid little enum abstracts it so we know when to put json headers
 /// on json content (user passed a table to RequestOptions.body) and know to
 /// handle binary differently from text/plain.
+#[derive(Clone)]
 pub enum RequestBody {
     Json(String),
     Bytes(Vec<u8>),
     Text(String),
+    /// `RequestOptions.form`, already urlencoded
+    Form(String),
+    Multipart(Multipart),
+}
+
+impl RequestBody {
+    /// What gets sent, for `@std/net/http/mock` to match and record; multipart bodies are streamed
+    /// from their parts so they don't have one
+    fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Json(body) | Self::Text(body) | Self::Form(body) => Some(body.as_bytes()),
+            Self::Bytes(bytes) => Some(bytes),
+            Self::Multipart(_) => None,
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct HttpRequest {
     method: Method,
     url: String,
@@ -38,6 +66,18 @@ pub struct HttpRequest {
     timeout: Option<TimeoutInfo>,
     max_body_size: Option<u64>,
     max_redirects: Option<u32>,
+    /// `RequestOptions.stream`: hand the body to Luau as an `HttpBodyStream` instead of reading it all up front
+    stream: bool,
+    /// the caller reads the body itself as it arrives (`http.download`, `http.events`), so it can't be buffered up front
+    streams_body: bool,
+    /// the `http.client` session's agent, so connections and cookies are shared between its requests;
+    /// one-off requests get a fresh agent of their own
+    agent: Option<ureq::Agent>,
+    retry: Option<RetryPolicy>,
+    tls: Option<TlsOptions>,
+    proxy: Option<ProxySetting>,
+    /// the mocks of the VM that made this request, if it's required `@std/net/http/mock`
+    mock: Option<MockHandle>,
 }
 
 impl HttpRequest {
@@ -100,6 +140,37 @@ impl HttpRequest {
             }
         };
 
+        let form = match config.raw_get("form")? {
+            LuaValue::Table(t) => {
+                let encoded: Vec<String> = Self::map_to_vec(t, "form", function_name)?
+                    .into_iter()
+                    .map(|(key, value)| format!("{}={}", urlencoding::encode(&key), urlencoding::encode(&value)))
+                    .collect();
+                Some(RequestBody::Form(encoded.join("&")))
+            },
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected RequestOptions.form to be {{ [string]: string }} or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let multipart = match config.raw_get("multipart")? {
+            LuaValue::Table(t) => Some(RequestBody::Multipart(Multipart::from_table(t, function_name)?)),
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected RequestOptions.multipart to be an array of MultipartFields or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let body = match (body, form, multipart) {
+            (body, None, None) => body,
+            (None, Some(form), None) => Some(form),
+            (None, None, Some(multipart)) => Some(multipart),
+            _ => {
+                return wrap_err!("{}: RequestOptions can only have one of body, form, or multipart", function_name);
+            }
+        };
+
         let max_body_size = match config.raw_get("max_body_size")? {
             LuaValue::UserData(ud) if let Ok(file_size) = ud.borrow::<FileSize>() => {
                 Some(file_size.as_bytes())
@@ -125,6 +196,19 @@ impl HttpRequest {
             }
         };
 
+        let stream = match config.raw_get("stream")? {
+            LuaValue::Boolean(stream) => stream,
+            LuaNil => false,
+            other => {
+                return wrap_err!("{}: expected config.stream to be a boolean or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let retry = RetryPolicy::from_config(&config, function_name)?;
+
+        let tls = TlsOptions::from_value(config.raw_get("tls")?, "RequestOptions.tls", function_name)?;
+        let proxy = ProxySetting::from_value(config.raw_get("proxy")?, "RequestOptions.proxy", function_name)?;
+
         let timeout = TimeoutInfo::from_config(config, function_name)?;
 
         Ok(Self {
@@ -135,12 +219,102 @@ impl HttpRequest {
             body,
             timeout,
             max_body_size,
-            max_redirects
+            max_redirects,
+            stream,
+            streams_body: false,
+            agent: None,
+            retry,
+            tls,
+            proxy,
+            mock: MockHandle::of(luau),
         })
     }
 
+    /// Sends this request through an `http.client` session, filling in whatever it didn't set itself
+    /// from the session's defaults (per-request headers, params, and timeout win).
+    pub fn with_client(mut self, client: &HttpClient, function_name: &'static str) -> LuaResult<Self> {
+        if let Some(base_url) = &client.base_url
+            && !self.url.contains("://")
+        {
+            self.url = if self.url.is_empty() {
+                base_url.clone()
+            } else {
+                format!("{}/{}", base_url.trim_end_matches('/'), self.url.trim_start_matches('/'))
+            };
+            if let Err(err) = self.url.parse::<ureq::http::Uri>() {
+                return wrap_err!("{}: invalid url {:?} (joined with the client's base_url): {}", function_name, self.url, err);
+            }
+        }
+
+        for (key, value) in &client.headers {
+            if !self.has_header(key) {
+                self.headers.get_or_insert_with(Vec::new).push((key.clone(), value.clone()));
+            }
+        }
+
+        if !client.params.is_empty() {
+            let params = self.params.get_or_insert_with(Vec::new);
+            for (key, value) in &client.params {
+                if !params.iter().any(|(existing, _)| existing == key) {
+                    params.push((key.clone(), value.clone()));
+                }
+            }
+        }
+
+        if self.timeout.is_none() {
+            self.timeout = client.timeout.clone();
+        }
+
+        // the session's pooled connections were set up with the client's tls settings, and ureq would happily
+        // reuse one of them for a request that asked for different ones
+        if self.tls.is_some() {
+            return wrap_err!("{}: RequestOptions.tls can't be set on requests sent through an http.client; set tls in the client's options instead", function_name);
+        }
+        self.tls = client.tls.clone();
+
+        if self.proxy.is_none() {
+            self.proxy = client.proxy.clone();
+        }
+
+        self.agent = Some(client.agent.clone());
+        Ok(self)
+    }
+
     /// Send the request without throwing errors in the common expected cases of io/network errors.
-    pub fn try_send(self, function_name: &'static str) -> LuaResult<HttpResponseResult>{
+    /// 
+    /// With `RequestOptions.retry`, retryable failures are retried (after a backoff) until one attempt works or we're
+    /// out of attempts; if the last attempt still failed, the result is `HttpResponseResult::Retried` with every attempt's error.
+    pub fn try_send(self, function_name: &'static str) -> LuaResult<HttpResponseResult> {
+        let Some(retry) = self.retry.clone() else {
+            return self.try_send_once(function_name);
+        };
+
+        let mut attempts = Vec::new();
+        let mut attempt = 1;
+        loop {
+            let result = self.clone().try_send_once(function_name)?;
+            if !retry.should_retry(&result) || attempt >= retry.attempts {
+                return Ok(match result {
+                    // out of attempts, but a response's still a response
+                    HttpResponseResult::Ok(_) => result,
+                    _ if attempts.is_empty() => result,
+                    last => {
+                        attempts.push(format!("attempt {}: {}", attempt, RetryPolicy::describe(&last)));
+                        HttpResponseResult::Retried { attempts, last: Box::new(last) }
+                    }
+                });
+            }
+
+            let delay = retry.delay(attempt, &result);
+            attempts.push(format!("attempt {}: {}", attempt, RetryPolicy::describe(&result)));
+            // lets the connection go before we sleep
+            drop(result);
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    fn try_send_once(self, function_name: &'static str) -> LuaResult<HttpResponseResult> {
         let Self { 
             method,
             url,
@@ -149,16 +323,60 @@ impl HttpRequest {
             body,
             timeout,
             max_body_size,
-            max_redirects
+            max_redirects,
+            stream,
+            streams_body,
+            agent,
+            retry: _,
+            tls,
+            proxy,
+            mock,
         } = self;
 
-        let result = Sender::from_http_method(method, url, function_name)?
-            .configure(timeout.clone(), max_redirects)
+        // what the request looks like on the wire, so mocks can match against the query string too
+        let full_url = match &params {
+            Some(params) if !params.is_empty() => {
+                let query: Vec<String> = params.iter()
+                    .map(|(key, value)| format!("{}={}", urlencoding::encode(key), urlencoding::encode(value)))
+                    .collect();
+                let separator = if url.contains('?') { '&' } else { '?' };
+                format!("{}{}{}", url, separator, query.join("&"))
+            },
+            _ => url.clone(),
+        };
+        let request_body = body.as_ref().and_then(RequestBody::as_bytes).map(<[u8]>::to_vec);
+
+        // recording reads the whole body before handing it back, which streamed bodies (and never-ending event streams) can't wait for
+        let recordable = !stream && !streams_body;
+        let intercepted = match &mock {
+            Some(mock) => mock.intercept(&method, &full_url, headers.as_deref().unwrap_or_default(), request_body.as_deref(), recordable),
+            None => Intercepted::Send,
+        };
+        let recording = match intercepted {
+            Intercepted::Send => None,
+            Intercepted::Record => mock,
+            Intercepted::Respond(response) => {
+                return Ok(HttpResponseResult::Ok(HttpResponse::new(response, max_body_size, stream)));
+            },
+            Intercepted::Unmatched(message) => {
+                return wrap_err!("{}: {}", function_name, message);
+            }
+        };
+
+        let agent = agent.unwrap_or_else(ureq::Agent::new_with_defaults);
+        let result = Sender::from_http_method(method.clone(), url, &agent, function_name)?
+            .configure(timeout.clone(), max_redirects, tls.as_ref(), proxy)
             .send(headers, params, body);
 
         match result {
+            Ok(response) if let Some(mock) = &recording => {
+                match mock.record(&method, &full_url, request_body.as_deref(), response) {
+                    Ok(response) => Ok(HttpResponseResult::Ok(HttpResponse::new(response, max_body_size, stream))),
+                    Err(err) => wrap_err!("{}: {}", function_name, err),
+                }
+            },
             Ok(response) => {
-                Ok(HttpResponseResult::Ok(HttpResponse::new(response, max_body_size)))
+                Ok(HttpResponseResult::Ok(HttpResponse::new(response, max_body_size, stream)))
             },
             Err(UreqError::Timeout(which)) => {
                 Ok(HttpResponseResult::Timeout(which, timeout))
@@ -173,16 +391,21 @@ impl HttpRequest {
     /// function for try_send that handles most of the cases; if you want to match against the actual 
     /// ureq errors directly, use try_send
     pub fn send(self, function_name: &'static str) -> LuaResult<HttpResponse> {
+        let mut response = self.send_without_body(function_name)?;
+        if let Err(err) = response.read_body_into_self() {
+            return wrap_err!("{}: cannot create response due to error reading response body: {}", function_name, err);
+        }
+        Ok(response)
+    }
+
+    /// Like `send`, but returns as soon as the response headers arrive, leaving the body unread
+    /// for callers that stream it somewhere themselves (like `http.download`).
+    pub fn send_without_body(self, function_name: &'static str) -> LuaResult<HttpResponse> {
         // all the luau errors (api usage errors) should be propagated upwards
         // whereas all the ureq errors we can now match against
 
         match self.try_send(function_name)? {
-            HttpResponseResult::Ok(mut response) => {
-                if let Err(err) = response.read_body_into_self() {
-                    return wrap_err!("{}: cannot create response due to error reading response body: {}", function_name, err);
-                }
-                Ok(response)
-            },
+            HttpResponseResult::Ok(response) => Ok(response),
             HttpResponseResult::Timeout(which, info) => {
                 let error_message = info
                     .map(|t| t.describe_elapsed(which))
@@ -200,10 +423,31 @@ impl HttpRequest {
             },
             HttpResponseResult::Err(err) => {
                 wrap_err!("{}: error sending request: {}", function_name, err)
+            },
+            HttpResponseResult::Retried { attempts, .. } => {
+                wrap_err!("{}: request failed after {} attempts:\n  {}", function_name, attempts.len(), attempts.join("\n  "))
             }
         }
     }
 
+    pub fn has_header(&self, key: &str) -> bool {
+        self.headers.as_ref().is_some_and(|headers| {
+            headers.iter().any(|(existing, _)| existing.eq_ignore_ascii_case(key))
+        })
+    }
+
+    /// Marks this request's body as read by the caller as it arrives instead of all at once
+    pub fn set_streams_body(&mut self) {
+        self.streams_body = true;
+    }
+
+    /// Sets header `key`, replacing any value the user passed in `RequestOptions.headers`
+    pub fn set_header(&mut self, key: &str, value: String) {
+        let headers = self.headers.get_or_insert_with(Vec::new);
+        headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(key));
+        headers.push((key.to_string(), value));
+    }
+
     fn check_valid_utf8(s: LuaString, what: &'static str, function_name: &'static str) -> LuaResult<String> {
         match s.to_str() {
             Ok(s) => Ok(s.to_string()),
@@ -213,7 +457,7 @@ impl HttpRequest {
         }
     }
 
-    fn map_to_vec(map: LuaTable, key: &'static str, function_name: &'static str) -> LuaResult<Vec<(String, String)>> {
+    pub(super) fn map_to_vec(map: LuaTable, key: &'static str, function_name: &'static str) -> LuaResult<Vec<(String, String)>> {
         let mut res = Vec::new();
         for pair in map.pairs::<LuaValue, LuaValue>() {
             match pair? {
@@ -0,0 +1,211 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::std_net::serve::mime;
+
+#[derive(Clone)]
+enum PartBody {
+    Bytes(Vec<u8>),
+    /// opened when the request's sent and streamed from disk, so big uploads don't have to fit in memory
+    File(PathBuf),
+}
+
+#[derive(Clone)]
+struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: PartBody,
+}
+
+/// A `RequestOptions.multipart` body (`multipart/form-data`, RFC 7578)
+#[derive(Clone)]
+pub struct Multipart {
+    boundary: String,
+    parts: Vec<Part>,
+}
+
+impl Multipart {
+    pub fn from_table(parts_table: LuaTable, function_name: &'static str) -> LuaResult<Self> {
+        let mut parts = Vec::new();
+        for (index, part) in parts_table.sequence_values::<LuaValue>().enumerate() {
+            let part = match part? {
+                LuaValue::Table(part) => part,
+                other => {
+                    return wrap_err!("{}: expected RequestOptions.multipart[{}] to be a MultipartField table, got: {:?}", function_name, index + 1, other);
+                }
+            };
+            parts.push(Self::part_from_table(part, index + 1, function_name)?);
+        }
+
+        if parts.is_empty() {
+            return wrap_err!("{}: RequestOptions.multipart should be an array of MultipartFields, got an empty table", function_name);
+        }
+
+        let mut random = [0u8; 16];
+        if SystemRandom::new().fill(&mut random).is_err() {
+            return wrap_err!("{}: unable to generate a multipart boundary", function_name);
+        }
+        let boundary = format!("----seal-boundary-{}", hex::encode(random));
+
+        Ok(Self { boundary, parts })
+    }
+
+    fn part_from_table(part: LuaTable, index: usize, function_name: &'static str) -> LuaResult<Part> {
+        let get_string = |key: &'static str| -> LuaResult<Option<String>> {
+            match part.raw_get(key)? {
+                LuaValue::String(s) => match s.to_str() {
+                    Ok(s) => Ok(Some(s.to_string())),
+                    Err(err) => {
+                        wrap_err!("{}: RequestOptions.multipart[{}].{} must be valid utf-8: {}", function_name, index, key, err)
+                    }
+                },
+                LuaNil => Ok(None),
+                other => {
+                    wrap_err!("{}: expected RequestOptions.multipart[{}].{} to be a string or nil, got: {:?}", function_name, index, key, other)
+                }
+            }
+        };
+
+        let Some(name) = get_string("name")? else {
+            return wrap_err!("{}: RequestOptions.multipart[{}] missing field name: expected string, got nil", function_name, index);
+        };
+        let mut filename = get_string("filename")?;
+        let mut content_type = get_string("content_type")?;
+
+        let body = match (part.raw_get("body")?, get_string("file")?) {
+            (LuaValue::String(body), None) => PartBody::Bytes(body.as_bytes().to_vec()),
+            (LuaValue::Buffer(body), None) => {
+                if content_type.is_none() {
+                    content_type = Some(String::from("application/octet-stream"));
+                }
+                PartBody::Bytes(body.to_vec())
+            },
+            (LuaNil, Some(path)) => {
+                let path = PathBuf::from(path);
+                match path.metadata() {
+                    Ok(metadata) if metadata.is_file() => {},
+                    Ok(_) => {
+                        return wrap_err!("{}: RequestOptions.multipart[{}].file '{}' isn't a file", function_name, index, path.display());
+                    },
+                    Err(err) => {
+                        return wrap_err!("{}: unable to read RequestOptions.multipart[{}].file '{}' due to err: {}", function_name, index, path.display(), err);
+                    }
+                }
+                if filename.is_none() {
+                    filename = path.file_name().map(|name| name.to_string_lossy().to_string());
+                }
+                if content_type.is_none() {
+                    content_type = Some(mime::from_path(&path).to_string());
+                }
+                PartBody::File(path)
+            },
+            (LuaNil, None) => {
+                return wrap_err!("{}: RequestOptions.multipart[{}] needs either a body (string or buffer) or a file (path)", function_name, index);
+            },
+            (_, Some(_)) => {
+                return wrap_err!("{}: RequestOptions.multipart[{}] can have either a body or a file, not both", function_name, index);
+            },
+            (other, None) => {
+                return wrap_err!("{}: expected RequestOptions.multipart[{}].body to be a string or buffer, got: {:?}", function_name, index, other);
+            }
+        };
+
+        Ok(Part { name, filename, content_type, body })
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    fn part_head(&self, part: &Part) -> Vec<u8> {
+        let mut head = format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"", self.boundary, escape_quoted(&part.name));
+        if let Some(filename) = &part.filename {
+            head.push_str(&format!("; filename=\"{}\"", escape_quoted(filename)));
+        }
+        head.push_str("\r\n");
+        if let Some(content_type) = &part.content_type {
+            head.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+        head.push_str("\r\n");
+        head.into_bytes()
+    }
+
+    /// Opens any file parts and returns the whole body as one reader, along with its length
+    /// (so it's sent with a Content-Length instead of chunked, which some servers don't accept for uploads)
+    pub fn into_reader(self) -> io::Result<(MultipartReader, u64)> {
+        let mut segments = Vec::new();
+        let mut len = 0;
+        for part in &self.parts {
+            let head = self.part_head(part);
+            len += head.len() as u64;
+            segments.push(Segment::Bytes(Cursor::new(head)));
+            match &part.body {
+                PartBody::Bytes(bytes) => {
+                    len += bytes.len() as u64;
+                    segments.push(Segment::Bytes(Cursor::new(bytes.clone())));
+                },
+                PartBody::File(path) => {
+                    let (file, file_len) = open_part_file(path)?;
+                    len += file_len;
+                    segments.push(Segment::File(file));
+                }
+            }
+            segments.push(Segment::Bytes(Cursor::new(b"\r\n".to_vec())));
+            len += 2;
+        }
+        let closing = format!("--{}--\r\n", self.boundary).into_bytes();
+        len += closing.len() as u64;
+        segments.push(Segment::Bytes(Cursor::new(closing)));
+
+        Ok((MultipartReader { segments, current: 0 }, len))
+    }
+}
+
+fn open_part_file(path: &Path) -> io::Result<(File, u64)> {
+    let with_context = |err: io::Error| io::Error::new(err.kind(), format!("unable to read multipart file '{}': {}", path.display(), err));
+    let file = File::open(path).map_err(with_context)?;
+    let len = file.metadata().map_err(with_context)?.len();
+    Ok((file, len))
+}
+
+/// Quotes and newlines would end the header value early
+fn escape_quoted(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+enum Segment {
+    Bytes(Cursor<Vec<u8>>),
+    File(File),
+}
+
+/// Reads each part's headers, body, and the boundaries between them in order
+pub struct MultipartReader {
+    segments: Vec<Segment>,
+    current: usize,
+}
+
+impl Read for MultipartReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(segment) = self.segments.get_mut(self.current) {
+            let bytes_read = match segment {
+                Segment::Bytes(cursor) => cursor.read(buf)?,
+                Segment::File(file) => file.read(buf)?,
+            };
+            if bytes_read > 0 || buf.is_empty() {
+                return Ok(bytes_read);
+            }
+            self.current += 1;
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,159 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::time::Duration;
+
+use ureq::http::header::RETRY_AFTER;
+
+use crate::std_time::duration::TimeDuration;
+use super::super::HttpResponseResult;
+use super::UreqError;
+
+const DEFAULT_ATTEMPTS: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Statuses that usually mean "try again later" rather than "you did something wrong"
+const DEFAULT_STATUSES: [u16; 4] = [429, 502, 503, 504];
+
+/// `RequestOptions.retry`: what to retry, how many times, and how long to wait in between
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// total attempts, including the first one
+    pub attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    statuses: Vec<u16>,
+    timeouts: bool,
+    connection_errors: bool,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &LuaTable, function_name: &'static str) -> LuaResult<Option<Self>> {
+        let retry = match config.raw_get("retry")? {
+            LuaValue::Table(retry) => retry,
+            LuaNil => return Ok(None),
+            other => {
+                return wrap_err!("{}: expected RequestOptions.retry to be a RetryOptions table or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let attempts = match retry.raw_get("attempts")? {
+            LuaValue::Integer(i) => int_to_u32(i, function_name, "retry.attempts")?,
+            LuaValue::Number(f) => float_to_u32(f, function_name, "retry.attempts")?,
+            LuaNil => DEFAULT_ATTEMPTS,
+            other => {
+                return wrap_err!("{}: expected RequestOptions.retry.attempts to be a number or nil, got: {:?}", function_name, other);
+            }
+        };
+        if attempts == 0 {
+            return wrap_err!("{}: RequestOptions.retry.attempts counts the first attempt too, so it must be at least 1", function_name);
+        }
+
+        let backoff = Self::get_duration(&retry, "backoff", function_name)?.unwrap_or(DEFAULT_BACKOFF);
+        let max_backoff = Self::get_duration(&retry, "max_backoff", function_name)?.unwrap_or(DEFAULT_MAX_BACKOFF);
+
+        let (statuses, timeouts, connection_errors) = match retry.raw_get("on")? {
+            LuaValue::Table(on) => {
+                let mut statuses = Vec::new();
+                let mut timeouts = false;
+                let mut connection_errors = false;
+                for condition in on.sequence_values::<LuaValue>() {
+                    match condition? {
+                        LuaValue::Integer(i) => statuses.push(int_to_u16(i, function_name, "retry.on status code")?),
+                        LuaValue::Number(f) => statuses.push(float_to_u16(f, function_name, "retry.on status code")?),
+                        LuaValue::String(s) if s == "timeout" => timeouts = true,
+                        LuaValue::String(s) if s == "connection" => connection_errors = true,
+                        other => {
+                            return wrap_err!(
+                                "{}: expected RequestOptions.retry.on to contain status codes (numbers), \"timeout\", or \"connection\", got: {:?}",
+                                function_name, other
+                            );
+                        }
+                    }
+                }
+                (statuses, timeouts, connection_errors)
+            },
+            LuaNil => (DEFAULT_STATUSES.to_vec(), true, true),
+            other => {
+                return wrap_err!("{}: expected RequestOptions.retry.on to be an array of status codes, \"timeout\", and \"connection\", or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        Ok(Some(Self { attempts, backoff, max_backoff, statuses, timeouts, connection_errors }))
+    }
+
+    fn get_duration(retry: &LuaTable, key: &'static str, function_name: &'static str) -> LuaResult<Option<Duration>> {
+        match retry.raw_get(key)? {
+            LuaValue::UserData(ud) if let Ok(duration) = ud.borrow::<TimeDuration>() => {
+                let duration = duration.inner;
+                if duration.is_negative() {
+                    return wrap_err!("{}: RequestOptions.retry.{} can't be negative, got: {:#?}", function_name, key, duration);
+                }
+                Ok(Some(duration.unsigned_abs()))
+            },
+            LuaValue::Number(_) | LuaValue::Integer(_) => {
+                wrap_err!("{}: RequestOptions.retry.{} should be a Duration (from @std/time), not a regular number", function_name, key)
+            },
+            LuaNil => Ok(None),
+            other => {
+                wrap_err!("{}: expected RequestOptions.retry.{} to be a Duration or nil, got: {:?}", function_name, key, other)
+            }
+        }
+    }
+
+    pub fn should_retry(&self, result: &HttpResponseResult) -> bool {
+        match result {
+            HttpResponseResult::Ok(response) => self.statuses.contains(&response.inner().status().as_u16()),
+            HttpResponseResult::Timeout(..) | HttpResponseResult::Err(UreqError::Timeout(_)) => self.timeouts,
+            HttpResponseResult::Err(UreqError::Io(_) | UreqError::ConnectionFailed | UreqError::HostNotFound) => self.connection_errors,
+            HttpResponseResult::Err(_) | HttpResponseResult::Retried { .. } => false,
+        }
+    }
+
+    /// How long to wait before retry number `retry` (starting at 1): the server's `Retry-After` if it sent one,
+    /// otherwise `backoff` doubled for every retry so far; either way no longer than `max_backoff`
+    pub fn delay(&self, retry: u32, result: &HttpResponseResult) -> Duration {
+        let retry_after = match result {
+            HttpResponseResult::Ok(response) => response.inner()
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after),
+            _ => None,
+        };
+        let delay = retry_after.unwrap_or_else(|| {
+            self.backoff.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        });
+        delay.min(self.max_backoff)
+    }
+
+    /// One line of the "request failed after n attempts" error
+    pub fn describe(result: &HttpResponseResult) -> String {
+        match result {
+            HttpResponseResult::Ok(response) => {
+                let status = response.inner().status();
+                format!("{} {}", status.as_u16(), status.canonical_reason().unwrap_or_default())
+            },
+            HttpResponseResult::Timeout(which, info) => {
+                info.as_ref()
+                    .map(|t| t.describe_elapsed(*which))
+                    .unwrap_or_else(|| format!("{:?} timeout elapsed", which))
+            },
+            HttpResponseResult::Err(UreqError::Io(err)) => format!("network error: {}", err),
+            HttpResponseResult::Err(err) => err.to_string(),
+            HttpResponseResult::Retried { attempts, .. } => attempts.join("; "),
+        }
+    }
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date (RFC 9110 10.2.3)
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = jiff::civil::DateTime::strptime("%a, %d %b %Y %H:%M:%S GMT", value).ok()?;
+    let at = date.to_zoned(jiff::tz::TimeZone::UTC).ok()?.timestamp();
+    let seconds = at.as_second().saturating_sub(jiff::Timestamp::now().as_second());
+    Some(Duration::from_secs(u64::try_from(seconds).unwrap_or(0)))
+}
@@ -2,7 +2,7 @@ use crate::prelude::*;
 use mluau::prelude::*;
 
 use ureq::http::{HeaderValue, Method};
-use ureq::http::header::CONTENT_TYPE;
+use ureq::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
 
 use super::super::{
     TimeoutInfo,
@@ -11,23 +11,26 @@ use super::super::{
 use super::RequestBody;
 use super::UreqError;
 
+use crate::std_net::proxy::ProxySetting;
+use crate::std_net::tls_config::TlsOptions;
+
 /// Configuring the config builder applies to both WithBody and WithoutBody;
 /// and there's no syntactical difference between the branches
 /// so I replaced the duplicate logic with this macro.
 macro_rules! configure_config_builder {
-    ($builder:expr, $timeout:expr, $max_redirects:expr) => {
+    ($builder:expr, $timeout:expr, $max_redirects:expr, $tls:expr, $proxy:expr) => {
         {
             let mut configuring = $builder
                 .config()
                 .http_status_as_error(false)
-                .tls_config(
-                    ureq::tls::TlsConfig::builder()
-                        .provider(ureq::tls::TlsProvider::Rustls)
-                        // combines the OS trust store and/or a custom CA bundle depending on
-                        // SEAL_SYSTEM_CERTS/SSL_CERT_FILE; see std_net::tls_config
-                        .root_certs(crate::std_net::tls_config::ureq_root_certs())
-                        .build()
-                );
+                // combines the OS trust store and/or a custom CA bundle depending on
+                // SEAL_SYSTEM_CERTS/SSL_CERT_FILE, plus RequestOptions.tls; see std_net::tls_config
+                .tls_config(crate::std_net::tls_config::ureq_tls_config($tls));
+
+            // otherwise ureq uses HTTP_PROXY/HTTPS_PROXY/NO_PROXY
+            if let Some(proxy) = $proxy {
+                configuring = configuring.proxy(proxy.into_ureq());
+            }
 
             if let Some(max_redirects) = $max_redirects {
                 configuring = configuring.max_redirects(max_redirects);
@@ -84,21 +87,22 @@ impl Sender {
     pub(super) fn from_http_method(
         m: Method, 
         uri: String, 
+        agent: &ureq::Agent,
         function_name: &'static str
     ) -> LuaResult<Self> {
         let builder = match m {
             // should be without body
-            Method::GET => Self::WithoutBody(ureq::get(uri)),
-            Method::TRACE => Self::WithoutBody(ureq::trace(uri)),
-            Method::DELETE => Self::WithoutBody(ureq::delete(uri)),
-            Method::CONNECT => Self::WithoutBody(ureq::connect(uri)),
-            Method::HEAD => Self::WithoutBody(ureq::head(uri)),
-            Method::OPTIONS => Self::WithoutBody(ureq::options(uri)),
+            Method::GET => Self::WithoutBody(agent.get(uri)),
+            Method::TRACE => Self::WithoutBody(agent.trace(uri)),
+            Method::DELETE => Self::WithoutBody(agent.delete(uri)),
+            Method::CONNECT => Self::WithoutBody(agent.connect(uri)),
+            Method::HEAD => Self::WithoutBody(agent.head(uri)),
+            Method::OPTIONS => Self::WithoutBody(agent.options(uri)),
 
             // should have body
-            Method::POST => Self::WithBody(ureq::post(uri)),
-            Method::PATCH => Self::WithBody(ureq::patch(uri)),
-            Method::PUT => Self::WithBody(ureq::put(uri)),
+            Method::POST => Self::WithBody(agent.post(uri)),
+            Method::PATCH => Self::WithBody(agent.patch(uri)),
+            Method::PUT => Self::WithBody(agent.put(uri)),
 
             // idk what to do with these
             other => {
@@ -112,15 +116,17 @@ impl Sender {
     pub(super) fn configure(
         self, 
         timeout: Option<TimeoutInfo>,
-        max_redirects: Option<u32>
+        max_redirects: Option<u32>,
+        tls: Option<&TlsOptions>,
+        proxy: Option<ProxySetting>,
     ) -> Self {
         match self {
             Self::WithBody(builder) => {
-                let builder = configure_config_builder!(builder, timeout, max_redirects);
+                let builder = configure_config_builder!(builder, timeout, max_redirects, tls, proxy);
                 Self::WithBody(builder)
             },
             Self::WithoutBody(builder) => {
-                let builder = configure_config_builder!(builder, timeout, max_redirects);
+                let builder = configure_config_builder!(builder, timeout, max_redirects, tls, proxy);
                 Self::WithoutBody(builder)
             }
         }
@@ -185,6 +191,22 @@ impl SendWithContext for RequestBuilderWithBody {
             RequestBody::Bytes(bytes) => {
                 self.set_content_type_if_unset(HeaderValue::from_static("application/octet-stream"));
                 self.send(bytes)
+            },
+            RequestBody::Form(body) => {
+                self.set_content_type_if_unset(HeaderValue::from_static("application/x-www-form-urlencoded"));
+                self.send(body)
+            },
+            RequestBody::Multipart(multipart) => {
+                let content_type = multipart.content_type();
+                let (reader, len) = multipart.into_reader()?;
+                if let Some(heads) = self.headers_mut() {
+                    // always ours; a user-set multipart Content-Type wouldn't have our boundary in it
+                    if let Ok(content_type) = HeaderValue::from_str(&content_type) {
+                        heads.insert(CONTENT_TYPE, content_type);
+                    }
+                    heads.insert(CONTENT_LENGTH, HeaderValue::from(len));
+                }
+                self.send(ureq::SendBody::from_owned_reader(reader))
             }
         }
     }
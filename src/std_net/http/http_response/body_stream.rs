@@ -0,0 +1,249 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::cell::RefCell;
+use std::io::Read;
+use std::rc::Rc;
+
+/// How much we read off the connection at a time (and the default `count` for `HttpBodyStream:read`)
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The body of a `stream = true` response, read off the connection as it's consumed instead of
+/// all at once into `HttpResponse.body`, so it never has to fit in memory.
+///
+/// Mirrors the `ChildProcessStream` api (`read`, `read_to`, `lines`, `iter`), except there's no producer thread:
+/// reads block until the server sends more data (bounded by the request's `timeout`), which also means
+/// nothing gets truncated if you read slowly; the server just waits on us.
+pub struct BodyStream {
+    /// `None` once the body's been fully read (or the stream was closed)
+    reader: Option<ureq::BodyReader<'static>>,
+    /// bytes read off the connection that haven't been handed out yet (by `read_to`/`lines`)
+    pending: Vec<u8>,
+}
+
+impl BodyStream {
+    pub fn new(reader: ureq::BodyReader<'static>) -> Self {
+        Self {
+            reader: Some(reader),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Reads the next chunk off the connection into `pending`; returns `false` if the body's over.
+    fn fill_pending(&mut self, function_name: &'static str) -> LuaResult<bool> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(false);
+        };
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => {
+                    // drops the reader so the connection can go back to ureq's pool
+                    self.reader = None;
+                    return Ok(false);
+                },
+                Ok(bytes_read) => {
+                    self.pending.extend_from_slice(&chunk[..bytes_read]);
+                    return Ok(true);
+                },
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.reader = None;
+                    return wrap_err!("{}: error reading response body: {}", function_name, err);
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "HttpBodyStream:read(count: number?)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let count = match multivalue.pop_front() {
+            Some(LuaValue::Integer(i)) => int_to_usize(i, function_name, "count")?,
+            Some(LuaValue::Number(f)) => float_to_usize(f, function_name, "count")?,
+            Some(LuaNil) | None => CHUNK_SIZE,
+            Some(other) => {
+                return wrap_err!("{} expected count to be an integer number, got: {:?}", function_name, other);
+            }
+        };
+
+        if count == 0 {
+            return wrap_err!("{}: why do you want to read 0 bytes from the stream???", function_name);
+        }
+
+        if self.pending.is_empty() && !self.fill_pending(function_name)? {
+            return Ok(LuaNil);
+        }
+
+        let count = std::cmp::min(self.pending.len(), count);
+        let bytes_read: Vec<u8> = self.pending.drain(..count).collect();
+        ok_buffy(bytes_read, luau)
+    }
+
+    /// Reads until `search_term`, returning `None` if the body ended first (leaving what was read in `pending`,
+    /// unless `allow_partial`, in which case the rest of the body is returned instead).
+    fn read_until(&mut self, search_term: &[u8], inclusive: bool, allow_partial: bool, function_name: &'static str) -> LuaResult<Option<Vec<u8>>> {
+        // everything before this was already searched, so we only rescan the overlap with newly read bytes
+        let mut search_from = 0;
+        loop {
+            if !search_term.is_empty()
+                && let Some(position) = self.pending[search_from..]
+                    .windows(search_term.len())
+                    .position(|window| window == search_term)
+            {
+                let end = search_from + position + search_term.len();
+                let mut drained: Vec<u8> = self.pending.drain(..end).collect();
+                if !inclusive {
+                    drained.truncate(drained.len() - search_term.len());
+                }
+                return Ok(Some(drained));
+            }
+
+            search_from = (self.pending.len() + 1).saturating_sub(search_term.len());
+            if !self.fill_pending(function_name)? {
+                // an empty search term means read to the end, like ChildProcessStream:read_to("")
+                if search_term.is_empty() || (allow_partial && !self.pending.is_empty()) {
+                    return Ok(Some(std::mem::take(&mut self.pending)));
+                }
+                return Ok(None);
+            }
+        }
+    }
+
+    fn read_to(&mut self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "HttpBodyStream:read_to(term: string, inclusive: boolean?, allow_partial: boolean?)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let search_term = match multivalue.pop_front() {
+            Some(LuaValue::String(t)) => t.as_bytes().to_vec(),
+            Some(LuaNil) | None => {
+                return wrap_err!("{} expected search term to be a string, got nothing or nil", function_name);
+            },
+            Some(other) => {
+                return wrap_err!("{} expected search term to be a string, got: {:?}", function_name, other);
+            }
+        };
+
+        let inclusive = match multivalue.pop_front() {
+            Some(LuaValue::Boolean(inclusive)) => inclusive,
+            Some(LuaNil) | None => false,
+            Some(other) => {
+                return wrap_err!("{} expected inclusive to be a boolean or nil (default false), got: {:?}", function_name, other);
+            }
+        };
+
+        let allow_partial = match multivalue.pop_front() {
+            Some(LuaValue::Boolean(partial)) => partial,
+            Some(LuaNil) | None => false,
+            Some(other) => {
+                return wrap_err!("{} expected allow_partial to be a boolean or nil (default false), got: {:?}", function_name, other);
+            }
+        };
+
+        match self.read_until(&search_term, inclusive, allow_partial, function_name)? {
+            Some(bytes) => ok_string(bytes, luau),
+            None => Ok(LuaNil),
+        }
+    }
+
+    fn next_line(&mut self, luau: &Lua) -> LuaValueResult {
+        let function_name = "HttpBodyStream:lines() iterator function";
+        match self.read_until(b"\n", false, true, function_name)? {
+            Some(mut line) => {
+                // users don't want the \r from \r\n line endings either
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                ok_string(line, luau)
+            },
+            None => Ok(LuaNil),
+        }
+    }
+
+    fn next_chunk(&mut self, luau: &Lua) -> LuaValueResult {
+        let function_name = "HttpBodyStream iterator function";
+        if self.pending.is_empty() && !self.fill_pending(function_name)? {
+            return Ok(LuaNil);
+        }
+        ok_buffy(std::mem::take(&mut self.pending), luau)
+    }
+
+    fn iter(stream_cell: &Rc<RefCell<Self>>, luau: &Lua, function_name: &'static str) -> LuaResult<LuaFunction> {
+        let stream_cell = Rc::clone(stream_cell);
+        luau.create_function(move |luau: &Lua, _value: LuaMultiValue| -> LuaValueResult {
+            match stream_cell.try_borrow_mut() {
+                Ok(ref mut stream) => stream.next_chunk(luau),
+                Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+            }
+        })
+    }
+
+    pub fn create_handle(stream_cell: Rc<RefCell<Self>>, luau: &Lua) -> LuaResult<LuaTable> {
+        TableBuilder::create(luau)?
+            .with_function_and_signature("read", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "HttpBodyStream:read(count: number?)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.read(luau, multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_HTTP_HTTP_BODY_STREAM_READ)?
+            .with_function_and_signature("read_to", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "HttpBodyStream:read_to(term: string, inclusive: boolean?, allow_partial: boolean?)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.read_to(luau, multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_HTTP_HTTP_BODY_STREAM_READ_TO)?
+            .with_function_and_signature("lines", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, _multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                    let stream_cell = Rc::clone(&stream_cell);
+                    luau.create_function(move |luau: &Lua, _value: LuaMultiValue| -> LuaValueResult {
+                        let function_name = "HttpBodyStream:lines() iterator function";
+                        match stream_cell.try_borrow_mut() {
+                            Ok(ref mut stream) => stream.next_line(luau),
+                            Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+                        }
+                    })
+                }
+            }, signatures::STD_NET_HTTP_HTTP_BODY_STREAM_LINES)?
+            .with_function_and_signature("iter", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, _multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                    Self::iter(&stream_cell, luau, "HttpBodyStream:iter()")
+                }
+            }, signatures::STD_NET_HTTP_HTTP_BODY_STREAM_ITER)?
+            .with_function_and_signature("close", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | _luau: &Lua, _multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "HttpBodyStream:close()";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => {
+                            // dropping the reader mid-body closes the connection instead of reading the rest of it
+                            stream.reader = None;
+                            stream.pending.clear();
+                            Ok(())
+                        },
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+                    }
+                }
+            }, signatures::STD_NET_HTTP_HTTP_BODY_STREAM_CLOSE)?
+            .with_metatable(TableBuilder::create(luau)?
+                .with_function_and_signature("__iter", {
+                    let stream_cell = Rc::clone(&stream_cell);
+                    move | luau: &Lua, _multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                        Self::iter(&stream_cell, luau, "HttpBodyStream:__iter()")
+                    }
+                }, c"HttpBodyStream:__iter() -> () -> buffer")?
+                .build_readonly()?
+            )?
+            .build_readonly()
+    }
+}
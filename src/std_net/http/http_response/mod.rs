@@ -6,6 +6,12 @@ use crate::std_json;
 use super::ResponseWithBody;
 use ureq::Error as UreqError;
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod body_stream;
+use body_stream::BodyStream;
+
 enum IsJson {
     Yes,
     Maybe,
@@ -22,23 +28,31 @@ enum IsJson {
 /// We also store a late initialized body_buffer in here
 /// because we need able to error out early in `http.request` 
 /// without throwing a generic wrap_err! that can't be turned into an HttpError
+/// 
+/// When the request was sent with `stream = true`, the body's never buffered;
+/// `into_table` hands the body reader to Luau as `HttpResponse.stream` instead.
 pub struct HttpResponse {
     inner: ResponseWithBody,
     body_buffer: Option<Vec<u8>>,
-    max_body_size: Option<u64>
+    max_body_size: Option<u64>,
+    stream: bool,
 }
 
 // methods wrt. HttpResponse objects but not exposed to Luau
 impl HttpResponse {
-    pub fn new(response: ResponseWithBody, max_body_size: Option<u64>) -> Self {
+    pub fn new(response: ResponseWithBody, max_body_size: Option<u64>, stream: bool) -> Self {
         Self {
             inner: response,
             body_buffer: None,
             max_body_size,
+            stream,
         }
     }
 
     pub fn read_body_into_self(&mut self) -> Result<(), UreqError> {
+        if self.stream {
+            return Ok(()); // read as it's consumed in Luau
+        }
         let read_result = if let Some(limit) = self.max_body_size {
             self.inner.body_mut().with_config().limit(limit).read_to_vec()
         } else {
@@ -54,6 +68,16 @@ impl HttpResponse {
         Ok(())
     }
 
+    /// The underlying ureq response, for callers that read the body themselves
+    pub fn into_inner(self) -> ResponseWithBody {
+        self.inner
+    }
+
+    /// The response's status and headers (and unread body)
+    pub fn inner(&self) -> &ResponseWithBody {
+        &self.inner
+    }
+
     pub fn into_table(self, luau: &Lua, function_name: &'static str) -> LuaResult<LuaTable> {
         let response = self.inner;
 
@@ -76,13 +100,6 @@ impl HttpResponse {
             .with_value("reason", reason)?
             .build_readonly()?;
 
-        let body = match self.body_buffer {
-            Some(body) => body,
-            None => {
-                panic!("{}: you forgot to call read_body_into_self before calling this function", function_name);
-            }
-        };
-
         let charset = response.body().charset();
         let mime_type = response.body().mime_type();
 
@@ -91,12 +108,29 @@ impl HttpResponse {
             .with_value("charset", charset)?
             .build_readonly()?;
 
+        let (body, stream) = match self.body_buffer {
+            Some(body) => (body, None),
+            None if self.stream => {
+                // streamed bodies are unbounded unless max_body_size was explicitly set
+                let reader = match self.max_body_size {
+                    Some(limit) => response.into_body().into_with_config().limit(limit).reader(),
+                    None => response.into_body().into_reader(),
+                };
+                let stream_cell = Rc::new(RefCell::new(BodyStream::new(reader)));
+                (Vec::new(), Some(BodyStream::create_handle(stream_cell, luau)?))
+            },
+            None => {
+                panic!("{}: you forgot to call read_body_into_self before calling this function", function_name);
+            }
+        };
+
         TableBuilder::create(luau)?
             .with_value("kind", kind)?
             .with_value("ok", is_ok)?
             .with_value("headers", headers)?
             .with_value("status", status)?
             .with_value("body", luau.create_string(body)?)?
+            .with_value("stream", stream)?
             .with_value("content_type", content_type)?
             .with_function_and_signature("expect_json", HttpResponse::expect_json, signatures::STD_NET_HTTP_HTTP_RESPONSE_EXPECT_JSON)?
             .with_function_and_signature("try_json", HttpResponse::try_json, signatures::STD_NET_HTTP_HTTP_RESPONSE_TRY_JSON)?
@@ -140,6 +174,9 @@ impl HttpResponse {
     }
 
     fn get_body(self_table: &LuaTable, function_name: &'static str) -> LuaResult<String> {
+        if let LuaValue::Table(_) = self_table.raw_get("stream")? {
+            return wrap_err!("{}: this response's body is streamed (the request had stream = true); read it from response.stream instead", function_name);
+        }
         match self_table.raw_get("body")? {
             LuaValue::String(body) => {
                 match body.to_str() {
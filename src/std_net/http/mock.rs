@@ -0,0 +1,552 @@
+//! `@std/net/http/mock`: canned responses and recorded cassettes that `@std/net/http` requests are answered
+//! from instead of the network, so scripts that make http requests can be tested offline.
+//!
+//! Every request (`http.get`/`post`/`request`, `http.client`, `http.download`, `http.events`) goes through
+//! `HttpRequest::try_send_once`, which asks its VM's `MockHandle::intercept` what to do before sending anything.
+
+use mluau::prelude::*;
+use crate::prelude::*;
+use crate::std_json;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use base64::Engine;
+use serde_json_lenient::{Map, Value};
+use ureq::http::Method;
+
+use super::ResponseWithBody;
+
+#[derive(Clone)]
+struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl MockResponse {
+    fn build(&self) -> Result<ResponseWithBody, ureq::http::Error> {
+        let mut builder = ureq::http::Response::builder().status(self.status);
+        let mut body = ureq::Body::builder();
+        for (key, value) in &self.headers {
+            builder = builder.header(key.as_str(), value.as_str());
+            // ureq normally fills these in from the real response's content-type
+            if key.eq_ignore_ascii_case("content-type") {
+                let mut parts = value.split(';');
+                if let Some(mime_type) = parts.next() {
+                    body = body.mime_type(mime_type.trim());
+                }
+                if let Some(charset) = parts.find_map(|part| part.trim().strip_prefix("charset=")) {
+                    body = body.charset(charset.trim_matches('"'));
+                }
+            }
+        }
+        builder.body(body.data(self.body.clone()))
+    }
+}
+
+/// A response registered with `mock.on`
+struct MockRoute {
+    /// `None` matches every method
+    method: Option<Method>,
+    url: String,
+    response: MockResponse,
+    /// how many more requests this route answers; `None` answers all of them
+    remaining: Option<u32>,
+}
+
+impl MockRoute {
+    fn matches(&self, method: &Method, url: &str) -> bool {
+        if self.remaining == Some(0) || self.method.as_ref().is_some_and(|expected| expected != method) {
+            return false;
+        }
+        // the query string only counts if the route has one
+        let url = if self.url.contains('?') { url } else { url.split('?').next().unwrap_or(url) };
+        match self.url.strip_suffix('*') {
+            Some(prefix) => url.starts_with(prefix),
+            None => url == self.url,
+        }
+    }
+}
+
+/// One request and its response in a cassette
+struct Interaction {
+    method: String,
+    url: String,
+    request_body: Option<Vec<u8>>,
+    response: MockResponse,
+    played: bool,
+}
+
+/// What `mock.requests()` returns: every request that was answered by a mock or recorded
+struct SeenRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+enum Mode {
+    /// requests go to the network like normal
+    #[default]
+    Off,
+    /// requests are answered by `mock.on` routes and `mock.replay` cassettes
+    Mock,
+    /// requests go to the network and are saved to the cassette at this path
+    Record(PathBuf),
+}
+
+#[derive(Default)]
+struct MockState {
+    mode: Mode,
+    routes: Vec<MockRoute>,
+    cassette: Vec<Interaction>,
+    /// in mock mode, send requests that don't match anything to the network instead of erroring
+    allow_network: bool,
+    requests: Vec<SeenRequest>,
+}
+
+/// A VM's mocks, kept in its app data so test files `seal test` runs side by side (each in its own VM)
+/// can't see each other's mocks. Requests hold a clone, so `http.events` reconnects still get mocked.
+#[derive(Clone, Default)]
+pub struct MockHandle(Arc<Mutex<MockState>>);
+
+impl MockHandle {
+    /// The mocks of a VM that's required `@std/net/http/mock`, if it has
+    pub fn of(luau: &Lua) -> Option<Self> {
+        luau.app_data_ref::<Self>().map(|handle| handle.clone())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The VM's mocks, setting them up the first time
+fn handle(luau: &Lua) -> MockHandle {
+    if let Some(handle) = MockHandle::of(luau) {
+        return handle;
+    }
+    let handle = MockHandle::default();
+    luau.set_app_data(handle.clone());
+    handle
+}
+
+/// What `HttpRequest::try_send_once` should do with a request
+pub enum Intercepted {
+    /// mocking's off (or the request's allowed through), so send it like normal
+    Send,
+    /// answer the request with this instead of sending it
+    Respond(ResponseWithBody),
+    /// send it, then hand the response to `record` so it ends up in the cassette
+    Record,
+    /// mocking's on but nothing matched; the error message to throw
+    Unmatched(String),
+}
+
+/// Cassettes play back in the order they were recorded; once every matching interaction's been played,
+/// the last one keeps answering (so polling the same url doesn't run out)
+fn play_from_cassette(cassette: &mut [Interaction], method: &Method, url: &str, body: Option<&[u8]>) -> Option<MockResponse> {
+    let matches = |interaction: &Interaction| {
+        interaction.method.eq_ignore_ascii_case(method.as_str())
+            && interaction.url == url
+            && (interaction.request_body.is_none() || interaction.request_body.as_deref() == body)
+    };
+    if let Some(interaction) = cassette.iter_mut().find(|interaction| !interaction.played && matches(interaction)) {
+        interaction.played = true;
+        return Some(interaction.response.clone());
+    }
+    cassette.iter().rev().find(|interaction| matches(interaction)).map(|interaction| interaction.response.clone())
+}
+
+impl MockHandle {
+    /// `recordable` is false for requests whose body is streamed to the caller; those are sent (but not saved) while recording
+    pub fn intercept(&self, method: &Method, url: &str, headers: &[(String, String)], body: Option<&[u8]>, recordable: bool) -> Intercepted {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        let seen = SeenRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers.to_vec(),
+            body: body.map(<[u8]>::to_vec),
+        };
+
+        match state.mode {
+            Mode::Off => Intercepted::Send,
+            Mode::Record(_) => {
+                state.requests.push(seen);
+                if recordable { Intercepted::Record } else { Intercepted::Send }
+            },
+            Mode::Mock => {
+                let response = if let Some(route) = state.routes.iter_mut().find(|route| route.matches(method, url)) {
+                    if let Some(remaining) = route.remaining.as_mut() {
+                        *remaining -= 1;
+                    }
+                    Some(route.response.clone())
+                } else {
+                    play_from_cassette(&mut state.cassette, method, url, body)
+                };
+
+                match response {
+                    Some(response) => {
+                        state.requests.push(seen);
+                        match response.build() {
+                            Ok(response) => Intercepted::Respond(response),
+                            Err(err) => Intercepted::Unmatched(format!("mocked response for {} {} is invalid: {}", method, url, err)),
+                        }
+                    },
+                    None if state.allow_network => Intercepted::Send,
+                    None => Intercepted::Unmatched(format!(
+                        "no mock matches {} {}; register one with mock.on or call mock.allow_network(true) to send unmatched requests",
+                        method, url
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Reads the live response's body so it can be saved to the cassette, then gives back an identical response
+    pub fn record(&self, method: &Method, url: &str, request_body: Option<&[u8]>, mut response: ResponseWithBody) -> Result<ResponseWithBody, String> {
+        let body = match response.body_mut().with_config().limit(u64::MAX).read_to_vec() {
+            Ok(body) => body,
+            Err(err) => return Err(format!("unable to read response body to record it: {}", err)),
+        };
+        // ureq's already decompressed and dechunked the body, so these would describe a body we don't have
+        let headers = response.headers()
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "content-encoding" | "content-length" | "transfer-encoding"))
+            .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let recorded = MockResponse { status: response.status().as_u16(), headers, body };
+
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        state.cassette.push(Interaction {
+            method: method.to_string(),
+            url: url.to_string(),
+            request_body: request_body.map(<[u8]>::to_vec),
+            response: recorded.clone(),
+            played: true,
+        });
+        if let Mode::Record(path) = &state.mode
+            && let Err(err) = fs::write(path, cassette_to_json(&state.cassette))
+        {
+            return Err(format!("unable to save cassette to '{}': {}", path.display(), err));
+        }
+
+        recorded.build().map_err(|err| err.to_string())
+    }
+}
+
+/// Bodies are saved as text when they're valid utf-8 so cassettes are easy to read and edit by hand
+fn body_to_json(map: &mut Map<String, Value>, body: &[u8]) {
+    match std::str::from_utf8(body) {
+        Ok(text) => map.insert(String::from("body"), Value::String(text.to_string())),
+        Err(_) => map.insert(String::from("body_base64"), Value::String(base64::engine::general_purpose::STANDARD.encode(body))),
+    };
+}
+
+fn body_from_json(map: &Map<String, Value>) -> Result<Option<Vec<u8>>, String> {
+    if let Some(encoded) = map.get("body_base64").and_then(Value::as_str) {
+        return match base64::engine::general_purpose::STANDARD.decode(encoded) {
+            Ok(body) => Ok(Some(body)),
+            Err(err) => Err(format!("invalid body_base64: {}", err)),
+        };
+    }
+    Ok(map.get("body").and_then(Value::as_str).map(|body| body.as_bytes().to_vec()))
+}
+
+fn cassette_to_json(cassette: &[Interaction]) -> String {
+    let interactions = cassette.iter().map(|interaction| {
+        let mut request = Map::new();
+        request.insert(String::from("method"), Value::String(interaction.method.clone()));
+        request.insert(String::from("url"), Value::String(interaction.url.clone()));
+        if let Some(body) = &interaction.request_body {
+            body_to_json(&mut request, body);
+        }
+
+        // repeated headers (like set-cookie) become arrays
+        let mut headers = Map::new();
+        for (key, value) in &interaction.response.headers {
+            match headers.get_mut(key) {
+                Some(Value::Array(values)) => values.push(Value::String(value.clone())),
+                Some(existing) => {
+                    let first = existing.take();
+                    *existing = Value::Array(vec![first, Value::String(value.clone())]);
+                },
+                None => {
+                    headers.insert(key.clone(), Value::String(value.clone()));
+                }
+            }
+        }
+        let mut response = Map::new();
+        response.insert(String::from("status"), Value::from(interaction.response.status));
+        response.insert(String::from("headers"), Value::Object(headers));
+        body_to_json(&mut response, &interaction.response.body);
+
+        let mut entry = Map::new();
+        entry.insert(String::from("request"), Value::Object(request));
+        entry.insert(String::from("response"), Value::Object(response));
+        Value::Object(entry)
+    }).collect();
+
+    let mut root = Map::new();
+    root.insert(String::from("interactions"), Value::Array(interactions));
+    serde_json_lenient::to_string_pretty(&Value::Object(root)).unwrap_or_default()
+}
+
+fn cassette_from_json(src: &str) -> Result<Vec<Interaction>, String> {
+    let root: Value = serde_json_lenient::from_str(src).map_err(|err| err.to_string())?;
+    let Some(interactions) = root.get("interactions").and_then(Value::as_array) else {
+        return Err(String::from("expected an object with an \"interactions\" array"));
+    };
+
+    let mut cassette = Vec::with_capacity(interactions.len());
+    for (index, interaction) in interactions.iter().enumerate() {
+        let invalid = |what: &str| format!("interaction {}: {}", index + 1, what);
+        let (Some(request), Some(response)) = (
+            interaction.get("request").and_then(Value::as_object),
+            interaction.get("response").and_then(Value::as_object),
+        ) else {
+            return Err(invalid("expected \"request\" and \"response\" objects"));
+        };
+
+        let (Some(method), Some(url)) = (
+            request.get("method").and_then(Value::as_str),
+            request.get("url").and_then(Value::as_str),
+        ) else {
+            return Err(invalid("request needs a \"method\" and \"url\""));
+        };
+        let Some(status) = response.get("status").and_then(Value::as_u64).and_then(|status| u16::try_from(status).ok()) else {
+            return Err(invalid("response needs a numeric \"status\""));
+        };
+
+        let mut headers = Vec::new();
+        if let Some(header_map) = response.get("headers").and_then(Value::as_object) {
+            for (key, value) in header_map {
+                match value {
+                    Value::String(value) => headers.push((key.clone(), value.clone())),
+                    Value::Array(values) => {
+                        headers.extend(values.iter().filter_map(Value::as_str).map(|value| (key.clone(), value.to_string())));
+                    },
+                    _ => return Err(invalid(&format!("header '{}' should be a string or an array of strings", key))),
+                }
+            }
+        }
+
+        cassette.push(Interaction {
+            method: method.to_string(),
+            url: url.to_string(),
+            request_body: body_from_json(request).map_err(|err| invalid(&err))?,
+            response: MockResponse {
+                status,
+                headers,
+                body: body_from_json(response).map_err(|err| invalid(&err))?.unwrap_or_default(),
+            },
+            played: false,
+        });
+    }
+    Ok(cassette)
+}
+
+fn pop_path(value: LuaValue, function_name: &'static str) -> LuaResult<PathBuf> {
+    match value {
+        LuaValue::String(path) => Ok(PathBuf::from(path.to_string_lossy())),
+        LuaNil => {
+            wrap_err!("{} called without required argument 'path' (expected string)", function_name)
+        },
+        other => {
+            wrap_err!("{}: expected path to be a string, got: {:?}", function_name, other)
+        }
+    }
+}
+
+fn mock_on(luau: &Lua, value: LuaValue) -> LuaEmptyResult {
+    let function_name = "mock.on(route: MockRoute)";
+    let route = match value {
+        LuaValue::Table(route) => route,
+        other => {
+            return wrap_err!("{}: expected route to be a MockRoute table ({{ url: string, method: HttpMethod?, status: number?, body: ..., ... }}), got: {:?}", function_name, other);
+        }
+    };
+
+    let url = match route.raw_get("url")? {
+        LuaValue::String(url) => url.to_string_lossy(),
+        other => {
+            return wrap_err!("{}: expected MockRoute.url to be a string (ending in * to match every url that starts with it), got: {:?}", function_name, other);
+        }
+    };
+
+    let method = match route.raw_get("method")? {
+        LuaValue::String(method) => match Method::from_bytes(&method.as_bytes()) {
+            Ok(method) => Some(method),
+            Err(err) => {
+                return wrap_err!("{}: MockRoute.method is an invalid http method: {}", function_name, err);
+            }
+        },
+        LuaNil => None,
+        other => {
+            return wrap_err!("{}: expected MockRoute.method to be an HttpMethod string or nil, got: {:?}", function_name, other);
+        }
+    };
+
+    let status = match route.raw_get("status")? {
+        LuaValue::Integer(i) => i,
+        LuaValue::Number(f) if f.fract() == 0.0 => f as i64,
+        LuaNil => 200,
+        other => {
+            return wrap_err!("{}: expected MockRoute.status to be a status code (number) or nil, got: {:?}", function_name, other);
+        }
+    };
+    let Ok(status) = u16::try_from(status) else {
+        return wrap_err!("{}: MockRoute.status {} isn't a valid status code", function_name, status);
+    };
+    if !(100..=999).contains(&status) {
+        return wrap_err!("{}: MockRoute.status {} isn't a valid status code", function_name, status);
+    }
+
+    let mut headers = Vec::new();
+    match route.raw_get("headers")? {
+        LuaValue::Table(header_map) => {
+            for pair in header_map.pairs::<LuaValue, LuaValue>() {
+                match pair? {
+                    (LuaValue::String(key), LuaValue::String(value)) => {
+                        headers.push((key.to_string_lossy(), value.to_string_lossy()));
+                    },
+                    (key, value) => {
+                        return wrap_err!("{}: MockRoute.headers contains invalid key/value types, expected [string] = string, got: [{:?}] = {:?}", function_name, key, value);
+                    }
+                }
+            }
+        },
+        LuaNil => {},
+        other => {
+            return wrap_err!("{}: expected MockRoute.headers to be {{ [string]: string }} or nil, got: {:?}", function_name, other);
+        }
+    }
+
+    // same content types http.post picks for request bodies
+    let (body, content_type) = match route.raw_get("body")? {
+        LuaValue::String(body) => (body.as_bytes().to_vec(), "text/plain; charset=utf-8"),
+        LuaValue::Buffer(body) => (body.to_vec(), "application/octet-stream"),
+        LuaValue::Table(body) => match std_json::json_raw_encode(luau, LuaValue::Table(body)) {
+            Ok(body) => (body.into_bytes(), "application/json"),
+            Err(err) => {
+                return wrap_err!("{}: unable to encode MockRoute.body table to json due to err: {}", function_name, err);
+            }
+        },
+        LuaNil => (Vec::new(), ""),
+        other => {
+            return wrap_err!("{}: expected MockRoute.body to be a string, buffer, table (sent as json), or nil, got: {:?}", function_name, other);
+        }
+    };
+    if !content_type.is_empty() && !headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("content-type")) {
+        headers.push((String::from("Content-Type"), String::from(content_type)));
+    }
+
+    let remaining = match route.raw_get("times")? {
+        LuaValue::Integer(i) => Some(int_to_u32(i, function_name, "times")?),
+        LuaValue::Number(f) => Some(float_to_u32(f, function_name, "times")?),
+        LuaNil => None,
+        other => {
+            return wrap_err!("{}: expected MockRoute.times to be a number or nil, got: {:?}", function_name, other);
+        }
+    };
+
+    let handle = handle(luau);
+    let mut state = handle.lock();
+    state.mode = Mode::Mock;
+    state.routes.push(MockRoute {
+        method,
+        url,
+        response: MockResponse { status, headers, body },
+        remaining,
+    });
+    Ok(())
+}
+
+fn mock_replay(luau: &Lua, value: LuaValue) -> LuaEmptyResult {
+    let function_name = "mock.replay(path: string)";
+    let path = pop_path(value, function_name)?;
+    let src = match fs::read_to_string(&path) {
+        Ok(src) => src,
+        Err(err) => {
+            return wrap_err!("{}: unable to read cassette '{}' due to err: {}", function_name, path.display(), err);
+        }
+    };
+    let cassette = match cassette_from_json(&src) {
+        Ok(cassette) => cassette,
+        Err(err) => {
+            return wrap_err!("{}: invalid cassette '{}': {}", function_name, path.display(), err);
+        }
+    };
+
+    let handle = handle(luau);
+    let mut state = handle.lock();
+    state.mode = Mode::Mock;
+    state.cassette = cassette;
+    Ok(())
+}
+
+fn mock_record(luau: &Lua, value: LuaValue) -> LuaEmptyResult {
+    let function_name = "mock.record(path: string)";
+    let path = pop_path(value, function_name)?;
+
+    let handle = handle(luau);
+    let mut state = handle.lock();
+    state.mode = Mode::Record(path);
+    state.cassette.clear();
+    Ok(())
+}
+
+fn mock_allow_network(luau: &Lua, value: LuaValue) -> LuaEmptyResult {
+    let function_name = "mock.allow_network(allowed: boolean)";
+    match value {
+        LuaValue::Boolean(allowed) => {
+            handle(luau).lock().allow_network = allowed;
+            Ok(())
+        },
+        other => {
+            wrap_err!("{}: expected allowed to be a boolean, got: {:?}", function_name, other)
+        }
+    }
+}
+
+fn mock_requests(luau: &Lua, _value: LuaValue) -> LuaValueResult {
+    let handle = handle(luau);
+    let state = handle.lock();
+    let mut requests = Vec::with_capacity(state.requests.len());
+    for request in &state.requests {
+        let headers = luau.create_table()?;
+        for (key, value) in &request.headers {
+            headers.raw_set(key.to_lowercase(), value.as_str())?;
+        }
+        let mut table = TableBuilder::create(luau)?
+            .with_value("method", request.method.as_str())?
+            .with_value("url", request.url.as_str())?
+            .with_value("headers", headers)?;
+        if let Some(body) = &request.body {
+            table = table.with_value("body", luau.create_string(body)?)?;
+        }
+        requests.push(table.build_readonly()?);
+    }
+    ok_table(luau.create_sequence_from(requests))
+}
+
+fn mock_reset(luau: &Lua, _value: LuaValue) -> LuaEmptyResult {
+    *handle(luau).lock() = MockState::default();
+    Ok(())
+}
+
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::create(luau)?
+        .with_function_and_signature("on", mock_on, signatures::STD_NET_HTTP_MOCK_ON)?
+        .with_function_and_signature("replay", mock_replay, signatures::STD_NET_HTTP_MOCK_REPLAY)?
+        .with_function_and_signature("record", mock_record, signatures::STD_NET_HTTP_MOCK_RECORD)?
+        .with_function_and_signature("allow_network", mock_allow_network, signatures::STD_NET_HTTP_MOCK_ALLOW_NETWORK)?
+        .with_function_and_signature("requests", mock_requests, signatures::STD_NET_HTTP_MOCK_REQUESTS)?
+        .with_function_and_signature("reset", mock_reset, signatures::STD_NET_HTTP_MOCK_RESET)?
+        .build_readonly()
+}
@@ -13,6 +13,11 @@ use http_response::HttpResponse;
 mod http_error;
 use http_error::HttpError;
 
+mod download;
+mod client;
+mod events;
+pub mod mock;
+
 use ureq::Error as UreqError;
 use ureq::http::Method;
 
@@ -60,39 +65,35 @@ pub enum HttpResponseResult {
     Ok(HttpResponse),
     Timeout(ureq::Timeout, Option<TimeoutInfo>),
     Err(UreqError),
+    /// `RequestOptions.retry` ran out of attempts; `attempts` describes what went wrong with each of them
+    Retried {
+        attempts: Vec<String>,
+        last: Box<HttpResponseResult>,
+    },
 }
 
-pub fn http_request(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
-    let function_name = "http.request(method: HttpMethod, options: HttpRequestWithoutBody | HttpRequestWithBody)";
-
-    let http_method = match multivalue.pop_front() {
+/// `http.request` and `HttpClient:request` take the method as a string like `"GET"`
+fn method_from_value(value: Option<LuaValue>, function_name: &'static str) -> LuaResult<Method> {
+    match value {
         Some(LuaValue::String(method)) => {
             match Method::from_bytes(&method.as_bytes()) {
-                Ok(method) => method,
+                Ok(method) => Ok(method),
                 Err(err) => {
-                    return wrap_err!("{}: invalid http method: {}", function_name, err);
+                    wrap_err!("{}: invalid http method: {}", function_name, err)
                 }
             }
         },
         Some(LuaNil) | None => {
-            return wrap_err!("{} was incorrectly called without an http method", function_name);
-        },
-        Some(other) => {
-            return wrap_err!("{} expected its first argument to be an http method (string), got: {:?}", function_name, other);
-        }
-    };
-
-    let config = match multivalue.pop_front() {
-        Some(LuaValue::Table(t)) => t,
-        Some(LuaNil) | None => {
-            return wrap_err!("{} called without required argument 'options' (expected table, got nothing or nil)", function_name);
+            wrap_err!("{} was incorrectly called without an http method", function_name)
         },
         Some(other) => {
-            return wrap_err!("{}: expected options to be an HttpRequestWithBody table, got: {:?}", function_name, other);
+            wrap_err!("{} expected its first argument to be an http method (string), got: {:?}", function_name, other)
         }
-    };
+    }
+}
 
-    let request = HttpRequest::from_config(http_method, config, luau, function_name)?;
+/// Sends `request`, returning an `HttpError` table instead of throwing on network errors and timeouts
+fn try_request(luau: &Lua, request: HttpRequest, function_name: &'static str) -> LuaValueResult {
     // we want to throw Luau errors but match on Ureq/Timeout errors and return them instead of throwing
     let result = request.try_send(function_name)?; 
 
@@ -109,14 +110,39 @@ pub fn http_request(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult
         },
         HttpResponseResult::Err(err) => {
             ok_table(HttpError::from_error(err, None).into_table(luau))
+        },
+        HttpResponseResult::Retried { attempts, last } => {
+            ok_table(HttpError::from_retried(attempts, *last).into_table(luau))
         }
     }
 }
 
+pub fn http_request(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+    let function_name = "http.request(method: HttpMethod, options: HttpRequestWithoutBody | HttpRequestWithBody)";
+
+    let http_method = method_from_value(multivalue.pop_front(), function_name)?;
+
+    let config = match multivalue.pop_front() {
+        Some(LuaValue::Table(t)) => t,
+        Some(LuaNil) | None => {
+            return wrap_err!("{} called without required argument 'options' (expected table, got nothing or nil)", function_name);
+        },
+        Some(other) => {
+            return wrap_err!("{}: expected options to be an HttpRequestWithBody table, got: {:?}", function_name, other);
+        }
+    };
+
+    let request = HttpRequest::from_config(http_method, config, luau, function_name)?;
+    try_request(luau, request, function_name)
+}
+
 pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
     TableBuilder::create(luau)?
         .with_function_and_signature("get", http_get, signatures::STD_NET_HTTP_GET)?
         .with_function_and_signature("post", http_post, signatures::STD_NET_HTTP_POST)?
         .with_function_and_signature("request", http_request, signatures::STD_NET_HTTP_REQUEST)?
+        .with_function_and_signature("download", download::http_download, signatures::STD_NET_HTTP_DOWNLOAD)?
+        .with_function_and_signature("client", client::http_client, signatures::STD_NET_HTTP_CLIENT)?
+        .with_function_and_signature("events", events::http_events, signatures::STD_NET_HTTP_EVENTS)?
         .build_readonly()
 }
@@ -0,0 +1,170 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+use super::ip;
+
+struct InterfaceAddress {
+    ip: IpAddr,
+    /// `None` if the os didn't report a netmask
+    prefix: Option<u32>,
+}
+
+struct Interface {
+    name: String,
+    up: bool,
+    loopback: bool,
+    addresses: Vec<InterfaceAddress>,
+}
+
+#[cfg(unix)]
+fn list_interfaces() -> io::Result<Vec<Interface>> {
+    use std::ffi::CStr;
+
+    /// # Safety
+    /// `address` has to be null or point to a valid sockaddr whose `sa_family` matches its actual type
+    unsafe fn sockaddr_to_ip(address: *const libc::sockaddr) -> Option<IpAddr> {
+        if address.is_null() {
+            return None;
+        }
+        // SAFETY: non-null and valid per the function's contract; the family tells us which sockaddr it really is
+        unsafe {
+            match (*address).sa_family as libc::c_int {
+                libc::AF_INET => {
+                    let address = &*(address as *const libc::sockaddr_in);
+                    Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr))))
+                },
+                libc::AF_INET6 => {
+                    let address = &*(address as *const libc::sockaddr_in6);
+                    Some(IpAddr::from(address.sin6_addr.s6_addr))
+                },
+                _ => None,
+            }
+        }
+    }
+
+    let mut first: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs only writes the head of a list it allocates into `first`, which we free below
+    if unsafe { libc::getifaddrs(&mut first) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut current = first;
+    while !current.is_null() {
+        // SAFETY: `current` is a non-null node of the list getifaddrs gave us, which stays valid until freeifaddrs
+        let entry = unsafe { &*current };
+        current = entry.ifa_next;
+
+        // SAFETY: ifa_name is a nul-terminated string owned by the list
+        let name = unsafe { CStr::from_ptr(entry.ifa_name) }.to_string_lossy().to_string();
+        let flags = entry.ifa_flags as libc::c_int;
+
+        // getifaddrs has an entry per address, so interfaces with several addresses show up several times
+        let index = match interfaces.iter().position(|interface| interface.name == name) {
+            Some(index) => index,
+            None => {
+                interfaces.push(Interface {
+                    name,
+                    up: flags & libc::IFF_UP != 0,
+                    loopback: flags & libc::IFF_LOOPBACK != 0,
+                    addresses: Vec::new(),
+                });
+                interfaces.len() - 1
+            }
+        };
+
+        // SAFETY: ifa_addr and ifa_netmask are null or valid sockaddrs owned by the list
+        let (ip, netmask) = unsafe { (sockaddr_to_ip(entry.ifa_addr), sockaddr_to_ip(entry.ifa_netmask)) };
+        if let Some(ip) = ip
+            && let Some(interface) = interfaces.get_mut(index)
+        {
+            let prefix = netmask.map(|netmask| match netmask {
+                IpAddr::V4(netmask) => u32::from(netmask).count_ones(),
+                IpAddr::V6(netmask) => u128::from(netmask).count_ones(),
+            });
+            interface.addresses.push(InterfaceAddress { ip, prefix });
+        }
+    }
+
+    // SAFETY: `first` is the list getifaddrs allocated, and nothing borrowed from it outlives this point
+    unsafe { libc::freeifaddrs(first) };
+    Ok(interfaces)
+}
+
+#[cfg(not(unix))]
+fn list_interfaces() -> io::Result<Vec<Interface>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "listing network interfaces isn't supported on this platform yet; use net.local_ip() instead"))
+}
+
+pub fn net_interfaces(luau: &Lua, _value: LuaValue) -> LuaValueResult {
+    let function_name = "net.interfaces()";
+    let interfaces = match list_interfaces() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            return wrap_err!("{}: unable to list network interfaces due to err: {}", function_name, err);
+        }
+    };
+
+    let mut interface_tables = Vec::with_capacity(interfaces.len());
+    for interface in interfaces {
+        let mut address_tables = Vec::with_capacity(interface.addresses.len());
+        for address in interface.addresses {
+            let mut address_table = TableBuilder::create(luau)?
+                .with_value("ip", address.ip.to_string())?
+                .with_value("family", ip::family(&address.ip))?;
+            if let Some(prefix) = address.prefix
+                && let Some(cidr) = ip::Cidr::new(address.ip, prefix)
+            {
+                address_table = address_table
+                    .with_value("prefix", prefix)?
+                    .with_value("network", cidr.to_string())?;
+            }
+            address_tables.push(address_table.build_readonly()?);
+        }
+
+        interface_tables.push(TableBuilder::create(luau)?
+            .with_value("name", interface.name)?
+            .with_value("up", interface.up)?
+            .with_value("loopback", interface.loopback)?
+            .with_value("addresses", luau.create_sequence_from(address_tables)?)?
+            .build_readonly()?
+        );
+    }
+
+    ok_table(luau.create_sequence_from(interface_tables))
+}
+
+/// The address this machine would use to reach the internet (its LAN address, usually). Connecting a UDP
+/// socket doesn't send anything, it just asks the os which route (and so which local address) it'd use.
+pub fn net_local_ip(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "net.local_ip(family: (\"IPv4\" | \"IPv6\")?)";
+    let (bind_address, remote_address) = match value {
+        LuaValue::String(family) if family == "IPv6" => ("[::]:0", "[2001:4860:4860::8888]:53"),
+        LuaValue::String(family) if family == "IPv4" => ("0.0.0.0:0", "8.8.8.8:53"),
+        LuaNil => ("0.0.0.0:0", "8.8.8.8:53"),
+        other => {
+            return wrap_err!("{}: expected family to be \"IPv4\", \"IPv6\", or nil (defaults to \"IPv4\"), got: {:?}", function_name, other);
+        }
+    };
+
+    let socket = match UdpSocket::bind(bind_address) {
+        Ok(socket) => socket,
+        Err(err) => {
+            return wrap_err!("{}: unable to create socket due to err: {}", function_name, err);
+        }
+    };
+    // no route (offline, or no ipv6) means no such address
+    if socket.connect(remote_address).is_err() {
+        return Ok(LuaNil);
+    }
+    match socket.local_addr() {
+        Ok(address) if !address.ip().is_unspecified() => ok_string(address.ip().to_string(), luau),
+        Ok(_) => Ok(LuaNil),
+        Err(err) => {
+            wrap_err!("{}: unable to get the socket's local address due to err: {}", function_name, err)
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// An ip address as a number, so ipv4 and ipv6 ranges can share the same math
+#[derive(Clone, Copy)]
+struct Bits {
+    value: u128,
+    ipv4: bool,
+}
+
+impl Bits {
+    fn from_ip(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => Self { value: u32::from(ip) as u128, ipv4: true },
+            IpAddr::V6(ip) => Self { value: u128::from(ip), ipv4: false },
+        }
+    }
+
+    fn ip(value: u128, ipv4: bool) -> IpAddr {
+        if ipv4 {
+            IpAddr::V4(Ipv4Addr::from(value as u32))
+        } else {
+            IpAddr::V6(Ipv6Addr::from(value))
+        }
+    }
+}
+
+pub fn family(ip: &IpAddr) -> &'static str {
+    match ip {
+        IpAddr::V4(_) => "IPv4",
+        IpAddr::V6(_) => "IPv6",
+    }
+}
+
+/// Private-use ranges: RFC 1918 for ipv4, unique local addresses (`fc00::/7`) for ipv6
+pub fn is_private(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private(),
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xfe00) == 0xfc00,
+    }
+}
+
+pub fn is_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xffc0) == 0xfe80,
+    }
+}
+
+/// A CIDR range like `10.0.0.0/8` or `fd00::/8`
+#[derive(Clone, Copy)]
+pub struct Cidr {
+    /// the first address in the range, with the host bits zeroed
+    network: u128,
+    prefix: u32,
+    ipv4: bool,
+}
+
+impl Cidr {
+    pub fn new(ip: IpAddr, prefix: u32) -> Option<Self> {
+        let bits = Bits::from_ip(ip);
+        let width = if bits.ipv4 { 32 } else { 128 };
+        if prefix > width {
+            return None;
+        }
+        let mut cidr = Self { network: 0, prefix, ipv4: bits.ipv4 };
+        cidr.network = bits.value & cidr.mask();
+        Some(cidr)
+    }
+
+    /// Like Python's `ipaddress.ip_network(strict=False)`, host bits are dropped, so `192.168.1.23/24`
+    /// means `192.168.1.0/24`; a bare ip is a range of just that address.
+    fn parse(range: &str) -> Result<Self, String> {
+        let (ip, prefix) = match range.split_once('/') {
+            Some((ip, prefix)) => (ip, Some(prefix)),
+            None => (range, None),
+        };
+        let ip = match ip.trim().parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(err) => return Err(format!("'{}' isn't a valid ip address: {}", ip, err)),
+        };
+        let width = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => match prefix.trim().parse::<u32>() {
+                Ok(prefix) if prefix <= width => prefix,
+                _ => return Err(format!("prefix length '{}' should be a number from 0 to {}", prefix, width)),
+            },
+            None => width,
+        };
+        Self::new(ip, prefix).ok_or_else(|| format!("prefix length {} is too long", prefix))
+    }
+
+    fn width(&self) -> u32 {
+        if self.ipv4 { 32 } else { 128 }
+    }
+
+    fn all_ones(&self) -> u128 {
+        if self.ipv4 { u32::MAX as u128 } else { u128::MAX }
+    }
+
+    fn mask(&self) -> u128 {
+        match self.all_ones().checked_shl(self.width() - self.prefix) {
+            Some(mask) => mask & self.all_ones(),
+            // a /0 has no network bits at all
+            None => 0,
+        }
+    }
+
+    fn last(&self) -> u128 {
+        self.network | (!self.mask() & self.all_ones())
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let bits = Bits::from_ip(ip);
+        bits.ipv4 == self.ipv4 && (bits.value & self.mask()) == self.network
+    }
+
+    /// How many addresses are in the range; a float since ipv6 ranges can hold way more than 2^53
+    fn size(&self) -> f64 {
+        2f64.powi((self.width() - self.prefix) as i32)
+    }
+
+    fn create_handle(self, luau: &Lua) -> LuaResult<LuaTable> {
+        let ipv4 = self.ipv4;
+        TableBuilder::create(luau)?
+            .with_value("network", Bits::ip(self.network, ipv4).to_string())?
+            .with_value("prefix", self.prefix)?
+            .with_value("netmask", Bits::ip(self.mask(), ipv4).to_string())?
+            .with_value("first", Bits::ip(self.network, ipv4).to_string())?
+            .with_value("last", Bits::ip(self.last(), ipv4).to_string())?
+            .with_value("size", self.size())?
+            .with_value("family", if ipv4 { "IPv4" } else { "IPv6" })?
+            .with_function_and_signature("contains", move | _luau: &Lua, mut multivalue: LuaMultiValue | -> LuaResult<bool> {
+                let function_name = "Cidr:contains(ip: string)";
+                pop_self(&mut multivalue, function_name)?;
+                match multivalue.pop_front() {
+                    Some(LuaValue::String(ip)) => {
+                        // anything that isn't an ip isn't in the range either
+                        Ok(ip.to_string_lossy().trim().parse::<IpAddr>().is_ok_and(|ip| self.contains(ip)))
+                    },
+                    Some(other) => wrap_err!("{}: expected ip to be a string, got: {:?}", function_name, other),
+                    None => wrap_err!("{} called without required argument 'ip' (expected string)", function_name),
+                }
+            }, signatures::STD_NET_IP_CIDR_CONTAINS)?
+            .with_function_and_signature("addresses", move | luau: &Lua, mut multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                pop_self(&mut multivalue, "Cidr:addresses()")?;
+                let last = self.last();
+                let mut next = Some(self.network);
+                luau.create_function_mut(move | luau: &Lua, _value: LuaMultiValue | -> LuaValueResult {
+                    match next {
+                        Some(current) => {
+                            next = current.checked_add(1).filter(|next| *next <= last);
+                            ok_string(Bits::ip(current, ipv4).to_string(), luau)
+                        },
+                        None => Ok(LuaNil),
+                    }
+                })
+            }, signatures::STD_NET_IP_CIDR_ADDRESSES)?
+            .with_function_and_signature("subnets", move | luau: &Lua, mut multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                let function_name = "Cidr:subnets(prefix: number)";
+                pop_self(&mut multivalue, function_name)?;
+                let new_prefix = match multivalue.pop_front() {
+                    Some(LuaValue::Integer(i)) => int_to_u32(i, function_name, "prefix")?,
+                    Some(LuaValue::Number(f)) => float_to_u32(f, function_name, "prefix")?,
+                    Some(other) => {
+                        return wrap_err!("{}: expected prefix to be a number, got: {:?}", function_name, other);
+                    },
+                    None => {
+                        return wrap_err!("{} called without required argument 'prefix' (expected number)", function_name);
+                    }
+                };
+                if new_prefix < self.prefix || new_prefix > self.width() {
+                    return wrap_err!("{}: prefix should be between {} and {} to split {} into subnets, got: {}", function_name, self.prefix, self.width(), self, new_prefix);
+                }
+                // only None for `::/0` split into /0s, which is just the one subnet
+                let step = 1u128.checked_shl(self.width() - new_prefix);
+                let last = self.last();
+                let mut next = Some(self.network);
+                luau.create_function_mut(move | luau: &Lua, _value: LuaMultiValue | -> LuaValueResult {
+                    match next {
+                        Some(current) => {
+                            next = step
+                                .and_then(|step| current.checked_add(step))
+                                .filter(|next| *next <= last);
+                            let subnet = Self { network: current, prefix: new_prefix, ipv4 };
+                            ok_table(subnet.create_handle(luau))
+                        },
+                        None => Ok(LuaNil),
+                    }
+                })
+            }, signatures::STD_NET_IP_CIDR_SUBNETS)?
+            .with_metatable(TableBuilder::create(luau)?
+                .with_function("__tostring", move | _luau: &Lua, _value: LuaValue | -> LuaResult<String> {
+                    Ok(self.to_string())
+                })?
+                .build_readonly()?
+            )?
+            .build_readonly()
+    }
+}
+
+impl std::fmt::Display for Cidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", Bits::ip(self.network, self.ipv4), self.prefix)
+    }
+}
+
+fn pop_ip(value: LuaValue, function_name: &'static str) -> LuaResult<String> {
+    match value {
+        LuaValue::String(ip) => Ok(ip.to_string_lossy()),
+        LuaNil => {
+            wrap_err!("{} called without required argument 'ip' (expected string)", function_name)
+        },
+        other => {
+            wrap_err!("{}: expected ip to be a string, got: {:?}", function_name, other)
+        }
+    }
+}
+
+fn ip_valid(_luau: &Lua, value: LuaValue) -> LuaResult<bool> {
+    let function_name = "ip.valid(ip: string)";
+    let ip = pop_ip(value, function_name)?;
+    Ok(ip.trim().parse::<IpAddr>().is_ok())
+}
+
+fn ip_parse(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "ip.parse(ip: string)";
+    let ip = match pop_ip(value, function_name)?.trim().parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => return Ok(LuaNil),
+    };
+
+    ok_table(TableBuilder::create(luau)?
+        // normalized, so "::0001" comes back as "::1"
+        .with_value("address", ip.to_string())?
+        .with_value("family", family(&ip))?
+        .with_value("loopback", ip.is_loopback())?
+        .with_value("private", is_private(&ip))?
+        .with_value("link_local", is_link_local(&ip))?
+        .with_value("multicast", ip.is_multicast())?
+        .with_value("unspecified", ip.is_unspecified())?
+        .build_readonly()
+    )
+}
+
+fn ip_cidr(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "ip.cidr(range: string)";
+    let range = match value {
+        LuaValue::String(range) => range.to_string_lossy(),
+        LuaNil => {
+            return wrap_err!("{} called without required argument 'range' (expected string like \"10.0.0.0/8\")", function_name);
+        },
+        other => {
+            return wrap_err!("{}: expected range to be a string like \"10.0.0.0/8\", got: {:?}", function_name, other);
+        }
+    };
+
+    match Cidr::parse(&range) {
+        Ok(cidr) => ok_table(cidr.create_handle(luau)),
+        Err(err) => wrap_err!("{}: invalid CIDR range '{}': {}", function_name, range, err),
+    }
+}
+
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::create(luau)?
+        .with_function_and_signature("valid", ip_valid, signatures::STD_NET_IP_VALID)?
+        .with_function_and_signature("parse", ip_parse, signatures::STD_NET_IP_PARSE)?
+        .with_function_and_signature("cidr", ip_cidr, signatures::STD_NET_IP_CIDR)?
+        .build_readonly()
+}
@@ -3,7 +3,13 @@ use mluau::prelude::*;
 pub mod http;
 pub mod serve;
 pub mod websocket;
+pub mod tcp;
+pub mod udp;
+pub mod dns;
+pub mod ip;
+mod interfaces;
 pub mod tls_config;
+pub mod proxy;
 
 use crate::prelude::*;
 
@@ -11,5 +17,11 @@ pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
     TableBuilder::create(luau)?
         .with_value("http", self::http::create(luau)?)?
         .with_value("websocket", websocket::create(luau)?)?
+        .with_value("tcp", tcp::create(luau)?)?
+        .with_value("udp", udp::create(luau)?)?
+        .with_value("dns", dns::create(luau)?)?
+        .with_value("ip", ip::create(luau)?)?
+        .with_function_and_signature("interfaces", interfaces::net_interfaces, signatures::STD_NET_INTERFACES)?
+        .with_function_and_signature("local_ip", interfaces::net_local_ip, signatures::STD_NET_LOCAL_IP)?
         .build_readonly()
 }
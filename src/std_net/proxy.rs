@@ -0,0 +1,123 @@
+//! Proxy settings shared by @std/net's http requests (ureq) and websockets (tungstenite).
+//!
+//! By default both use the proxy from the `ALL_PROXY`/`HTTPS_PROXY`/`HTTP_PROXY` env vars
+//! (skipping hosts listed in `NO_PROXY`), like curl and most other tools do; `RequestOptions.proxy`
+//! can point a request at a specific proxy instead, or pass `false` to connect directly.
+
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use base64::Engine;
+use ureq::{Proxy, ProxyProtocol};
+
+use super::tcp;
+
+/// `RequestOptions.proxy`
+#[derive(Clone)]
+pub enum ProxySetting {
+    /// `proxy = false`
+    Disabled,
+    /// `proxy = "http://proxy.corp:3128"`
+    Url(Proxy),
+}
+
+impl ProxySetting {
+    /// `None` means the user didn't set one, so the env vars decide
+    pub fn from_value(value: LuaValue, field_name: &'static str, function_name: &'static str) -> LuaResult<Option<Self>> {
+        match value {
+            LuaValue::String(url) => {
+                let url = url.to_string_lossy();
+                let proxy = match Proxy::new(&url) {
+                    Ok(proxy) => proxy,
+                    Err(err) => {
+                        return wrap_err!("{}: invalid {} '{}': {}", function_name, field_name, url, err);
+                    }
+                };
+                if !matches!(proxy.protocol(), ProxyProtocol::Http | ProxyProtocol::Https) {
+                    return wrap_err!("{}: {} '{}' isn't supported; only http:// and https:// proxies are", function_name, field_name, url);
+                }
+                Ok(Some(Self::Url(proxy)))
+            },
+            LuaValue::Boolean(false) => Ok(Some(Self::Disabled)),
+            LuaNil => Ok(None),
+            other => {
+                wrap_err!("{}: expected {} to be a proxy url (string), false (to not use a proxy), or nil (to use HTTP_PROXY/HTTPS_PROXY), got: {:?}", function_name, field_name, other)
+            }
+        }
+    }
+
+    /// What to pass to ureq's `ConfigBuilder::proxy`
+    pub fn into_ureq(self) -> Option<Proxy> {
+        match self {
+            Self::Disabled => None,
+            Self::Url(proxy) => Some(proxy),
+        }
+    }
+}
+
+/// The proxy a websocket connecting to `host` should tunnel through, if any
+pub fn websocket_proxy(setting: Option<&ProxySetting>, host: &str) -> Option<Proxy> {
+    match setting {
+        Some(ProxySetting::Disabled) => None,
+        Some(ProxySetting::Url(proxy)) => Some(proxy.clone()),
+        None => {
+            let proxy = Proxy::try_from_env()?;
+            let uri = format!("ws://{}", host).parse::<ureq::http::Uri>().ok()?;
+            if proxy.is_no_proxy(&uri) { None } else { Some(proxy) }
+        }
+    }
+}
+
+/// Opens a tunnel to `host:port` through an http proxy with `CONNECT`, returning the stream to speak
+/// the websocket handshake (and TLS, for `wss://`) over. `timeout` bounds connecting to the proxy and each read/write
+/// of the `CONNECT` exchange; the caller sets the stream's timeouts again afterwards.
+pub fn connect_tunnel(proxy: &Proxy, host: &str, port: u16, timeout: Option<Duration>) -> io::Result<TcpStream> {
+    if proxy.protocol() != ProxyProtocol::Http {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("websockets can only connect through http:// proxies, not {}", proxy.uri()),
+        ));
+    }
+
+    let proxy_addresses: Vec<_> = (proxy.host(), proxy.port()).to_socket_addrs()?.collect();
+    let mut stream = tcp::connect_any(&proxy_addresses, timeout)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some(username) = proxy.username() {
+        let credentials = format!("{}:{}", username, proxy.password().unwrap_or_default());
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", encoded));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    // read one byte at a time so we don't eat into what the server sends after the tunnel's open
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "proxy closed the connection during CONNECT"));
+        }
+        head.push(byte[0]);
+        if head.len() > 64 * 1024 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "proxy's CONNECT response headers are too large"));
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(stream),
+        _ => Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("proxy refused to CONNECT to {}:{}: {}", host, port, status_line),
+        )),
+    }
+}
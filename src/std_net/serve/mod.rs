@@ -0,0 +1,490 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use crate::std_fs::file_size::FileSize;
+use crate::std_time::duration::TimeDuration;
+use crate::std_thread;
+use crate::require::get_chunk_name_for_module;
+use crate::std_net::websocket::WebsocketStream;
+
+use std::fs;
+use std::net::{TcpListener, TcpStream};
+use std::io::BufReader;
+use std::time::Duration;
+use std::sync::Arc;
+
+mod router;
+mod request;
+mod response;
+mod workers;
+mod tls;
+pub(crate) mod mime;
+mod upgrade;
+pub mod static_files;
+use router::{Router, RouteMatch};
+use request::{Limits, ParsedRequest, RequestError};
+use response::{Response, WriteError, WriteOptions};
+
+/// How long an idle keep-alive connection waits for its next request (and how long we wait
+/// between reads mid-request) unless `keep_alive_timeout` says otherwise
+const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What `ServeConfig.handler` (or a handler module) was set to
+enum Handler {
+    Function(LuaFunction),
+    Routes(Router),
+}
+
+/// A handler and its middleware; these are Luau functions so they're bound to the VM that created them
+struct Service {
+    handler: Handler,
+    middleware: Vec<LuaFunction>,
+}
+
+impl Service {
+    fn from_values(handler: LuaValue, middleware: LuaValue, function_name: &'static str) -> LuaResult<Self> {
+        let handler = match handler {
+            LuaValue::Function(f) => Handler::Function(f),
+            LuaValue::Table(routes) => Handler::Routes(Router::from_table(routes, function_name)?),
+            other => {
+                return wrap_err!("{} expected handler to be a function or route table, got: {:?}", function_name, other);
+            }
+        };
+        let middleware = router::parse_middleware(middleware, function_name)?;
+        Ok(Self { handler, middleware })
+    }
+
+    /// Loads a handler module, which can return a handler function, a route table,
+    /// or a table with fields `handler` and (optionally) `middleware`
+    fn from_module(luau: &Lua, module: &HandlerModule, function_name: &'static str) -> LuaResult<Self> {
+        let src = match fs::read_to_string(&module.chunk_name) {
+            Ok(src) => src,
+            Err(err) => {
+                return wrap_err!("{}: unable to read handler module at '{}' due to err: {}", function_name, module.chunk_name, err);
+            }
+        };
+        let returned = luau.load(Chunk::src(src)).set_name(&module.chunk_name).eval::<LuaValue>()?;
+        match returned {
+            LuaValue::Table(t) if !matches!(t.raw_get("handler")?, LuaNil) => {
+                Self::from_values(t.raw_get("handler")?, t.raw_get("middleware")?, function_name)
+            },
+            handler @ (LuaValue::Function(_) | LuaValue::Table(_)) => {
+                Self::from_values(handler, LuaNil, function_name)
+            },
+            other => {
+                wrap_err!("{}: expected handler module '{}' to return a handler function, route table, or {{ handler, middleware }} table, got: {:?}", function_name, module.chunk_name, other)
+            }
+        }
+    }
+}
+
+/// `ServeConfig.handler` given as a path to a module; each worker VM loads its own copy
+#[derive(Debug, Clone)]
+struct HandlerModule {
+    chunk_name: String,
+}
+
+enum HandlerConfig {
+    Loaded(Service),
+    Module(HandlerModule),
+}
+
+/// Per-connection settings; no Luau values so worker threads can share them
+#[derive(Debug, Clone)]
+struct ConnectionSettings {
+    limits: Limits,
+    keep_alive: bool,
+    keep_alive_timeout: Duration,
+    /// set by `ServeConfig.tls`; accepted connections get wrapped in a rustls server session
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+struct ServeConfig {
+    address_port: String,
+    handler: HandlerConfig,
+    settings: ConnectionSettings,
+    workers: Option<usize>,
+}
+
+fn parse_file_size(value: LuaValue, field: &'static str, function_name: &'static str) -> LuaResult<Option<u64>> {
+    match value {
+        LuaValue::UserData(ud) if let Ok(file_size) = ud.borrow::<FileSize>() => {
+            Ok(Some(file_size.as_bytes()))
+        },
+        LuaValue::UserData(other) => {
+            wrap_err!("{}: expected config.{} to be a FileSize userdata from std/fs/filesize, got an unexpected userdata instance: {:?}", function_name, field, other)
+        },
+        LuaValue::Number(_) | LuaValue::Integer(_) => {
+            wrap_err!("{}: expected config.{} to be a FileSize (userdata) from std/fs/filesize, not a number of bytes", function_name, field)
+        },
+        LuaNil => Ok(None),
+        other => {
+            wrap_err!("{}: expected config.{} to be a FileSize from std/fs/filesize or nil, got: {:?}", function_name, field, other)
+        }
+    }
+}
+
+impl ServeConfig {
+    fn from_value(luau: &Lua, value: LuaValue, function_name: &'static str) -> LuaResult<Self> {
+        let config = match value {
+            LuaValue::Table(config) => config,
+            other => {
+                return wrap_err!("{} expected ServeConfig table (with fields address, port, handler, etc.), got: {:#?}", function_name, other);
+            }
+        };
+
+        let address = match config.raw_get("address")? {
+            LuaValue::String(address) => address.to_string_lossy(),
+            other => {
+                return wrap_err!("{} expected config.address to be a string (like \"localhost\" or \"0.0.0.0\"), got: {:?}", function_name, other);
+            }
+        };
+
+        let port = match config.raw_get("port")? {
+            LuaValue::String(port) => port.to_string_lossy(),
+            LuaValue::Integer(port) => port.to_string(),
+            LuaValue::Number(port) => float_to_u16(port, function_name, "port")?.to_string(),
+            other => {
+                return wrap_err!("{} expected config.port to be a string or number, got: {:?}", function_name, other);
+            }
+        };
+
+        let handler = match config.raw_get("handler")? {
+            LuaValue::String(path) => {
+                if !matches!(config.raw_get("middleware")?, LuaNil) {
+                    return wrap_err!("{}: config.middleware can't be used with a handler module path; return {{ handler = ..., middleware = {{ ... }} }} from the module instead", function_name);
+                }
+                let path = std_thread::path_relative_to_script(luau, &path.to_string_lossy(), function_name)?;
+                let chunk_name = match path.to_str() {
+                    Some(path_str) => match get_chunk_name_for_module(path_str, function_name)? {
+                        Some(chunk_name) => chunk_name,
+                        None => {
+                            return wrap_err!("{}: handler module '{}' not found; does it exist and is it either a .luau file or directory with an init.luau?", function_name, path.display());
+                        }
+                    },
+                    None => {
+                        return wrap_err!("{}: handler module path '{}' isn't valid utf-8", function_name, path.display());
+                    }
+                };
+                HandlerConfig::Module(HandlerModule { chunk_name })
+            },
+            handler => HandlerConfig::Loaded(Service::from_values(handler, config.raw_get("middleware")?, function_name)?),
+        };
+
+        let workers = match config.raw_get("workers")? {
+            LuaNil => None,
+            LuaValue::Integer(i) => Some(int_to_usize(i, function_name, "workers")?),
+            LuaValue::Number(f) => Some(float_to_usize(f, function_name, "workers")?),
+            other => {
+                return wrap_err!("{} expected config.workers to be a number or nil, got: {:?}", function_name, other);
+            }
+        };
+        if let Some(workers) = workers {
+            if workers == 0 {
+                return wrap_err!("{}: config.workers must be at least 1", function_name);
+            }
+            if matches!(handler, HandlerConfig::Loaded(_)) {
+                return wrap_err!("{}: config.workers requires config.handler to be a path to a handler module (functions can't be shared between workers' VMs)", function_name);
+            }
+        }
+
+        let defaults = Limits::default();
+        let limits = Limits {
+            max_header_size: parse_file_size(config.raw_get("max_header_size")?, "max_header_size", function_name)?
+                .unwrap_or(defaults.max_header_size),
+            max_body_size: parse_file_size(config.raw_get("max_body_size")?, "max_body_size", function_name)?
+                .unwrap_or(defaults.max_body_size),
+        };
+
+        let keep_alive = match config.raw_get("keep_alive")? {
+            LuaValue::Boolean(keep_alive) => keep_alive,
+            // without workers there's only one thread serving clients, so an idle keep-alive connection
+            // (like a browser's) would hold up everyone else until it times out
+            LuaNil => workers.is_some(),
+            other => {
+                return wrap_err!("{} expected config.keep_alive to be a boolean or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let keep_alive_timeout = match config.raw_get("keep_alive_timeout")? {
+            LuaValue::UserData(ud) if let Ok(duration) = ud.borrow::<TimeDuration>() => {
+                let timeout = duration.inner;
+                if !timeout.is_positive() {
+                    return wrap_err!("{}: config.keep_alive_timeout must be a positive Duration, got: {:#?}", function_name, timeout);
+                }
+                timeout.unsigned_abs()
+            },
+            LuaNil => DEFAULT_KEEP_ALIVE_TIMEOUT,
+            other => {
+                return wrap_err!("{} expected config.keep_alive_timeout to be a Duration (from @std/time) or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let tls = match config.raw_get("tls")? {
+            LuaNil => None,
+            value => Some(tls::server_config(value, function_name)?),
+        };
+
+        Ok(Self {
+            address_port: format!("{}:{}", address, port),
+            handler,
+            settings: ConnectionSettings {
+                limits,
+                keep_alive,
+                keep_alive_timeout,
+                tls,
+            },
+            workers,
+        })
+    }
+}
+
+fn server_serve(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "server.serve(config: ServeConfig)";
+    let config = ServeConfig::from_value(luau, value, function_name)?;
+
+    let listener = match TcpListener::bind(&config.address_port) {
+        Ok(listener) => listener,
+        Err(err) => {
+            return wrap_err!("{}: failed to bind to {} with error: {}", function_name, config.address_port, err);
+        }
+    };
+
+    let service = match config.handler {
+        HandlerConfig::Module(module) if let Some(workers) = config.workers => {
+            return workers::serve(listener, module, config.settings, workers, function_name);
+        },
+        HandlerConfig::Module(module) => Service::from_module(luau, &module, function_name)?,
+        HandlerConfig::Loaded(service) => service,
+    };
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(err) = handle_client(stream, &config.settings, Some(luau), |request, peer_address| respond(luau, request, peer_address, &service)) {
+                    return wrap_err!("{}: failed to handle client: {}", function_name, err);
+                }
+            }
+            Err(err) => {
+                eputs!("server.serve: connection failed: {}", err)?;
+            }
+        }
+    }
+
+    Ok(LuaNil)
+}
+
+/// A client connection, plain or TLS; `'static` because websocket upgrades hand it to Luau as userdata
+trait Connection: WebsocketStream + 'static {
+    fn tcp(&self) -> &TcpStream;
+}
+
+impl Connection for TcpStream {
+    fn tcp(&self) -> &TcpStream {
+        self
+    }
+}
+
+impl WebsocketStream for TcpStream {
+    fn socket(&self) -> Option<&TcpStream> {
+        Some(self)
+    }
+}
+
+impl Connection for tls::TlsStream {
+    fn tcp(&self) -> &TcpStream {
+        tls::TlsStream::tcp(self)
+    }
+}
+
+impl WebsocketStream for tls::TlsStream {
+    fn socket(&self) -> Option<&TcpStream> {
+        Some(tls::TlsStream::tcp(self))
+    }
+}
+
+/// Serves every request on the connection with `respond`, which gets the parsed request and the client's address.
+/// `luau` is the VM `respond`'s handlers live in, needed to accept websocket upgrades; `None` when there isn't one.
+fn handle_client<F>(stream: TcpStream, settings: &ConnectionSettings, luau: Option<&Lua>, respond: F) -> LuaEmptyResult
+where
+    F: FnMut(&ParsedRequest, &str) -> LuaResult<Response>,
+{
+    let peer_address = match stream.peer_addr() {
+        Ok(address) => address.to_string(),
+        Err(err) => format!("Unknown ({})", err),
+    };
+
+    // without a read timeout an idle keep-alive connection (or a client that never finishes
+    // sending its request) would block the server forever
+    if stream.set_read_timeout(Some(settings.keep_alive_timeout)).is_err() {
+        return Ok(()); // socket's already dead
+    }
+
+    match &settings.tls {
+        Some(tls_config) => {
+            let connection = match rustls::ServerConnection::new(Arc::clone(tls_config)) {
+                Ok(connection) => connection,
+                Err(err) => {
+                    return wrap_err!("unable to start TLS session with {}: {}", peer_address, err);
+                }
+            };
+            // the handshake happens on first read, so clients that fail it (or speak plain http) just get disconnected
+            serve_connection(tls::TlsStream::new(connection, stream), &peer_address, settings, luau, respond)
+        },
+        None => serve_connection(stream, &peer_address, settings, luau, respond),
+    }
+}
+
+/// Reads requests off a connection and answers them until the client or handler asks to close,
+/// the keep-alive timeout elapses, the client sends something we can't parse, or the connection
+/// gets upgraded to a websocket.
+fn serve_connection<S, F>(stream: S, peer_address: &str, settings: &ConnectionSettings, luau: Option<&Lua>, mut respond: F) -> LuaEmptyResult
+where
+    S: Connection,
+    F: FnMut(&ParsedRequest, &str) -> LuaResult<Response>,
+{
+    let mut reader = BufReader::new(stream);
+    loop {
+        let request = match request::read_request(&mut reader, &settings.limits) {
+            Ok(request) => request,
+            Err(RequestError::Closed | RequestError::Io(_)) => return Ok(()),
+            Err(err) => {
+                if let Some(status) = err.status() {
+                    // we don't know where the next request would start, so always close afterwards
+                    let _ = Response::text(status, err.message()).write_to(reader.get_mut(), &WriteOptions::closing());
+                }
+                return Ok(());
+            }
+        };
+
+        let mut response = respond(&request, peer_address)?;
+        if let Some(on_upgrade) = response.websocket.take() {
+            match (upgrade::accept_key(&request), luau) {
+                (Some(accept_key), Some(luau)) => {
+                    let function_name = "server.serve(config: ServeConfig)";
+                    return upgrade::serve_websocket(luau, reader, &response, &accept_key, on_upgrade, function_name);
+                },
+                _ => response = upgrade::upgrade_required(),
+            }
+        }
+
+        let chunked = request.minor_version >= 1;
+        let keep_alive = settings.keep_alive
+            && request.keep_alive
+            && !response.close
+            && (chunked || !response.is_chunked());
+
+        let options = WriteOptions {
+            keep_alive,
+            head_only: request.method == "HEAD",
+            range: request.header("range"),
+            chunked,
+        };
+        match response.write_to(reader.get_mut(), &options) {
+            Ok(()) if keep_alive => continue,
+            Ok(()) | Err(WriteError::Io(_)) => return Ok(()),
+            Err(WriteError::Luau(err)) => return Err(err),
+        }
+    }
+}
+
+/// Builds the `ServeRequest` table for the handler
+fn request_table(luau: &Lua, request: &ParsedRequest, peer_address: &str, params: LuaTable, route: Option<String>) -> LuaResult<LuaTable> {
+    // duplicate headers are combined into one comma separated value (RFC 9110 5.3),
+    // except cookies which are separated by semicolons
+    let mut combined_headers: Vec<(&str, String)> = Vec::new();
+    for (name, value) in &request.headers {
+        match combined_headers.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, existing_value)) => {
+                existing_value.push_str(if name == "cookie" { "; " } else { ", " });
+                existing_value.push_str(value);
+            },
+            None => combined_headers.push((name.as_str(), value.clone())),
+        }
+    }
+    let headers = create_table_with_capacity(luau, 0, combined_headers.len())?;
+    for (name, value) in combined_headers {
+        headers.raw_set(name, value)?;
+    }
+    headers.set_readonly(true);
+
+    // bodies that aren't valid utf-8 (images, archives, etc.) are passed along as buffers so
+    // handlers can't accidentally mangle them by treating them as text
+    let body = if std::str::from_utf8(&request.body).is_ok() {
+        LuaValue::String(luau.create_string(&request.body)?)
+    } else {
+        LuaValue::Buffer(luau.create_buffer(&request.body)?)
+    };
+
+    let mut raw_text = request.head.clone();
+    raw_text.extend_from_slice(&request.body);
+
+    params.set_readonly(true);
+
+    TableBuilder::create(luau)?
+        .with_value("peer_address", peer_address)?
+        .with_value("method", request.method.as_str())?
+        .with_value("path", request.path.as_str())?
+        .with_value("http_version", if request.minor_version == 0 { "HTTP/1.0" } else { "HTTP/1.1" })?
+        .with_value("params", params)?
+        .with_value("route", route)?
+        .with_value("headers", headers)?
+        .with_value("body", body)?
+        .with_value("raw_text", luau.create_string(&raw_text)?)?
+        .build_readonly()
+}
+
+fn respond(luau: &Lua, request: &ParsedRequest, peer_address: &str, service: &Service) -> LuaResult<Response> {
+    let function_name = "server.serve(config: ServeConfig)";
+
+    let params_table = luau.create_table()?;
+    let (endpoint, route) = match &service.handler {
+        Handler::Function(f) => (f.clone(), None),
+        Handler::Routes(router) => match router.find(&request.method, &request.path) {
+            RouteMatch::Found { route, params } => {
+                for (name, value) in params {
+                    params_table.raw_set(name, value)?;
+                }
+                (route.handler.clone(), Some(route.pattern.clone()))
+            },
+            RouteMatch::MethodNotAllowed(allowed) => {
+                let allow = allowed.join(", ");
+                (fallback_handler(luau, "405 Method Not Allowed", Some(allow))?, None)
+            },
+            RouteMatch::NotFound => {
+                (fallback_handler(luau, "404 Not Found", None)?, None)
+            }
+        }
+    };
+
+    let serve_request_info = request_table(luau, request, peer_address, params_table, route)?;
+
+    match router::call_with_middleware(luau, &service.middleware, 0, LuaValue::Table(serve_request_info), &endpoint) {
+        Ok(LuaValue::Table(serve_response)) => Response::from_table(serve_response, function_name),
+        Ok(other) => wrap_err!("{}: handler (or middleware) should return a ServeResponse table, got: {:#?}", function_name, other),
+        Err(err) => wrap_err!("{}: handler (or middleware) call failed with error: {}", function_name, err),
+    }
+}
+
+/// Handler used when a route table has no route for the request, so middleware
+/// (loggers, etc.) still sees and can rewrite 404s and 405s
+fn fallback_handler(luau: &Lua, status_code: &'static str, allow: Option<String>) -> LuaResult<LuaFunction> {
+    luau.create_function(move |luau, _request: LuaValue| {
+        let headers = luau.create_table()?;
+        if let Some(allow) = &allow {
+            headers.raw_set("Allow", allow.as_str())?;
+        }
+        TableBuilder::create(luau)?
+            .with_value("status_code", status_code)?
+            .with_value("content_type", "text")?
+            .with_value("body", status_code)?
+            .with_value("headers", headers)?
+            .build()
+    })
+}
+
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::create(luau)?
+        .with_function_and_signature("serve", server_serve, c"net.http.server.serve(config: ServeConfig)")?
+        .build_readonly()
+}
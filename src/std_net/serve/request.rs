@@ -0,0 +1,327 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// httparse needs a fixed number of header slots up front; more than this gets a 431
+const MAX_HEADER_COUNT: usize = 128;
+/// chunk size lines are just a hex number and optional extensions, they shouldn't be long
+const MAX_CHUNK_LINE_SIZE: u64 = 4096;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// max size of the request line + headers (and chunked trailers)
+    pub max_header_size: u64,
+    pub max_body_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_header_size: 64 * 1024,
+            // same default as http.request's max_body_size
+            max_body_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsedRequest {
+    pub method: String,
+    /// request target as sent, including any query string
+    pub path: String,
+    /// 0 for HTTP/1.0, 1 for HTTP/1.1
+    pub minor_version: u8,
+    /// lowercased header names in the order they were received; duplicates are kept
+    pub headers: Vec<(String, String)>,
+    /// the raw request line + headers
+    pub head: Vec<u8>,
+    pub body: Vec<u8>,
+    /// whether the client wants (and is allowed) to send another request on this connection
+    pub keep_alive: bool,
+}
+
+#[derive(Debug)]
+pub enum RequestError {
+    /// client closed the connection (or idled past the keep-alive timeout) before sending a request
+    Closed,
+    Io(io::Error),
+    BadRequest(String),
+    HeadersTooLarge,
+    BodyTooLarge,
+    UnsupportedTransferEncoding(String),
+    UnsupportedVersion,
+}
+
+impl ParsedRequest {
+    /// First value of a header; `name` must be lowercase
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl RequestError {
+    /// status line to respond with, if the client should get a response at all
+    pub fn status(&self) -> Option<&'static str> {
+        match self {
+            Self::Closed | Self::Io(_) => None,
+            Self::BadRequest(_) => Some("400 Bad Request"),
+            Self::HeadersTooLarge => Some("431 Request Header Fields Too Large"),
+            Self::BodyTooLarge => Some("413 Content Too Large"),
+            Self::UnsupportedTransferEncoding(_) => Some("501 Not Implemented"),
+            Self::UnsupportedVersion => Some("505 HTTP Version Not Supported"),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::Closed => String::from("connection closed"),
+            Self::Io(err) => format!("io error: {}", err),
+            Self::BadRequest(reason) => format!("bad request: {}", reason),
+            Self::HeadersTooLarge => String::from("request headers too large"),
+            Self::BodyTooLarge => String::from("request body too large"),
+            Self::UnsupportedTransferEncoding(encoding) => format!("unsupported transfer-encoding: {}", encoding),
+            Self::UnsupportedVersion => String::from("only HTTP/1.0 and HTTP/1.1 are supported"),
+        }
+    }
+}
+
+impl From<io::Error> for RequestError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => Self::BadRequest(String::from("connection closed mid-request")),
+            _ => Self::Io(err),
+        }
+    }
+}
+
+/// Reads a line (including its `\n`) into `buf`, reading at most `limit` bytes; returns how many bytes were read.
+fn read_line_limited<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>, limit: u64) -> io::Result<usize> {
+    reader.by_ref().take(limit).read_until(b'\n', buf)
+}
+
+/// Reads the request line and headers up to and including the empty line that ends them.
+fn read_head<R: BufRead>(reader: &mut R, limits: &Limits) -> Result<Vec<u8>, RequestError> {
+    let mut head: Vec<u8> = Vec::new();
+    loop {
+        let remaining = limits.max_header_size.saturating_sub(head.len() as u64);
+        if remaining == 0 {
+            return Err(RequestError::HeadersTooLarge);
+        }
+        let line_start = head.len();
+        let read = match read_line_limited(reader, &mut head, remaining) {
+            Ok(read) => read,
+            // nothing read yet means the client went quiet between requests, not mid-request
+            Err(err) if head.is_empty() && matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::ConnectionReset) => {
+                return Err(RequestError::Closed);
+            },
+            Err(err) => return Err(err.into()),
+        };
+        if read == 0 {
+            return if head.is_empty() {
+                Err(RequestError::Closed)
+            } else {
+                Err(RequestError::BadRequest(String::from("connection closed before end of headers")))
+            };
+        }
+        if !head.ends_with(b"\n") {
+            // take() cut the line off before its newline
+            return Err(RequestError::HeadersTooLarge);
+        }
+
+        let line = &head[line_start..];
+        let is_empty_line = line == b"\r\n" || line == b"\n";
+        if is_empty_line && line_start == 0 {
+            // RFC 9112 2.2: servers should ignore at least one empty line before the request line
+            head.clear();
+            continue;
+        }
+        if is_empty_line {
+            return Ok(head);
+        }
+    }
+}
+
+/// Comma separated header values like `Connection: keep-alive, Upgrade` as lowercase tokens
+fn header_tokens<'h>(headers: &'h [(String, String)], name: &str) -> impl Iterator<Item = String> + 'h {
+    let name = name.to_string();
+    headers.iter()
+        .filter(move |(header, _)| *header == name)
+        .flat_map(|(_, value)| value.split(','))
+        .map(|token| token.trim().to_ascii_lowercase())
+        .filter(|token| !token.is_empty())
+}
+
+enum BodyKind {
+    None,
+    ContentLength(u64),
+    Chunked,
+}
+
+fn body_kind(headers: &[(String, String)]) -> Result<BodyKind, RequestError> {
+    let transfer_encodings: Vec<String> = header_tokens(headers, "transfer-encoding").collect();
+    if let Some(last) = transfer_encodings.last() {
+        // chunked must be the final encoding for requests (RFC 9112 6.3); we don't
+        // decompress gzip etc. ourselves so anything besides plain chunked is unsupported
+        return if last == "chunked" && transfer_encodings.len() == 1 {
+            Ok(BodyKind::Chunked)
+        } else if last == "chunked" {
+            Err(RequestError::UnsupportedTransferEncoding(transfer_encodings.join(", ")))
+        } else {
+            Err(RequestError::BadRequest(format!("transfer-encoding '{}' doesn't end with chunked", transfer_encodings.join(", "))))
+        };
+    }
+
+    let mut content_length: Option<u64> = None;
+    for value in headers.iter().filter(|(name, _)| name == "content-length").flat_map(|(_, value)| value.split(',')) {
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RequestError::BadRequest(format!("invalid content-length '{}'", value)));
+        }
+        let Ok(length) = value.parse::<u64>() else {
+            return Err(RequestError::BadRequest(format!("content-length '{}' out of range", value)));
+        };
+        match content_length {
+            Some(existing) if existing != length => {
+                return Err(RequestError::BadRequest(String::from("conflicting content-length headers")));
+            },
+            _ => content_length = Some(length),
+        }
+    }
+
+    Ok(match content_length {
+        Some(0) | None => BodyKind::None,
+        Some(length) => BodyKind::ContentLength(length),
+    })
+}
+
+fn read_chunked_body<R: BufRead>(reader: &mut R, limits: &Limits) -> Result<Vec<u8>, RequestError> {
+    let mut body: Vec<u8> = Vec::new();
+    let mut line: Vec<u8> = Vec::new();
+    loop {
+        line.clear();
+        read_line_limited(reader, &mut line, MAX_CHUNK_LINE_SIZE)?;
+        if !line.ends_with(b"\n") {
+            return Err(RequestError::BadRequest(String::from("malformed chunk size line")));
+        }
+        let line_str = String::from_utf8_lossy(&line);
+        // chunk extensions (;name=value) are allowed and ignored
+        let size_str = line_str.split(';').next().unwrap_or_default().trim();
+        let Ok(size) = u64::from_str_radix(size_str, 16) else {
+            return Err(RequestError::BadRequest(format!("invalid chunk size '{}'", size_str)));
+        };
+
+        if size == 0 {
+            break;
+        }
+        if (body.len() as u64).saturating_add(size) > limits.max_body_size {
+            return Err(RequestError::BodyTooLarge);
+        }
+
+        let start = body.len();
+        body.resize(start + size as usize, 0);
+        reader.read_exact(&mut body[start..])?;
+
+        let mut crlf = [0u8; 2];
+        reader.read_exact(&mut crlf)?;
+        if &crlf != b"\r\n" {
+            return Err(RequestError::BadRequest(String::from("chunk data not followed by CRLF")));
+        }
+    }
+
+    // trailer fields; we don't expose these but still have to consume them
+    let mut trailer_size: u64 = 0;
+    loop {
+        line.clear();
+        let read = read_line_limited(reader, &mut line, MAX_CHUNK_LINE_SIZE)?;
+        if read == 0 || !line.ends_with(b"\n") {
+            return Err(RequestError::BadRequest(String::from("malformed chunked trailer")));
+        }
+        trailer_size += read as u64;
+        if trailer_size > limits.max_header_size {
+            return Err(RequestError::HeadersTooLarge);
+        }
+        if line == b"\r\n" || line == b"\n" {
+            break;
+        }
+    }
+
+    Ok(body)
+}
+
+/// Reads one request from `reader`, answering `Expect: 100-continue` on the underlying stream if needed.
+pub fn read_request<S: Read + Write>(reader: &mut BufReader<S>, limits: &Limits) -> Result<ParsedRequest, RequestError> {
+    let head = read_head(reader, limits)?;
+
+    let mut header_slots = [httparse::EMPTY_HEADER; MAX_HEADER_COUNT];
+    let mut parsed = httparse::Request::new(&mut header_slots);
+    match parsed.parse(&head) {
+        Ok(httparse::Status::Complete(_)) => {},
+        Ok(httparse::Status::Partial) => {
+            return Err(RequestError::BadRequest(String::from("incomplete request head")));
+        },
+        Err(httparse::Error::TooManyHeaders) => return Err(RequestError::HeadersTooLarge),
+        Err(httparse::Error::Version) => return Err(RequestError::UnsupportedVersion),
+        Err(err) => return Err(RequestError::BadRequest(err.to_string())),
+    }
+
+    let (Some(method), Some(path), Some(minor_version)) = (parsed.method, parsed.path, parsed.version) else {
+        return Err(RequestError::BadRequest(String::from("incomplete request line")));
+    };
+    let method = method.to_string();
+    let path = path.to_string();
+
+    let headers: Vec<(String, String)> = parsed.headers.iter()
+        .map(|header| (header.name.to_ascii_lowercase(), String::from_utf8_lossy(header.value).into_owned()))
+        .collect();
+
+    if minor_version == 1 && !headers.iter().any(|(name, _)| name == "host") {
+        return Err(RequestError::BadRequest(String::from("HTTP/1.1 requests must have a host header")));
+    }
+
+    let connection: Vec<String> = header_tokens(&headers, "connection").collect();
+    let mut keep_alive = if minor_version == 0 {
+        connection.iter().any(|token| token == "keep-alive")
+    } else {
+        !connection.iter().any(|token| token == "close")
+    };
+
+    let has_transfer_encoding = headers.iter().any(|(name, _)| name == "transfer-encoding");
+    let has_content_length = headers.iter().any(|(name, _)| name == "content-length");
+    if has_transfer_encoding && has_content_length {
+        // request smuggling vector; transfer-encoding wins but we can't trust the connection afterwards
+        keep_alive = false;
+    }
+
+    let kind = body_kind(&headers)?;
+
+    if let BodyKind::ContentLength(length) = kind && length > limits.max_body_size {
+        return Err(RequestError::BodyTooLarge);
+    }
+
+    let expects_continue = header_tokens(&headers, "expect").any(|token| token == "100-continue");
+    if expects_continue && minor_version == 1 && !matches!(kind, BodyKind::None) {
+        let stream = reader.get_mut();
+        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        stream.flush()?;
+    }
+
+    let body = match kind {
+        BodyKind::None => Vec::new(),
+        BodyKind::ContentLength(length) => {
+            let mut body = vec![0u8; length as usize];
+            reader.read_exact(&mut body)?;
+            body
+        },
+        BodyKind::Chunked => read_chunked_body(reader, limits)?,
+    };
+
+    Ok(ParsedRequest {
+        method,
+        path,
+        minor_version,
+        headers,
+        head,
+        body,
+        keep_alive,
+    })
+}
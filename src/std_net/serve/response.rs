@@ -0,0 +1,427 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use ureq::http::StatusCode;
+
+use super::mime;
+
+/// Headers seal computes itself; handlers setting these would produce a malformed response
+const MANAGED_HEADERS: [&str; 3] = ["content-length", "transfer-encoding", "connection"];
+
+#[derive(Debug)]
+pub enum Body {
+    Bytes(Vec<u8>),
+    /// `ServeResponse.file`; streamed from disk when the response is written so large files
+    /// never have to fit in memory
+    File { file: File, len: u64 },
+    /// `ServeResponse.body` as an iterator function; called until it returns nil, with
+    /// each chunk sent as it's produced (using chunked transfer encoding for HTTP/1.1 clients)
+    Chunks(LuaFunction),
+}
+
+/// A `ServeResponse` table converted into what we'll actually write to the client
+#[derive(Debug)]
+pub struct Response {
+    /// "200 OK", "404 Not Found", etc.
+    pub status: String,
+    pub content_type: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Body,
+    /// handler explicitly asked to close the connection with `Connection: close`
+    pub close: bool,
+    /// `ServeResponse.websocket`; called with the upgraded connection if the request was a websocket handshake
+    pub websocket: Option<LuaFunction>,
+}
+
+/// What the request asked for that affects how the response gets written
+pub struct WriteOptions<'a> {
+    pub keep_alive: bool,
+    /// HEAD requests get headers (including the Content-Length the body would have had) but no body
+    pub head_only: bool,
+    /// the request's `Range` header, only used for file responses
+    pub range: Option<&'a str>,
+    /// whether the client understands chunked transfer encoding (HTTP/1.1)
+    pub chunked: bool,
+}
+
+impl WriteOptions<'_> {
+    /// For responses seal sends itself before a request could be parsed
+    pub fn closing() -> Self {
+        Self { keep_alive: false, head_only: false, range: None, chunked: false }
+    }
+}
+
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    /// a body iterator function errored or returned something that isn't a chunk
+    Luau(LuaError),
+}
+
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// no (usable) range requested; send the whole thing
+    Full,
+    /// inclusive start and end offsets
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parses a single `Range: bytes=start-end` (or `bytes=start-` or `bytes=-suffix_length`) header.
+/// Malformed and multi-range headers are ignored, which RFC 9110 14.2 allows.
+fn parse_range(header: &str, len: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    match (start.trim(), end.trim()) {
+        ("", suffix_length) => {
+            let Ok(suffix_length) = suffix_length.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            if suffix_length == 0 || len == 0 {
+                return ByteRange::Unsatisfiable;
+            }
+            ByteRange::Partial(len.saturating_sub(suffix_length), len - 1)
+        },
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            if start >= len {
+                return ByteRange::Unsatisfiable;
+            }
+            let end = if end.is_empty() {
+                len - 1
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.min(len - 1),
+                    _ => return ByteRange::Full,
+                }
+            };
+            ByteRange::Partial(start, end)
+        }
+    }
+}
+
+fn expand_content_type(content_type: &str) -> String {
+    match content_type.to_lowercase().as_str() {
+        "text" => "text/plain; charset=utf-8".to_string(),
+        "html" => "text/html; charset=utf-8".to_string(),
+        "json" => "application/json".to_string(),
+        "xml"  => "application/xml".to_string(),
+        "css"  => "text/css".to_string(),
+        "javascript" => "text/javascript; charset=utf-8".to_string(),
+        "binary" => "application/octet-stream".to_string(),
+        _ => content_type.to_string(),
+    }
+}
+
+/// `status_code` can be a full status like `"200 OK"`, a bare code like `"200"`, or a number;
+/// bare codes get `reason_phrase` or the canonical reason for that code
+fn status_line(status_code: LuaValue, reason_phrase: Option<String>, function_name: &'static str) -> LuaResult<String> {
+    let status = match status_code {
+        LuaValue::String(status) => status.to_string_lossy(),
+        LuaValue::Integer(code) => code.to_string(),
+        LuaValue::Number(code) if code.fract() == 0.0 => format!("{}", code as i64),
+        LuaNil => {
+            return wrap_err!("{}: ServeResponse missing 'status_code' (like \"200 OK\" or 404)", function_name);
+        },
+        other => {
+            return wrap_err!("{}: expected ServeResponse.status_code to be a string like \"200 OK\" or a number, got: {:?}", function_name, other);
+        }
+    };
+
+    let (code, reason) = match status.trim().split_once(' ') {
+        Some((code, reason)) => (code, Some(reason.trim().to_string())),
+        None => (status.trim(), None),
+    };
+
+    let status_code = match code.parse::<u16>().ok().and_then(|code| StatusCode::from_u16(code).ok()) {
+        Some(status_code) => status_code,
+        None => {
+            return wrap_err!("{}: ServeResponse.status_code '{}' doesn't start with a valid HTTP status code", function_name, status);
+        }
+    };
+
+    let reason = reason_phrase
+        .or(reason)
+        .or_else(|| status_code.canonical_reason().map(String::from))
+        .unwrap_or_default();
+
+    Ok(format!("{} {}", status_code.as_u16(), reason))
+}
+
+impl Response {
+    pub fn from_table(serve_response: LuaTable, function_name: &'static str) -> LuaResult<Self> {
+        let reason_phrase: Option<String> = match serve_response.raw_get("reason_phrase")? {
+            LuaValue::String(reason) => Some(reason.to_string_lossy()),
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected ServeResponse.reason_phrase to be a string or nil, got: {:?}", function_name, other);
+            }
+        };
+        let websocket = match serve_response.raw_get("websocket")? {
+            LuaValue::Function(on_upgrade) => Some(on_upgrade),
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected ServeResponse.websocket to be a function (socket: Websocket) -> () or nil, got: {:?}", function_name, other);
+            }
+        };
+        let status = match serve_response.raw_get("status_code")? {
+            // the status is always 101 for upgrades so there's no point making handlers spell it out
+            LuaNil if websocket.is_some() => String::from("101 Switching Protocols"),
+            status_code => status_line(status_code, reason_phrase, function_name)?,
+        };
+
+        let file_path = match serve_response.raw_get("file")? {
+            LuaValue::String(path) => Some(PathBuf::from(path.to_string_lossy())),
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected ServeResponse.file to be a path (string) or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let content_type = match serve_response.raw_get("content_type")? {
+            LuaValue::String(content_type) => expand_content_type(&content_type.to_string_lossy()),
+            LuaNil => match &file_path {
+                Some(path) => mime::from_path(path).to_string(),
+                None => String::from("text/plain; charset=utf-8"),
+            },
+            other => {
+                return wrap_err!("{}: expected ServeResponse.content_type to be a string, got: {:?}", function_name, other);
+            }
+        };
+
+        let body = match (serve_response.raw_get("body")?, file_path) {
+            (LuaNil, Some(path)) => {
+                let file = match File::open(&path) {
+                    Ok(file) => file,
+                    Err(err) => {
+                        return wrap_err!("{}: unable to open ServeResponse.file '{}' due to err: {}", function_name, path.display(), err);
+                    }
+                };
+                let len = match file.metadata() {
+                    Ok(metadata) if metadata.is_file() => metadata.len(),
+                    Ok(_) => {
+                        return wrap_err!("{}: ServeResponse.file '{}' isn't a file", function_name, path.display());
+                    },
+                    Err(err) => {
+                        return wrap_err!("{}: unable to read metadata of ServeResponse.file '{}' due to err: {}", function_name, path.display(), err);
+                    }
+                };
+                Body::File { file, len }
+            },
+            (_, Some(_)) => {
+                return wrap_err!("{}: ServeResponse can have either a body or a file, not both", function_name);
+            },
+            (LuaValue::String(body), None) => Body::Bytes(body.as_bytes().to_vec()),
+            (LuaValue::Buffer(buffy), None) => Body::Bytes(buffy.to_vec()),
+            (LuaValue::Function(iterator), None) => Body::Chunks(iterator),
+            (LuaNil, None) => Body::Bytes(Vec::new()),
+            (other, None) => {
+                return wrap_err!("{}: expected ServeResponse.body to be a string, buffer, or iterator function, got: {:?}", function_name, other);
+            }
+        };
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut close = false;
+        match serve_response.raw_get("headers")? {
+            LuaValue::Table(headers_table) => {
+                for pair in headers_table.pairs::<LuaString, LuaString>() {
+                    let (key, value) = pair?;
+                    let (key, value) = (key.to_string_lossy(), value.to_string_lossy());
+                    if key.contains(['\r', '\n', ':']) || value.contains(['\r', '\n']) {
+                        return wrap_err!("{}: ServeResponse header '{}' contains newlines or colons", function_name, key);
+                    }
+                    let lowercase_key = key.to_ascii_lowercase();
+                    if lowercase_key == "connection" && value.to_ascii_lowercase().contains("close") {
+                        close = true;
+                    }
+                    if MANAGED_HEADERS.contains(&lowercase_key.as_str()) {
+                        continue;
+                    }
+                    headers.push((key, value));
+                }
+            },
+            LuaNil => {},
+            other => {
+                return wrap_err!("{}: expected ServeResponse.headers to be a table of strings or nil, got: {:?}", function_name, other);
+            }
+        }
+
+        match serve_response.raw_get("cookies")? {
+            LuaValue::Table(cookies_table) => {
+                for pair in cookies_table.pairs::<LuaString, LuaString>() {
+                    let (key, value) = pair?;
+                    headers.push((String::from("Set-Cookie"), format!("{}={}", key.to_string_lossy(), value.to_string_lossy())));
+                }
+            },
+            LuaNil => {},
+            other => {
+                return wrap_err!("{}: expected ServeResponse.cookies to be a table of strings or nil, got: {:?}", function_name, other);
+            }
+        }
+
+        match serve_response.raw_get("redirect_url")? {
+            LuaValue::String(url) => headers.push((String::from("Location"), url.to_string_lossy())),
+            LuaNil => {},
+            other => {
+                return wrap_err!("{}: expected ServeResponse.redirect_url to be a string or nil, got: {:?}", function_name, other);
+            }
+        }
+
+        Ok(Self { status, content_type, headers, body, close, websocket })
+    }
+
+    /// Plain text response for errors seal answers itself (bad requests, missing routes, etc.)
+    pub fn text(status: &str, body: impl Into<String>) -> Self {
+        Self {
+            status: status.to_string(),
+            content_type: String::from("text/plain; charset=utf-8"),
+            headers: Vec::new(),
+            body: Body::Bytes(body.into().into_bytes()),
+            close: false,
+            websocket: None,
+        }
+    }
+
+    /// Chunked responses have no length, so HTTP/1.0 clients only know they're done when we close the connection
+    pub fn is_chunked(&self) -> bool {
+        matches!(self.body, Body::Chunks(_))
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W, options: &WriteOptions) -> Result<(), WriteError> {
+        // 1xx, 204, and 304 responses can't have a body (RFC 9110 6.4.1)
+        let bodiless = self.status.starts_with('1') || self.status.starts_with("204") || self.status.starts_with("304");
+
+        let mut status = self.status.as_str();
+        let mut extra_headers: Vec<(&str, String)> = Vec::new();
+        // (offset, length) of the file to send
+        let mut file_section = (0, 0);
+        let content_length = match &self.body {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::File { len, .. } => {
+                extra_headers.push(("Accept-Ranges", String::from("bytes")));
+                let range = match options.range {
+                    Some(range) if status.starts_with("200") => parse_range(range, *len),
+                    _ => ByteRange::Full,
+                };
+                match range {
+                    ByteRange::Full => {
+                        file_section = (0, *len);
+                        Some(*len)
+                    },
+                    ByteRange::Partial(start, end) => {
+                        status = "206 Partial Content";
+                        extra_headers.push(("Content-Range", format!("bytes {}-{}/{}", start, end, len)));
+                        file_section = (start, end - start + 1);
+                        Some(end - start + 1)
+                    },
+                    ByteRange::Unsatisfiable => {
+                        status = "416 Range Not Satisfiable";
+                        extra_headers.push(("Content-Range", format!("bytes */{}", len)));
+                        Some(0)
+                    }
+                }
+            },
+            Body::Chunks(_) => {
+                if options.chunked {
+                    extra_headers.push(("Transfer-Encoding", String::from("chunked")));
+                }
+                None
+            }
+        };
+
+        let mut head = format!("HTTP/1.1 {}\r\n", status);
+        if !bodiless {
+            head.push_str(&format!("Content-Type: {}\r\n", self.content_type));
+        }
+        for (key, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        if !bodiless {
+            for (key, value) in &extra_headers {
+                head.push_str(&format!("{}: {}\r\n", key, value));
+            }
+            if let Some(content_length) = content_length {
+                head.push_str(&format!("Content-Length: {}\r\n", content_length));
+            }
+        }
+        head.push_str(if options.keep_alive { "Connection: keep-alive\r\n" } else { "Connection: close\r\n" });
+        head.push_str("\r\n");
+
+        writer.write_all(head.as_bytes())?;
+        if options.head_only || bodiless {
+            writer.flush()?;
+            return Ok(());
+        }
+
+        match &self.body {
+            Body::Bytes(bytes) => writer.write_all(bytes)?,
+            Body::File { file, .. } => {
+                let (offset, length) = file_section;
+                let mut file = file;
+                file.seek(SeekFrom::Start(offset))?;
+                let copied = io::copy(&mut file.take(length), writer)?;
+                if copied < length {
+                    // file shrank after we sent its Content-Length; the only honest thing left to do is hang up
+                    return Err(WriteError::Io(io::Error::new(io::ErrorKind::UnexpectedEof, "file shrank while being sent")));
+                }
+            },
+            Body::Chunks(iterator) => write_chunks(writer, iterator, options.chunked)?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn write_chunks<W: Write>(writer: &mut W, iterator: &LuaFunction, encode: bool) -> Result<(), WriteError> {
+    let function_name = "server.serve(config: ServeConfig)";
+    loop {
+        let chunk = match iterator.call::<LuaValue>(()) {
+            Ok(LuaValue::String(chunk)) => chunk.as_bytes().to_vec(),
+            Ok(LuaValue::Buffer(buffy)) => buffy.to_vec(),
+            Ok(LuaNil) => break,
+            Ok(other) => {
+                return wrap_err!("{}: expected ServeResponse.body iterator to return a string, buffer, or nil (to finish), got: {:?}", function_name, other)
+                    .map_err(WriteError::Luau);
+            },
+            Err(err) => {
+                return wrap_err!("{}: ServeResponse.body iterator errored: {}", function_name, err)
+                    .map_err(WriteError::Luau);
+            }
+        };
+        // a zero-length chunk would end the response early
+        if chunk.is_empty() {
+            continue;
+        }
+        if encode {
+            writer.write_all(format!("{:X}\r\n", chunk.len()).as_bytes())?;
+            writer.write_all(&chunk)?;
+            writer.write_all(b"\r\n")?;
+        } else {
+            writer.write_all(&chunk)?;
+        }
+        // send each chunk as soon as it's ready so clients tailing logs see them right away
+        writer.flush()?;
+    }
+    if encode {
+        writer.write_all(b"0\r\n\r\n")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,256 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::cmp::Ordering;
+
+/// One `/`-separated piece of a route pattern like `/users/:id/files/*path`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// must match the request path's segment exactly
+    Static(String),
+    /// `:name` matches any single segment and captures it as `params.name`
+    Param(String),
+    /// `*name` matches the rest of the path (zero or more segments) and captures it as `params.name`;
+    /// a bare `*` captures as `params["*"]`
+    Wildcard(String),
+}
+
+impl Segment {
+    /// lower rank = more specific; used to order routes so `/users/me` beats `/users/:id` beats `/users/*`
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Static(_) => 0,
+            Self::Param(_) => 1,
+            Self::Wildcard(_) => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// `None` matches any method (`"* /path"` or just `"/path"`)
+    method: Option<String>,
+    segments: Vec<Segment>,
+    /// the route table key as the user wrote it; exposed as `request.route`
+    pub pattern: String,
+    pub handler: LuaFunction,
+}
+
+impl Route {
+    fn parse(pattern: String, handler: LuaFunction, function_name: &'static str) -> LuaResult<Self> {
+        let (method, path) = match pattern.trim().split_once(char::is_whitespace) {
+            Some((method, path)) => (method.trim(), path.trim()),
+            None => ("*", pattern.trim()),
+        };
+
+        let method = match method {
+            "*" | "ANY" | "any" => None,
+            method if !method.is_empty() && method.chars().all(|c| c.is_ascii_alphabetic()) => Some(method.to_ascii_uppercase()),
+            other => {
+                return wrap_err!("{}: route '{}' has an invalid method '{}'; expected something like \"GET /path\" or \"* /path\"", function_name, pattern, other);
+            }
+        };
+
+        if !path.starts_with('/') {
+            return wrap_err!("{}: route '{}' has a path that doesn't start with '/'", function_name, pattern);
+        }
+
+        let mut segments = Vec::new();
+        let raw_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        for (index, raw_segment) in raw_segments.iter().enumerate() {
+            let segment = if let Some(name) = raw_segment.strip_prefix(':') {
+                if name.is_empty() {
+                    return wrap_err!("{}: route '{}' has a ':' parameter without a name (expected something like ':id')", function_name, pattern);
+                }
+                Segment::Param(name.to_string())
+            } else if let Some(name) = raw_segment.strip_prefix('*') {
+                if index != raw_segments.len() - 1 {
+                    return wrap_err!("{}: route '{}' has a '*' wildcard that isn't the last segment of the path", function_name, pattern);
+                }
+                Segment::Wildcard(if name.is_empty() { String::from("*") } else { name.to_string() })
+            } else {
+                Segment::Static(raw_segment.to_string())
+            };
+            segments.push(segment);
+        }
+
+        Ok(Self { method, segments, pattern, handler })
+    }
+
+    /// Returns the captured params if `path_segments` matches this route's pattern, ignoring method.
+    fn captures(&self, path_segments: &[&str]) -> Option<Vec<(String, String)>> {
+        let mut params = Vec::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Static(expected) => {
+                    if path_segments.get(index) != Some(&expected.as_str()) {
+                        return None;
+                    }
+                },
+                Segment::Param(name) => {
+                    let value = path_segments.get(index)?;
+                    params.push((name.clone(), percent_decode(value)));
+                },
+                Segment::Wildcard(name) => {
+                    let rest = path_segments.get(index..).unwrap_or_default();
+                    let rest: Vec<String> = rest.iter().map(|s| percent_decode(s)).collect();
+                    params.push((name.clone(), rest.join("/")));
+                    return Some(params);
+                }
+            }
+        }
+        if path_segments.len() == self.segments.len() {
+            Some(params)
+        } else {
+            None
+        }
+    }
+
+    fn matches_method(&self, method: &str) -> bool {
+        match &self.method {
+            None => true,
+            Some(expected) => expected == method,
+        }
+    }
+
+    /// more specific routes sort first; compares segment by segment (where the end of a shorter
+    /// pattern ranks like a param so `/users` still beats `/users/*rest`), then prefers routes
+    /// with an explicit method over any-method routes
+    fn specificity(&self, other: &Self) -> Ordering {
+        const END_OF_PATTERN: u8 = 1;
+        let longest = self.segments.len().max(other.segments.len());
+        for index in 0..longest {
+            let ours = self.segments.get(index).map_or(END_OF_PATTERN, Segment::rank);
+            let theirs = other.segments.get(index).map_or(END_OF_PATTERN, Segment::rank);
+            match ours.cmp(&theirs) {
+                Ordering::Equal => continue,
+                unequal => return unequal,
+            }
+        }
+        other.method.is_some().cmp(&self.method.is_some())
+            .then_with(|| self.pattern.cmp(&other.pattern))
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    match urlencoding::decode(s) {
+        Ok(decoded) => decoded.into_owned(),
+        Err(_) => s.to_string(),
+    }
+}
+
+pub enum RouteMatch<'r> {
+    Found {
+        route: &'r Route,
+        params: Vec<(String, String)>,
+    },
+    /// some route matched the path but not the method; contains the methods that would've matched
+    MethodNotAllowed(Vec<String>),
+    NotFound,
+}
+
+/// Route table passed as `ServeConfig.handler`, keyed by `"METHOD /path/:param/*wildcard"`
+#[derive(Debug, Clone)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    pub fn from_table(table: LuaTable, function_name: &'static str) -> LuaResult<Self> {
+        let mut routes = Vec::new();
+        for pair in table.pairs::<LuaValue, LuaValue>() {
+            let (key, value) = pair?;
+            let pattern = match key {
+                LuaValue::String(pattern) => pattern.to_string_lossy(),
+                other => {
+                    return wrap_err!("{}: expected route table keys to be strings like \"GET /users/:id\", got: {:?}", function_name, other);
+                }
+            };
+            let handler = match value {
+                LuaValue::Function(handler) => handler,
+                other => {
+                    return wrap_err!("{}: expected route '{}' to map to a handler function, got: {:?}", function_name, pattern, other);
+                }
+            };
+            routes.push(Route::parse(pattern, handler, function_name)?);
+        }
+
+        if routes.is_empty() {
+            return wrap_err!("{}: route table is empty; expected at least one route like [\"GET /\"] = function(request) ... end", function_name);
+        }
+
+        routes.sort_by(|a, b| a.specificity(b));
+
+        Ok(Self { routes })
+    }
+
+    /// `path` is the request target; any query string is ignored when matching
+    pub fn find(&self, method: &str, path: &str) -> RouteMatch<'_> {
+        let path = path.split_once('?').map(|(path, _query)| path).unwrap_or(path);
+        let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let mut allowed_methods: Vec<String> = Vec::new();
+        let mut get_for_head: Option<RouteMatch<'_>> = None;
+        for route in &self.routes {
+            let Some(params) = route.captures(&path_segments) else {
+                continue;
+            };
+            if route.matches_method(method) {
+                return RouteMatch::Found { route, params };
+            } else if method == "HEAD" && get_for_head.is_none() && route.matches_method("GET") {
+                // HEAD is GET without the body, so GET routes answer HEAD requests unless there's a HEAD route
+                get_for_head = Some(RouteMatch::Found { route, params });
+            } else if let Some(route_method) = &route.method && !allowed_methods.contains(route_method) {
+                allowed_methods.push(route_method.clone());
+            }
+        }
+
+        if let Some(found) = get_for_head {
+            return found;
+        }
+
+        if allowed_methods.is_empty() {
+            RouteMatch::NotFound
+        } else {
+            RouteMatch::MethodNotAllowed(allowed_methods)
+        }
+    }
+}
+
+/// Calls `middleware[index]` with `(request, next)`, where `next` continues down the chain and
+/// eventually calls `endpoint`; middleware can short-circuit by returning a response without
+/// calling `next`, or wrap the response `next` returns.
+pub fn call_with_middleware(luau: &Lua, middleware: &[LuaFunction], index: usize, request: LuaValue, endpoint: &LuaFunction) -> LuaValueResult {
+    let Some(current) = middleware.get(index) else {
+        return endpoint.call::<LuaValue>(request);
+    };
+
+    let remaining = middleware.to_vec();
+    let endpoint_for_next = endpoint.clone();
+    let next = luau.create_function(move |luau, request: LuaValue| {
+        call_with_middleware(luau, &remaining, index + 1, request, &endpoint_for_next)
+    })?;
+
+    current.call::<LuaValue>((request, next))
+}
+
+pub fn parse_middleware(value: LuaValue, function_name: &'static str) -> LuaResult<Vec<LuaFunction>> {
+    match value {
+        LuaNil => Ok(Vec::new()),
+        LuaValue::Table(list) => {
+            let mut middleware = Vec::with_capacity(list.raw_len());
+            for (index, value) in list.sequence_values::<LuaValue>().enumerate() {
+                match value? {
+                    LuaValue::Function(f) => middleware.push(f),
+                    other => {
+                        return wrap_err!("{}: expected middleware[{}] to be a function (request, next) -> ServeResponse, got: {:?}", function_name, index + 1, other);
+                    }
+                }
+            }
+            Ok(middleware)
+        },
+        other => {
+            wrap_err!("{}: expected middleware to be an array of functions or nil, got: {:?}", function_name, other)
+        }
+    }
+}
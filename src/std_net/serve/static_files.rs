@@ -0,0 +1,349 @@
+//! `seal serve <dir>`: serves a directory over HTTP with the same connection handling as
+//! `@std/net/http/server`, but without a Luau handler in the loop, so connections are served by a fixed pool
+//! of threads instead of one at a time.
+
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use crate::Args;
+use crate::std_archive::extract::unsafe_path_traversal;
+use crate::std_fs::file_size::FileSize;
+
+use std::fs::{self, File, Metadata};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use super::{ConnectionSettings, DEFAULT_KEEP_ALIVE_TIMEOUT, handle_client, mime};
+use super::request::{Limits, ParsedRequest};
+use super::response::{Body, Response};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8000;
+/// How many connections are served at once; idle keep-alive connections hold a thread until they time out,
+/// so this caps the threads (and memory) lots of them can tie up
+const CONNECTION_THREADS: usize = 64;
+/// How many accepted connections can wait for a free thread before the accept loop blocks
+const QUEUED_CONNECTIONS: usize = 256;
+
+struct ServeDirectoryOptions {
+    directory: PathBuf,
+    address: String,
+    port: u16,
+    listings: bool,
+}
+
+impl ServeDirectoryOptions {
+    fn from_args(mut args: Args, function_name: &'static str) -> LuaResult<Self> {
+        let mut directory: Option<PathBuf> = None;
+        let mut address = String::from(DEFAULT_ADDRESS);
+        let mut port = DEFAULT_PORT;
+        let mut listings = true;
+
+        while let Some(arg) = args.pop_front() {
+            let Some(arg) = arg.to_str() else {
+                return wrap_err!("{}: arguments must be valid utf-8", function_name);
+            };
+            match arg {
+                "--port" | "-p" => {
+                    let value = args.pop_front().and_then(|value| value.into_string().ok());
+                    port = match value.as_deref().map(str::parse::<u16>) {
+                        Some(Ok(port)) => port,
+                        _ => {
+                            return wrap_err!("{}: expected a port number after {}, got: {:?}", function_name, arg, value);
+                        }
+                    };
+                },
+                "--address" | "-a" => {
+                    address = match args.pop_front().and_then(|value| value.into_string().ok()) {
+                        Some(value) => value,
+                        None => {
+                            return wrap_err!("{}: expected an address (like 0.0.0.0) after {}", function_name, arg);
+                        }
+                    };
+                },
+                "--no-listing" => listings = false,
+                flag if flag.starts_with('-') => {
+                    return wrap_err!("{}: unknown flag '{}'; see seal serve --help", function_name, flag);
+                },
+                path => {
+                    if directory.is_some() {
+                        return wrap_err!("{}: expected one directory to serve, got another: '{}'", function_name, path);
+                    }
+                    directory = Some(PathBuf::from(path));
+                }
+            }
+        }
+
+        Ok(Self {
+            directory: directory.unwrap_or_else(|| PathBuf::from(".")),
+            address,
+            port,
+            listings,
+        })
+    }
+}
+
+/// The directory being served; `root` is canonicalized so we can check resolved request paths
+/// (including ones that go through symlinks) are still inside it
+struct StaticDirectory {
+    root: PathBuf,
+    listings: bool,
+}
+
+impl StaticDirectory {
+    fn respond(&self, request: &ParsedRequest) -> Response {
+        if request.method != "GET" && request.method != "HEAD" {
+            let mut response = Response::text("405 Method Not Allowed", "405 Method Not Allowed");
+            response.headers.push((String::from("Allow"), String::from("GET, HEAD")));
+            return response;
+        }
+
+        let (url_path, query) = match request.path.split_once('?') {
+            Some((url_path, query)) => (url_path, Some(query)),
+            None => (request.path.as_str(), None),
+        };
+        let decoded = match urlencoding::decode(url_path) {
+            Ok(decoded) if !decoded.contains('\0') => decoded,
+            _ => return Response::text("400 Bad Request", "400 Bad Request: invalid path"),
+        };
+
+        let relative: PathBuf = decoded.split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect();
+        if unsafe_path_traversal(&relative).is_some()
+            || !relative.components().all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Response::text("403 Forbidden", "403 Forbidden");
+        }
+
+        #[allow(clippy::disallowed_methods, reason = "relative was just checked to only contain normal components, so it can't clobber root")]
+        let full_path = self.root.join(&relative);
+        let resolved = match full_path.canonicalize() {
+            Ok(resolved) => resolved,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Response::text("404 Not Found", "404 Not Found"),
+            Err(_) => return Response::text("403 Forbidden", "403 Forbidden"),
+        };
+        // symlinks can point anywhere
+        if !resolved.starts_with(&self.root) {
+            return Response::text("403 Forbidden", "403 Forbidden");
+        }
+
+        if resolved.is_dir() {
+            // relative links in listings and index.html only resolve correctly with the trailing slash
+            if !url_path.ends_with('/') {
+                let mut location = format!("{}/", url_path);
+                if let Some(query) = query {
+                    location.push('?');
+                    location.push_str(query);
+                }
+                let mut response = Response::text("301 Moved Permanently", "");
+                response.headers.push((String::from("Location"), location));
+                return response;
+            }
+
+            #[allow(clippy::disallowed_methods, reason = "child is a literal that doesn't start with an absolute path separator")]
+            let index_path = resolved.join("index.html");
+            if index_path.is_file() {
+                return self.file_response(&index_path, request);
+            }
+            if !self.listings {
+                return Response::text("404 Not Found", "404 Not Found");
+            }
+            return self.listing(&resolved, &decoded);
+        }
+
+        self.file_response(&resolved, request)
+    }
+
+    fn file_response(&self, path: &Path, request: &ParsedRequest) -> Response {
+        let (file, metadata) = match File::open(path).and_then(|file| {
+            let metadata = file.metadata()?;
+            Ok((file, metadata))
+        }) {
+            Ok(opened) => opened,
+            Err(_) => return Response::text("403 Forbidden", "403 Forbidden"),
+        };
+
+        let (etag, last_modified) = validators(&metadata);
+        let mut headers = vec![
+            (String::from("ETag"), etag.clone()),
+            // dev server: let browsers cache but make them revalidate so edits show up on reload
+            (String::from("Cache-Control"), String::from("no-cache")),
+        ];
+        if let Some(last_modified) = &last_modified {
+            headers.push((String::from("Last-Modified"), last_modified.clone()));
+        }
+
+        // If-None-Match wins over If-Modified-Since when both are sent (RFC 9110 13.1.3)
+        let not_modified = match request.header("if-none-match") {
+            Some(tags) => tags.split(',').any(|tag| {
+                let tag = tag.trim();
+                tag == "*" || tag.trim_start_matches("W/") == etag
+            }),
+            None => matches!(
+                (request.header("if-modified-since"), &last_modified),
+                (Some(since), Some(last_modified)) if since.trim() == last_modified
+            ),
+        };
+        if not_modified {
+            return Response {
+                status: String::from("304 Not Modified"),
+                content_type: String::new(),
+                headers,
+                body: Body::Bytes(Vec::new()),
+                close: false,
+                websocket: None,
+            };
+        }
+
+        Response {
+            status: String::from("200 OK"),
+            content_type: mime::from_path(path).to_string(),
+            headers,
+            body: Body::File { len: metadata.len(), file },
+            close: false,
+            websocket: None,
+        }
+    }
+
+    fn listing(&self, directory: &Path, url_path: &str) -> Response {
+        let read_dir = match fs::read_dir(directory) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return Response::text("403 Forbidden", "403 Forbidden"),
+        };
+
+        // (name, is_dir, size)
+        let mut entries: Vec<(String, bool, u64)> = read_dir
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.file_name().to_string_lossy().into_owned(), metadata.is_dir(), metadata.len()))
+            })
+            .collect();
+        entries.sort_by(|(a_name, a_is_dir, _), (b_name, b_is_dir, _)| {
+            b_is_dir.cmp(a_is_dir).then_with(|| a_name.to_lowercase().cmp(&b_name.to_lowercase()))
+        });
+
+        let title = escape_html(url_path);
+        let mut rows = String::new();
+        if url_path != "/" {
+            rows.push_str("<tr><td><a href=\"../\">../</a></td><td></td></tr>\n");
+        }
+        for (name, is_dir, size) in entries {
+            let slash = if is_dir { "/" } else { "" };
+            let size = if is_dir { String::new() } else { FileSize::from_bytes(size).to_string() };
+            rows.push_str(&format!(
+                "<tr><td><a href=\"{}{}\">{}{}</a></td><td>{}</td></tr>\n",
+                urlencoding::encode(&name), slash, escape_html(&name), slash, size
+            ));
+        }
+
+        let body = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Index of {title}</title>\n\
+            <style>body {{ font-family: monospace; }} td {{ padding-right: 2em; }}</style>\n</head>\n\
+            <body>\n<h1>Index of {title}</h1>\n<table>\n{rows}</table>\n</body>\n</html>\n"
+        );
+
+        let mut response = Response::text("200 OK", body);
+        response.content_type = String::from("text/html; charset=utf-8");
+        response
+    }
+}
+
+/// (ETag, Last-Modified) for a file; the ETag changes whenever the file's size or mtime does
+fn validators(metadata: &Metadata) -> (String, Option<String>) {
+    let modified = metadata.modified().ok().and_then(|modified| jiff::Timestamp::try_from(modified).ok());
+    let etag = format!("\"{:x}-{:x}\"", metadata.len(), modified.map_or(0, |modified| modified.as_nanosecond()));
+    let last_modified = modified.map(|modified| modified.strftime("%a, %d %b %Y %H:%M:%S GMT").to_string());
+    (etag, last_modified)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Entry point for `seal serve [dir] [--port 8000] [--address 127.0.0.1] [--no-listing]`
+pub fn run(args: Args) -> LuaEmptyResult {
+    let function_name = "seal serve";
+    let options = ServeDirectoryOptions::from_args(args, function_name)?;
+
+    let root = match options.directory.canonicalize() {
+        Ok(root) if root.is_dir() => root,
+        Ok(_) => {
+            return wrap_err!("{}: '{}' isn't a directory", function_name, options.directory.display());
+        },
+        Err(err) => {
+            return wrap_err!("{}: unable to serve '{}' due to err: {}", function_name, options.directory.display(), err);
+        }
+    };
+
+    let address_port = format!("{}:{}", options.address, options.port);
+    let listener = match TcpListener::bind(&address_port) {
+        Ok(listener) => listener,
+        Err(err) => {
+            return wrap_err!("{}: failed to bind to {} with error: {}", function_name, address_port, err);
+        }
+    };
+
+    puts!("serving {} at http://{}/", root.display(), address_port)?;
+
+    let directory = Arc::new(StaticDirectory { root, listings: options.listings });
+    let settings = ConnectionSettings {
+        limits: Limits::default(),
+        keep_alive: true,
+        keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+        tls: None,
+    };
+
+    // no Luau VM involved, so connections don't have to wait on each other (up to CONNECTION_THREADS at once)
+    let (sender, receiver) = crossbeam_channel::bounded::<TcpStream>(QUEUED_CONNECTIONS);
+    for index in 0..CONNECTION_THREADS {
+        let receiver = receiver.clone();
+        let directory = Arc::clone(&directory);
+        let settings = settings.clone();
+        let spawn_result = thread::Builder::new()
+            .name(format!("seal-serve-connection-{}", index + 1))
+            .spawn(move || {
+                for stream in receiver.iter() {
+                    let _ = handle_client(stream, &settings, None, |request, peer_address| {
+                        let response = directory.respond(request);
+                        puts!("{} \"{} {}\" {}", peer_address, request.method, request.path, response.status)?;
+                        Ok(response)
+                    });
+                }
+            });
+        if let Err(err) = spawn_result {
+            return wrap_err!("{}: unable to spawn connection thread {} due to err: {}", function_name, index + 1, err);
+        }
+    }
+    // the connection threads hold the only receivers now
+    drop(receiver);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if sender.send(stream).is_err() {
+                    return wrap_err!("{}: every connection thread has exited", function_name);
+                }
+            },
+            Err(err) => {
+                eputs!("{}: connection failed: {}", function_name, err)?;
+            }
+        }
+    }
+
+    Ok(())
+}
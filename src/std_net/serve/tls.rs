@@ -0,0 +1,137 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use ureq::tls::PemItem;
+
+/// Reads a PEM file, returning its parsed items (certificates, private keys)
+fn read_pem(path: &str, field: &'static str, function_name: &'static str) -> LuaResult<Vec<PemItem<'static>>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return wrap_err!("{}: unable to read config.tls.{} at '{}' due to err: {}", function_name, field, path, err);
+        }
+    };
+    let mut items = Vec::new();
+    for item in ureq::tls::parse_pem(&bytes) {
+        match item {
+            Ok(item) => items.push(item),
+            Err(err) => {
+                return wrap_err!("{}: config.tls.{} at '{}' isn't a valid PEM file: {}", function_name, field, path, err);
+            }
+        }
+    }
+    Ok(items)
+}
+
+fn get_path(tls: &LuaTable, field: &'static str, function_name: &'static str) -> LuaResult<String> {
+    match tls.raw_get(field)? {
+        LuaValue::String(path) => Ok(path.to_string_lossy()),
+        other => {
+            wrap_err!("{} expected config.tls.{} to be a path to a PEM file, got: {:?}", function_name, field, other)
+        }
+    }
+}
+
+/// Builds a rustls `ServerConfig` from `ServeConfig.tls = { cert = path, key = path }`;
+/// `cert` can contain the whole chain (leaf first), `key` is the leaf's private key (PKCS#8, PKCS#1, or SEC1).
+pub fn server_config(value: LuaValue, function_name: &'static str) -> LuaResult<Arc<ServerConfig>> {
+    let tls = match value {
+        LuaValue::Table(tls) => tls,
+        other => {
+            return wrap_err!("{} expected config.tls to be a table {{ cert: string, key: string }} or nil, got: {:?}", function_name, other);
+        }
+    };
+
+    let cert_path = get_path(&tls, "cert", function_name)?;
+    let key_path = get_path(&tls, "key", function_name)?;
+
+    let cert_chain: Vec<CertificateDer<'static>> = read_pem(&cert_path, "cert", function_name)?
+        .into_iter()
+        .filter_map(|item| match item {
+            PemItem::Certificate(cert) => Some(CertificateDer::from(cert.der().to_vec())),
+            _ => None,
+        })
+        .collect();
+    if cert_chain.is_empty() {
+        return wrap_err!("{}: config.tls.cert at '{}' doesn't contain any PEM-encoded certificates", function_name, cert_path);
+    }
+
+    let key_der = read_pem(&key_path, "key", function_name)?
+        .into_iter()
+        .find_map(|item| match item {
+            PemItem::PrivateKey(key) => Some(key.der().to_vec()),
+            _ => None,
+        });
+    let key = match key_der.map(PrivateKeyDer::try_from) {
+        Some(Ok(key)) => key,
+        Some(Err(err)) => {
+            return wrap_err!("{}: unable to parse private key in config.tls.key at '{}': {}", function_name, key_path, err);
+        },
+        None => {
+            return wrap_err!("{}: config.tls.key at '{}' doesn't contain a PEM-encoded private key", function_name, key_path);
+        }
+    };
+
+    let provider = rustls::crypto::CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()));
+
+    let builder = match ServerConfig::builder_with_provider(provider).with_safe_default_protocol_versions() {
+        Ok(builder) => builder,
+        Err(err) => {
+            return wrap_err!("{}: unable to configure TLS protocol versions: {}", function_name, err);
+        }
+    };
+    let mut config = match builder.with_no_client_auth().with_single_cert(cert_chain, key) {
+        Ok(config) => config,
+        Err(err) => {
+            return wrap_err!("{}: config.tls.cert and config.tls.key can't be used together: {}", function_name, err);
+        }
+    };
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+/// A TLS connection accepted by `server.serve`; owned (instead of borrowing a `StreamOwned`) so it can
+/// outlive the request loop when it gets upgraded to a websocket.
+pub struct TlsStream(StreamOwned<ServerConnection, TcpStream>);
+
+impl TlsStream {
+    pub fn new(connection: ServerConnection, stream: TcpStream) -> Self {
+        Self(StreamOwned::new(connection, stream))
+    }
+    pub fn tcp(&self) -> &TcpStream {
+        self.0.get_ref()
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Drop for TlsStream {
+    fn drop(&mut self) {
+        // lets the client tell a clean close from a truncated response
+        self.0.conn.send_close_notify();
+        let _ = self.0.flush();
+    }
+}
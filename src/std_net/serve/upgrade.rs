@@ -0,0 +1,93 @@
+//! Turns a `server.serve` connection into a websocket when the handler returns `ServeResponse.websocket`
+
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use crate::std_net::websocket::WebsocketWrapper;
+
+use std::io::{BufReader, Write};
+use std::time::Duration;
+
+use tungstenite::protocol::Role;
+
+use super::Connection;
+use super::request::ParsedRequest;
+use super::response::Response;
+
+/// How long we wait for the client's close frame after the websocket callback returns (both per read
+/// and overall, so a client that ignores our close can't keep us reading its frames forever)
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Returns the `Sec-WebSocket-Accept` value if `request` is a valid websocket handshake (RFC 6455 4.2.1)
+pub fn accept_key(request: &ParsedRequest) -> Option<String> {
+    let has_token = |name: &str, token: &str| {
+        request.header(name).is_some_and(|value| {
+            value.split(',').any(|part| part.trim().eq_ignore_ascii_case(token))
+        })
+    };
+    if request.method != "GET"
+        || !has_token("upgrade", "websocket")
+        || !has_token("connection", "upgrade")
+        || request.header("sec-websocket-version").map(str::trim) != Some("13")
+    {
+        return None;
+    }
+    let key = request.header("sec-websocket-key")?.trim();
+    Some(tungstenite::handshake::derive_accept_key(key.as_bytes()))
+}
+
+/// What a handler's websocket response turns into when the request wasn't a websocket handshake
+pub fn upgrade_required() -> Response {
+    let mut response = Response::text("426 Upgrade Required", "426 Upgrade Required: expected a websocket handshake");
+    response.headers.push((String::from("Upgrade"), String::from("websocket")));
+    response.headers.push((String::from("Sec-WebSocket-Version"), String::from("13")));
+    response
+}
+
+/// Finishes the handshake, then hands the connection to `on_upgrade` as a `Websocket`. The connection's
+/// closed once `on_upgrade` returns, so it never goes back to serving http requests.
+pub fn serve_websocket<S: Connection>(
+    luau: &Lua,
+    mut reader: BufReader<S>,
+    response: &Response,
+    accept_key: &str,
+    on_upgrade: LuaFunction,
+    function_name: &'static str,
+) -> LuaEmptyResult {
+    let mut head = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n",
+        accept_key
+    );
+    // handler headers like Sec-WebSocket-Protocol and cookies still apply
+    for (key, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    head.push_str("\r\n");
+    let stream = reader.get_mut();
+    if stream.write_all(head.as_bytes()).and_then(|_| stream.flush()).is_err() {
+        return Ok(()); // client's gone
+    }
+
+    // the client might've sent its first frames along with the handshake
+    let leftover = reader.buffer().to_vec();
+    let stream = reader.into_inner();
+    // websockets idle for as long as they like; Websocket:read() should block until a message comes in
+    if stream.tcp().set_read_timeout(None).is_err() {
+        return Ok(());
+    }
+
+    let socket = tungstenite::WebSocket::from_partially_read(stream, leftover, Role::Server, None);
+    let socket = luau.create_userdata(WebsocketWrapper::new(socket))?;
+    let result = on_upgrade.call::<()>(socket.clone());
+
+    // take the socket back so the connection closes now instead of whenever the userdata gets garbage collected
+    if let Ok(mut socket) = socket.take::<WebsocketWrapper<S>>() {
+        let _ = socket.get_ref().tcp().set_read_timeout(Some(CLOSE_TIMEOUT));
+        socket.shutdown(CLOSE_TIMEOUT);
+    }
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => wrap_err!("{}: websocket handler failed with error: {}", function_name, err),
+    }
+}
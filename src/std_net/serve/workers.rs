@@ -0,0 +1,65 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use crate::{err, std_thread};
+
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use super::{ConnectionSettings, HandlerModule, Service, handle_client, respond};
+
+/// How many accepted connections can queue up per worker before the accept loop blocks
+const QUEUED_CONNECTIONS_PER_WORKER: usize = 16;
+
+/// Serves `listener` with `count` worker threads, each with its own sandboxed Luau VM (like
+/// `thread.spawn`) that loads the handler module and pulls accepted connections off a shared queue.
+pub fn serve(listener: TcpListener, module: HandlerModule, settings: ConnectionSettings, count: usize, function_name: &'static str) -> LuaValueResult {
+    let (sender, receiver) = crossbeam_channel::bounded::<TcpStream>(count * QUEUED_CONNECTIONS_PER_WORKER);
+
+    for index in 0..count {
+        let receiver = receiver.clone();
+        let module = module.clone();
+        let settings = settings.clone();
+        let worker_name = format!("seal-serve-worker-{}", index + 1);
+        let spawn_result = thread::Builder::new()
+            .name(worker_name.clone())
+            .spawn(move || {
+                if let Err(err) = run_worker(receiver, &module, &settings, function_name) {
+                    let formatted_err = LuaError::external(format!("{}{}{}\n Error occurred in server.serve worker '{}'", colors::RED, err, colors::RESET, worker_name));
+                    // same as thread.spawn: a dead worker would otherwise silently shrink the pool
+                    err::display_error_and_exit(formatted_err);
+                }
+            });
+        if let Err(err) = spawn_result {
+            return wrap_err!("{}: unable to spawn worker thread {} due to err: {}", function_name, index + 1, err);
+        }
+    }
+    // workers hold the only receivers now, so sends fail once they've all exited
+    drop(receiver);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if sender.send(stream).is_err() {
+                    return wrap_err!("{}: all workers have exited", function_name);
+                }
+            },
+            Err(err) => {
+                eputs!("server.serve: connection failed: {}", err)?;
+            }
+        }
+    }
+
+    Ok(LuaNil)
+}
+
+fn run_worker(receiver: crossbeam_channel::Receiver<TcpStream>, module: &HandlerModule, settings: &ConnectionSettings, function_name: &'static str) -> LuaEmptyResult {
+    let luau = std_thread::create_sandboxed_vm(&module.chunk_name)?;
+    let service = Service::from_module(&luau, module, function_name)?;
+    for stream in receiver.iter() {
+        if let Err(err) = handle_client(stream, settings, Some(&luau), |request, peer_address| respond(&luau, request, peer_address, &service)) {
+            return wrap_err!("{}: failed to handle client: {}", function_name, err);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,585 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::std_process::stream::parse_timeout_value;
+
+/// How much we read off the socket at a time (and the default `count` for `TcpStream:read`)
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// What happened when we tried to read more off the socket
+enum Fill {
+    Data,
+    /// the peer closed its side (or we closed the stream)
+    Eof,
+    TimedOut,
+}
+
+/// A `TcpStream` from `tcp.connect` or `TcpListener:accept`.
+///
+/// Reads work like `ChildProcessStream`'s (`read`, `read_exact`, `read_to`, `lines`, returning nil once
+/// `timeout` elapses), but like `HttpBodyStream` there's no producer thread: we only read off the socket
+/// when asked to, so nothing's ever truncated and a slow reader just makes the peer wait.
+pub struct TcpConnection {
+    /// `None` once closed
+    stream: Option<TcpStream>,
+    /// bytes read off the socket that haven't been handed out yet (by `read_exact`/`read_to`/`lines`)
+    pending: Vec<u8>,
+    local_address: String,
+    peer_address: String,
+}
+
+impl TcpConnection {
+    fn new(stream: TcpStream, function_name: &'static str) -> LuaResult<Self> {
+        let (local_address, peer_address) = match (stream.local_addr(), stream.peer_addr()) {
+            (Ok(local), Ok(peer)) => (local.to_string(), peer.to_string()),
+            (Err(err), _) | (_, Err(err)) => {
+                return wrap_err!("{}: unable to get the connection's addresses due to err: {}", function_name, err);
+            }
+        };
+        Ok(Self {
+            stream: Some(stream),
+            pending: Vec::new(),
+            local_address,
+            peer_address,
+        })
+    }
+
+    /// Reads the next chunk off the socket into `pending`, waiting until `deadline` at most
+    /// (or forever, if there isn't one).
+    fn fill_pending(&mut self, deadline: Option<Instant>, function_name: &'static str) -> LuaResult<Fill> {
+        let Some(stream) = self.stream.as_mut() else {
+            return Ok(Fill::Eof);
+        };
+
+        // a zero read timeout means "block forever" to the os, so timeouts that already
+        // elapsed (like `timeout = 0` for a nonblocking check) still get a tiny wait
+        let read_timeout = deadline.map(|deadline| {
+            deadline.saturating_duration_since(Instant::now()).max(Duration::from_millis(1))
+        });
+        if let Err(err) = stream.set_read_timeout(read_timeout) {
+            return wrap_err!("{}: unable to set read timeout due to err: {}", function_name, err);
+        }
+
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => return Ok(Fill::Eof),
+                Ok(bytes_read) => {
+                    self.pending.extend_from_slice(&chunk[..bytes_read]);
+                    return Ok(Fill::Data);
+                },
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                    return Ok(Fill::TimedOut);
+                },
+                Err(err) => {
+                    return wrap_err!("{}: error reading from {}: {}", function_name, self.peer_address, err);
+                }
+            }
+        }
+    }
+
+    fn deadline(timeout: Option<Duration>) -> Option<Instant> {
+        timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    fn pop_count(multivalue: &mut LuaMultiValue, default: Option<usize>, function_name: &'static str) -> LuaResult<usize> {
+        let count = match multivalue.pop_front() {
+            Some(LuaValue::Integer(i)) => int_to_usize(i, function_name, "count")?,
+            Some(LuaValue::Number(f)) => float_to_usize(f, function_name, "count")?,
+            Some(LuaNil) | None => match default {
+                Some(default) => default,
+                None => {
+                    return wrap_err!("{} called without required argument 'count'", function_name);
+                }
+            },
+            Some(other) => {
+                return wrap_err!("{} expected count to be an integer number, got: {:?}", function_name, other);
+            }
+        };
+        if count == 0 {
+            return wrap_err!("{}: why do you want to read 0 bytes from the stream???", function_name);
+        }
+        Ok(count)
+    }
+
+    fn read(&mut self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "TcpStream:read(count: number?, timeout: (number | Duration)?)";
+        pop_self(&mut multivalue, function_name)?;
+        let count = Self::pop_count(&mut multivalue, Some(CHUNK_SIZE), function_name)?;
+        let deadline = Self::deadline(parse_timeout_value(multivalue.pop_front(), function_name)?);
+
+        if self.pending.is_empty() {
+            match self.fill_pending(deadline, function_name)? {
+                Fill::Data => {},
+                Fill::Eof | Fill::TimedOut => return Ok(LuaNil),
+            }
+        }
+
+        let count = std::cmp::min(self.pending.len(), count);
+        let bytes_read: Vec<u8> = self.pending.drain(..count).collect();
+        ok_string(bytes_read, luau)
+    }
+
+    fn read_exact(&mut self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "TcpStream:read_exact(count: number, timeout: (number | Duration)?)";
+        pop_self(&mut multivalue, function_name)?;
+        let count = Self::pop_count(&mut multivalue, None, function_name)?;
+        let deadline = Self::deadline(parse_timeout_value(multivalue.pop_front(), function_name)?);
+
+        while self.pending.len() < count {
+            match self.fill_pending(deadline, function_name)? {
+                Fill::Data => {},
+                // what we got so far stays in pending for the next read
+                Fill::Eof | Fill::TimedOut => return Ok(LuaNil),
+            }
+        }
+
+        let bytes_read: Vec<u8> = self.pending.drain(..count).collect();
+        ok_string(bytes_read, luau)
+    }
+
+    /// Reads until `search_term`, returning `None` if the connection closed or `deadline` passed first (leaving what
+    /// was read in `pending`, unless `allow_partial`, in which case everything that was read is returned instead).
+    fn read_until(
+        &mut self,
+        search_term: &[u8],
+        inclusive: bool,
+        deadline: Option<Instant>,
+        allow_partial: bool,
+        function_name: &'static str,
+    ) -> LuaResult<Option<Vec<u8>>> {
+        // everything before this was already searched, so we only rescan the overlap with newly read bytes
+        let mut search_from = 0;
+        loop {
+            if !search_term.is_empty()
+                && let Some(position) = self.pending[search_from..]
+                    .windows(search_term.len())
+                    .position(|window| window == search_term)
+            {
+                let end = search_from + position + search_term.len();
+                let mut drained: Vec<u8> = self.pending.drain(..end).collect();
+                if !inclusive {
+                    drained.truncate(drained.len() - search_term.len());
+                }
+                return Ok(Some(drained));
+            }
+
+            search_from = (self.pending.len() + 1).saturating_sub(search_term.len());
+            match self.fill_pending(deadline, function_name)? {
+                Fill::Data => {},
+                // an empty search term means read until the peer closes the connection, like ChildProcessStream:read_to("")
+                Fill::Eof if search_term.is_empty() => return Ok(Some(std::mem::take(&mut self.pending))),
+                Fill::Eof | Fill::TimedOut => {
+                    if allow_partial && !self.pending.is_empty() {
+                        return Ok(Some(std::mem::take(&mut self.pending)));
+                    }
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn read_to(&mut self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "TcpStream:read_to(term: string, inclusive: boolean?, timeout: (number | Duration)?, allow_partial: boolean?)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let search_term = match multivalue.pop_front() {
+            Some(LuaValue::String(t)) => t.as_bytes().to_vec(),
+            Some(LuaNil) | None => {
+                return wrap_err!("{} expected search term to be a string, got nothing or nil", function_name);
+            },
+            Some(other) => {
+                return wrap_err!("{} expected search term to be a string, got: {:?}", function_name, other);
+            }
+        };
+
+        let inclusive = match multivalue.pop_front() {
+            Some(LuaValue::Boolean(inclusive)) => inclusive,
+            Some(LuaNil) | None => false,
+            Some(other) => {
+                return wrap_err!("{} expected inclusive to be a boolean or nil (default false), got: {:?}", function_name, other);
+            }
+        };
+
+        let deadline = Self::deadline(parse_timeout_value(multivalue.pop_front(), function_name)?);
+
+        let allow_partial = match multivalue.pop_front() {
+            Some(LuaValue::Boolean(partial)) => partial,
+            Some(LuaNil) | None => false,
+            Some(other) => {
+                return wrap_err!("{} expected allow_partial to be a boolean or nil (default false), got: {:?}", function_name, other);
+            }
+        };
+
+        match self.read_until(&search_term, inclusive, deadline, allow_partial, function_name)? {
+            Some(bytes) => ok_string(bytes, luau),
+            None => Ok(LuaNil),
+        }
+    }
+
+    fn next_line(&mut self, luau: &Lua) -> LuaValueResult {
+        let function_name = "TcpStream:lines() iterator function";
+        match self.read_until(b"\n", false, None, true, function_name)? {
+            Some(mut line) => {
+                // users don't want the \r from \r\n line endings either
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                ok_string(line, luau)
+            },
+            None => Ok(LuaNil),
+        }
+    }
+
+    fn write(&mut self, mut multivalue: LuaMultiValue) -> LuaEmptyResult {
+        let function_name = "TcpStream:write(data: string | buffer, timeout: (number | Duration)?)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let data = match multivalue.pop_front() {
+            Some(LuaValue::String(s)) => s.as_bytes().to_vec(),
+            Some(LuaValue::Buffer(buffy)) => buffy.to_vec(),
+            Some(LuaNil) | None => {
+                return wrap_err!("{} called without required argument 'data' (expected string or buffer)", function_name);
+            },
+            Some(other) => {
+                return wrap_err!("{} expected data to be a string or buffer, got: {:?}", function_name, other);
+            }
+        };
+
+        let timeout = parse_timeout_value(multivalue.pop_front(), function_name)?;
+
+        let Some(stream) = self.stream.as_mut() else {
+            return wrap_err!("{}: can't write to a closed TcpStream", function_name);
+        };
+        // zero would mean no timeout to the os
+        if let Err(err) = stream.set_write_timeout(timeout.map(|timeout| timeout.max(Duration::from_millis(1)))) {
+            return wrap_err!("{}: unable to set write timeout due to err: {}", function_name, err);
+        }
+        match stream.write_all(&data).and_then(|_| stream.flush()) {
+            Ok(()) => Ok(()),
+            Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                wrap_err!("{}: timed out writing to {}", function_name, self.peer_address)
+            },
+            Err(err) => {
+                wrap_err!("{}: error writing to {}: {}", function_name, self.peer_address, err)
+            }
+        }
+    }
+
+    fn set_nodelay(&mut self, mut multivalue: LuaMultiValue) -> LuaEmptyResult {
+        let function_name = "TcpStream:set_nodelay(nodelay: boolean)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let nodelay = match multivalue.pop_front() {
+            Some(LuaValue::Boolean(nodelay)) => nodelay,
+            Some(other) => {
+                return wrap_err!("{} expected nodelay to be a boolean, got: {:?}", function_name, other);
+            },
+            None => {
+                return wrap_err!("{} called without required argument 'nodelay' (expected boolean)", function_name);
+            }
+        };
+
+        let Some(stream) = self.stream.as_ref() else {
+            return wrap_err!("{}: TcpStream is closed", function_name);
+        };
+        match stream.set_nodelay(nodelay) {
+            Ok(()) => Ok(()),
+            Err(err) => wrap_err!("{}: unable to set TCP_NODELAY due to err: {}", function_name, err),
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            // the peer sees the connection close even if another handle to the socket's still around
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.pending.clear();
+    }
+
+    pub fn create_handle(self, luau: &Lua) -> LuaResult<LuaTable> {
+        let local_address = self.local_address.clone();
+        let peer_address = self.peer_address.clone();
+        let stream_cell = Rc::new(RefCell::new(self));
+        TableBuilder::create(luau)?
+            .with_value("local_address", local_address)?
+            .with_value("peer_address", peer_address)?
+            .with_function_and_signature("read", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "TcpStream:read(count: number?, timeout: (number | Duration)?)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.read(luau, multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_READ)?
+            .with_function_and_signature("read_exact", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "TcpStream:read_exact(count: number, timeout: (number | Duration)?)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.read_exact(luau, multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_READ_EXACT)?
+            .with_function_and_signature("read_to", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "TcpStream:read_to(term: string, inclusive: boolean?, timeout: (number | Duration)?, allow_partial: boolean?)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.read_to(luau, multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_READ_TO)?
+            .with_function_and_signature("lines", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | luau: &Lua, _multivalue: LuaMultiValue | -> LuaResult<LuaFunction> {
+                    let stream_cell = Rc::clone(&stream_cell);
+                    luau.create_function(move |luau: &Lua, _value: LuaMultiValue| -> LuaValueResult {
+                        let function_name = "TcpStream:lines() iterator function";
+                        match stream_cell.try_borrow_mut() {
+                            Ok(ref mut stream) => stream.next_line(luau),
+                            Err(_) => wrap_err!("{}: stream already borrowed", function_name),
+                        }
+                    })
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_LINES)?
+            .with_function_and_signature("write", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | _luau: &Lua, multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "TcpStream:write(data: string | buffer, timeout: (number | Duration)?)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.write(multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_WRITE)?
+            .with_function_and_signature("set_nodelay", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | _luau: &Lua, multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "TcpStream:set_nodelay(nodelay: boolean)";
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.set_nodelay(multivalue),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_SET_NODELAY)?
+            .with_function_and_signature("close", {
+                let stream_cell = Rc::clone(&stream_cell);
+                move | _luau: &Lua, mut multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "TcpStream:close()";
+                    pop_self(&mut multivalue, function_name)?;
+                    match stream_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => {
+                            stream.close();
+                            Ok(())
+                        },
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_STREAM_CLOSE)?
+            .build_readonly()
+    }
+}
+
+/// A `TcpListener` from `tcp.listen`
+struct Listener {
+    /// `None` once closed
+    inner: Option<TcpListener>,
+}
+
+impl Listener {
+    fn accept(&mut self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "TcpListener:accept(timeout: (number | Duration)?)";
+        pop_self(&mut multivalue, function_name)?;
+        let timeout = parse_timeout_value(multivalue.pop_front(), function_name)?;
+
+        let Some(listener) = self.inner.as_ref() else {
+            return wrap_err!("{}: can't accept connections on a closed TcpListener", function_name);
+        };
+
+        let stream = match timeout {
+            None => match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    return wrap_err!("{}: unable to accept connection due to err: {}", function_name, err);
+                }
+            },
+            // std has no accept timeout, so we poll a nonblocking listener like ChildProcessStream polls its buffer
+            Some(timeout) => {
+                if let Err(err) = listener.set_nonblocking(true) {
+                    return wrap_err!("{}: unable to set listener to nonblocking due to err: {}", function_name, err);
+                }
+                let start_time = Instant::now();
+                let accepted = loop {
+                    match listener.accept() {
+                        Ok((stream, _)) => break Ok(Some(stream)),
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            if start_time.elapsed() >= timeout {
+                                break Ok(None);
+                            }
+                            std::thread::sleep(Duration::from_millis(10));
+                        },
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => break Err(err),
+                    }
+                };
+                if let Err(err) = listener.set_nonblocking(false) {
+                    return wrap_err!("{}: unable to set listener back to blocking due to err: {}", function_name, err);
+                }
+                match accepted {
+                    Ok(Some(stream)) => {
+                        // some platforms have accepted sockets inherit the listener's nonblocking flag
+                        if let Err(err) = stream.set_nonblocking(false) {
+                            return wrap_err!("{}: unable to set accepted connection to blocking due to err: {}", function_name, err);
+                        }
+                        stream
+                    },
+                    Ok(None) => return Ok(LuaNil),
+                    Err(err) => {
+                        return wrap_err!("{}: unable to accept connection due to err: {}", function_name, err);
+                    }
+                }
+            }
+        };
+
+        ok_table(TcpConnection::new(stream, function_name)?.create_handle(luau))
+    }
+
+    fn create_handle(self, luau: &Lua, local_address: String) -> LuaResult<LuaTable> {
+        let listener_cell = Rc::new(RefCell::new(self));
+        TableBuilder::create(luau)?
+            .with_value("local_address", local_address)?
+            .with_function_and_signature("accept", {
+                let listener_cell = Rc::clone(&listener_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "TcpListener:accept(timeout: (number | Duration)?)";
+                    match listener_cell.try_borrow_mut() {
+                        Ok(ref mut listener) => listener.accept(luau, multivalue),
+                        Err(_) => wrap_err!("{}: listener already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_LISTENER_ACCEPT)?
+            .with_function_and_signature("close", {
+                let listener_cell = Rc::clone(&listener_cell);
+                move | _luau: &Lua, mut multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "TcpListener:close()";
+                    pop_self(&mut multivalue, function_name)?;
+                    match listener_cell.try_borrow_mut() {
+                        Ok(ref mut listener) => {
+                            listener.inner = None;
+                            Ok(())
+                        },
+                        Err(_) => wrap_err!("{}: listener already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_TCP_TCP_LISTENER_CLOSE)?
+            .build_readonly()
+    }
+}
+
+/// Resolves `address` (`"host:port"`) to every address it could mean, so `localhost` can fall back from `::1` to `127.0.0.1`
+pub fn resolve_address(address: &str, function_name: &'static str) -> LuaResult<Vec<SocketAddr>> {
+    match address.to_socket_addrs() {
+        Ok(addresses) => {
+            let addresses: Vec<SocketAddr> = addresses.collect();
+            if addresses.is_empty() {
+                return wrap_err!("{}: '{}' didn't resolve to any addresses", function_name, address);
+            }
+            Ok(addresses)
+        },
+        Err(err) => {
+            wrap_err!("{}: unable to resolve address '{}' (expected \"host:port\") due to err: {}", function_name, address, err)
+        }
+    }
+}
+
+/// Connects to the first of `addresses` that accepts, like `TcpStream::connect`, but giving each one at most `timeout`;
+/// callers reject zero timeouts when parsing options since `connect_timeout` refuses them
+pub fn connect_any(addresses: &[SocketAddr], timeout: Option<Duration>) -> io::Result<TcpStream> {
+    let Some(timeout) = timeout else {
+        return TcpStream::connect(addresses);
+    };
+    // connect_timeout only takes one address, so try them in order like TcpStream::connect does
+    let mut last_err = None;
+    for socket_address in addresses {
+        match TcpStream::connect_timeout(socket_address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")))
+}
+
+fn pop_address(multivalue: &mut LuaMultiValue, function_name: &'static str) -> LuaResult<String> {
+    match multivalue.pop_front() {
+        Some(LuaValue::String(address)) => Ok(address.to_string_lossy()),
+        Some(LuaNil) | None => {
+            wrap_err!("{} called without required argument 'address' (expected \"host:port\" string)", function_name)
+        },
+        Some(other) => {
+            wrap_err!("{}: expected address to be a \"host:port\" string, got: {:?}", function_name, other)
+        }
+    }
+}
+
+fn tcp_connect(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+    let function_name = "tcp.connect(address: string, timeout: (number | Duration)?)";
+    let address = pop_address(&mut multivalue, function_name)?;
+    let timeout = parse_timeout_value(multivalue.pop_front(), function_name)?;
+    if timeout.is_some_and(|timeout| timeout.is_zero()) {
+        return wrap_err!("{}: timeout must be greater than 0 (leave it out to wait as long as the os lets us)", function_name);
+    }
+
+    let addresses = resolve_address(&address, function_name)?;
+    let stream = match connect_any(&addresses, timeout) {
+        Ok(stream) => stream,
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+            return wrap_err!("{}: timed out connecting to '{}'", function_name, address);
+        },
+        Err(err) => {
+            return wrap_err!("{}: unable to connect to '{}' due to err: {}", function_name, address, err);
+        }
+    };
+
+    ok_table(TcpConnection::new(stream, function_name)?.create_handle(luau))
+}
+
+fn tcp_listen(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+    let function_name = "tcp.listen(address: string)";
+    let address = pop_address(&mut multivalue, function_name)?;
+
+    let addresses = resolve_address(&address, function_name)?;
+    let listener = match TcpListener::bind(&addresses[..]) {
+        Ok(listener) => listener,
+        Err(err) => {
+            return wrap_err!("{}: unable to listen on '{}' due to err: {}", function_name, address, err);
+        }
+    };
+    // with port 0 the os picks one, so this is how users find out which
+    let local_address = match listener.local_addr() {
+        Ok(local_address) => local_address.to_string(),
+        Err(err) => {
+            return wrap_err!("{}: unable to get the listener's address due to err: {}", function_name, err);
+        }
+    };
+
+    ok_table(Listener { inner: Some(listener) }.create_handle(luau, local_address))
+}
+
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::create(luau)?
+        .with_function_and_signature("connect", tcp_connect, signatures::STD_NET_TCP_CONNECT)?
+        .with_function_and_signature("listen", tcp_listen, signatures::STD_NET_TCP_LISTEN)?
+        .build_readonly()
+}
@@ -7,12 +7,21 @@
 //! - `SEAL_SYSTEM_CERTS`: whether to also trust the OS's certificate store. Defaults to
 //!   true; set to "0"/"false"/"no" to trust *only* the certs from `SSL_CERT_FILE`
 //!   (or, if that's unset too, only the bundled Mozilla root list).
+//!
+//! Requests and websockets can layer their own settings on top of that with `TlsOptions`
+//! (`RequestOptions.tls`): extra CAs to trust, a client certificate for mTLS, or skipping
+//! verification entirely for local dev servers.
+
+use mluau::prelude::*;
+use crate::prelude::*;
 
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
 
-use ureq::tls::{Certificate, PemItem, RootCerts};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer};
+use ureq::tls::{Certificate, ClientCert, KeyKind, PemItem, PrivateKey, RootCerts, TlsConfig, TlsProvider};
 
 fn warn(message: &str) {
     let mut stderr = std::io::stderr().lock();
@@ -75,16 +84,20 @@ fn custom_ca_certs() -> &'static [Certificate<'static>] {
     })
 }
 
-fn native_certs_as_ureq_certs() -> Vec<Certificate<'static>> {
-    let result = rustls_native_certs::load_native_certs();
-    for err in &result.errors {
-        warn(&format!("error loading a system root certificate: {}", err));
-    }
-    result
-        .certs
-        .into_iter()
-        .map(|der| Certificate::from_der(der.as_ref()).to_owned())
-        .collect()
+/// The OS's certificate store, read once; it's the whole system trust store, so reading it per request adds up.
+fn native_certs_as_ureq_certs() -> &'static [Certificate<'static>] {
+    static NATIVE_CERTS: OnceLock<Vec<Certificate<'static>>> = OnceLock::new();
+    NATIVE_CERTS.get_or_init(|| {
+        let result = rustls_native_certs::load_native_certs();
+        for err in &result.errors {
+            warn(&format!("error loading a system root certificate: {}", err));
+        }
+        result
+            .certs
+            .into_iter()
+            .map(|der| Certificate::from_der(der.as_ref()).to_owned())
+            .collect()
+    })
 }
 
 /// The `ureq::tls::RootCerts` to use, combining `SSL_CERT_FILE` and
@@ -102,7 +115,7 @@ pub fn ureq_root_certs() -> RootCerts {
                 (false, _) => {
                     let mut certs = custom.to_vec();
                     if use_system {
-                        certs.extend(native_certs_as_ureq_certs());
+                        certs.extend(native_certs_as_ureq_certs().iter().cloned());
                     }
                     RootCerts::new_with_certs(&certs)
                 }
@@ -111,52 +124,297 @@ pub fn ureq_root_certs() -> RootCerts {
         .clone()
 }
 
+/// The root store websockets (tungstenite) trust, with the same trust settings as `ureq_root_certs`;
+/// built once (sharing the cached system certs) and cloned per connection.
+fn rustls_root_store() -> &'static rustls::RootCertStore {
+    static ROOT_STORE: OnceLock<rustls::RootCertStore> = OnceLock::new();
+    ROOT_STORE.get_or_init(|| {
+        let mut store = rustls::RootCertStore::empty();
+        let use_system = settings().use_system_certs;
+        let custom = custom_ca_certs();
+
+        // Mozilla's bundled list is only a fallback for when we have nothing else to trust:
+        // either the system store couldn't be read, or the user disabled system certs and
+        // didn't provide their own CA either. If the user explicitly disabled system certs
+        // and provided SSL_CERT_FILE, we should trust *only* that (matching ureq_root_certs),
+        // not silently widen trust back out to the public CA list.
+        if use_system {
+            let native = native_certs_as_ureq_certs()
+                .iter()
+                .map(|cert| CertificateDer::from(cert.der().to_vec()));
+            let (added, _ignored) = store.add_parsable_certificates(native);
+            if added == 0 && custom.is_empty() {
+                store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            }
+        } else if custom.is_empty() {
+            store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+
+        for cert in custom {
+            let der = CertificateDer::from(cert.der().to_vec());
+            if let Err(err) = store.add(der) {
+                warn(&format!("unable to trust a certificate from SSL_CERT_FILE: {}", err));
+            }
+        }
+
+        store
+    })
+}
+
+fn crypto_provider() -> Arc<rustls::crypto::CryptoProvider> {
+    rustls::crypto::CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()))
+}
+
 /// The rustls `ClientConfig` websockets (tungstenite) should use, sharing the same
 /// trust settings as `ureq_root_certs`.
 pub fn rustls_client_config() -> Arc<rustls::ClientConfig> {
     static CONFIG: OnceLock<Arc<rustls::ClientConfig>> = OnceLock::new();
     CONFIG
         .get_or_init(|| {
-            let mut store = rustls::RootCertStore::empty();
-            let use_system = settings().use_system_certs;
-            let custom = custom_ca_certs();
+            let config = rustls::ClientConfig::builder_with_provider(crypto_provider())
+                .with_safe_default_protocol_versions()
+                .expect("rustls default protocol versions are always valid")
+                .with_root_certificates(rustls_root_store().clone())
+                .with_no_client_auth();
 
-            // Mozilla's bundled list is only a fallback for when we have nothing else to trust:
-            // either the system store couldn't be read, or the user disabled system certs and
-            // didn't provide their own CA either. If the user explicitly disabled system certs
-            // and provided SSL_CERT_FILE, we should trust *only* that (matching ureq_root_certs),
-            // not silently widen trust back out to the public CA list.
-            if use_system {
-                let result = rustls_native_certs::load_native_certs();
-                for err in &result.errors {
-                    warn(&format!("error loading a system root certificate: {}", err));
+            Arc::new(config)
+        })
+        .clone()
+}
+
+/// The TLS config for an http request, with `options` (from `RequestOptions.tls`) layered on the env's trust settings
+pub fn ureq_tls_config(options: Option<&TlsOptions>) -> TlsConfig {
+    let builder = TlsConfig::builder().provider(TlsProvider::Rustls);
+    let Some(options) = options else {
+        return builder.root_certs(ureq_root_certs()).build();
+    };
+
+    builder
+        .root_certs(options.root_certs.clone().unwrap_or_else(ureq_root_certs))
+        .client_cert(options.client_cert.clone())
+        .disable_verification(options.insecure_skip_verify)
+        .build()
+}
+
+fn rustls_key(key: &PrivateKey<'static>) -> Option<PrivateKeyDer<'static>> {
+    let der = key.der().to_vec();
+    match key.kind() {
+        KeyKind::Pkcs1 => Some(PrivateKeyDer::Pkcs1(PrivatePkcs1KeyDer::from(der))),
+        KeyKind::Pkcs8 => Some(PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(der))),
+        KeyKind::Sec1 => Some(PrivateKeyDer::Sec1(PrivateSec1KeyDer::from(der))),
+        _ => None,
+    }
+}
+
+/// Per-request TLS settings from a `TlsOptions` table (`RequestOptions.tls`, `HttpClientOptions.tls`,
+/// and `websocket.connect`'s `options.tls`)
+#[derive(Clone, Default)]
+pub struct TlsOptions {
+    extra_certs: Vec<Certificate<'static>>,
+    /// what ureq should trust with `extra_certs` added, built once up front so every request
+    /// (like each of an `http.client`'s) can share it; `None` without `extra_certs`
+    root_certs: Option<RootCerts>,
+    client_cert: Option<ClientCert>,
+    insecure_skip_verify: bool,
+}
+
+impl TlsOptions {
+    pub fn from_value(value: LuaValue, field_name: &'static str, function_name: &'static str) -> LuaResult<Option<Self>> {
+        let options = match value {
+            LuaValue::Table(options) => options,
+            LuaNil => return Ok(None),
+            other => {
+                return wrap_err!("{}: expected {} to be a TlsOptions table or nil, got: {:?}", function_name, field_name, other);
+            }
+        };
+
+        let get_path = |key: &'static str| -> LuaResult<Option<PathBuf>> {
+            match options.raw_get(key)? {
+                LuaValue::String(path) => Ok(Some(PathBuf::from(path.to_string_lossy()))),
+                LuaNil => Ok(None),
+                other => {
+                    wrap_err!("{}: expected {}.{} to be a path (string) or nil, got: {:?}", function_name, field_name, key, other)
                 }
-                let (added, _ignored) = store.add_parsable_certificates(result.certs);
-                if added == 0 && custom.is_empty() {
-                    store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            }
+        };
+
+        let mut extra_certs = Vec::new();
+        if let Some(path) = get_path("ca_file")? {
+            let (certs, _key) = read_pem(&path, field_name, "ca_file", function_name)?;
+            if certs.is_empty() {
+                return wrap_err!("{}: {}.ca_file '{}' doesn't contain any PEM certificates", function_name, field_name, path.display());
+            }
+            extra_certs = certs;
+        }
+
+        let client_cert = match (get_path("client_cert")?, get_path("client_key")?) {
+            (Some(cert_path), key_path) => {
+                let (chain, key_in_cert_file) = read_pem(&cert_path, field_name, "client_cert", function_name)?;
+                if chain.is_empty() {
+                    return wrap_err!("{}: {}.client_cert '{}' doesn't contain any PEM certificates", function_name, field_name, cert_path.display());
                 }
-            } else if custom.is_empty() {
-                store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+                let key = match key_path {
+                    Some(key_path) => read_pem(&key_path, field_name, "client_key", function_name)?.1,
+                    // some tools bundle the key and cert into one file
+                    None => key_in_cert_file,
+                };
+                let Some(key) = key else {
+                    return wrap_err!("{}: {}.client_key is required unless client_cert also contains the private key", function_name, field_name);
+                };
+                Some(Self::checked_client_cert(chain, key, field_name, function_name)?)
+            },
+            (None, Some(_)) => {
+                return wrap_err!("{}: {}.client_key was set without a client_cert", function_name, field_name);
+            },
+            (None, None) => None,
+        };
+
+        let insecure_skip_verify = match options.raw_get("insecure_skip_verify")? {
+            LuaValue::Boolean(skip) => skip,
+            LuaNil => false,
+            other => {
+                return wrap_err!("{}: expected {}.insecure_skip_verify to be a boolean or nil, got: {:?}", function_name, field_name, other);
             }
+        };
+
+        let root_certs = if extra_certs.is_empty() {
+            None
+        } else {
+            // ureq can't add certs to the platform verifier or the bundled Mozilla roots, so extra CAs are
+            // trusted alongside SSL_CERT_FILE and the system's certificate store (read once, see
+            // native_certs_as_ureq_certs) instead, which is also what SSL_CERT_FILE does
+            let mut certs = custom_ca_certs().to_vec();
+            if settings().use_system_certs {
+                certs.extend(native_certs_as_ureq_certs().iter().cloned());
+            }
+            certs.extend(extra_certs.iter().cloned());
+            Some(RootCerts::new_with_certs(&certs))
+        };
+
+        Ok(Some(Self { extra_certs, root_certs, client_cert, insecure_skip_verify }))
+    }
+
+    /// ureq panics if a client cert's key is unusable, so we check it with rustls first
+    fn checked_client_cert(
+        chain: Vec<Certificate<'static>>,
+        key: PrivateKey<'static>,
+        field_name: &'static str,
+        function_name: &'static str,
+    ) -> LuaResult<ClientCert> {
+        let Some(key_der) = rustls_key(&key) else {
+            return wrap_err!("{}: {}.client_key is an unsupported kind of private key", function_name, field_name);
+        };
+        let chain_der = chain.iter().map(|cert| CertificateDer::from(cert.der().to_vec())).collect();
+        let check = rustls::ClientConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_root_certificates(rustls::RootCertStore::empty()).with_client_auth_cert(chain_der, key_der));
+        if let Err(err) = check {
+            return wrap_err!("{}: {}.client_cert and client_key can't be used together: {}", function_name, field_name, err);
+        }
+        Ok(ClientCert::new_with_certs(&chain, key))
+    }
+
+    /// The rustls config for a websocket connection with these options
+    pub fn rustls_client_config(&self) -> Result<Arc<rustls::ClientConfig>, rustls::Error> {
+        let builder = rustls::ClientConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()?;
+
+        let builder = if self.insecure_skip_verify {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(SkipVerification(crypto_provider())))
+        } else {
+            let mut store = rustls_root_store().clone();
+            store.add_parsable_certificates(self.extra_certs.iter().map(|cert| CertificateDer::from(cert.der().to_vec())));
+            builder.with_root_certificates(store)
+        };
+
+        let config = match &self.client_cert {
+            Some(client_cert) => {
+                let chain = client_cert.certs().iter().map(|cert| CertificateDer::from(cert.der().to_vec())).collect();
+                let Some(key) = rustls_key(client_cert.private_key()) else {
+                    return Err(rustls::Error::General(String::from("unsupported kind of private key")));
+                };
+                builder.with_client_auth_cert(chain, key)?
+            },
+            None => builder.with_no_client_auth(),
+        };
 
-            for cert in custom {
-                let der = rustls_pki_types::CertificateDer::from(cert.der().to_vec());
-                if let Err(err) = store.add(der) {
-                    warn(&format!("unable to trust a certificate from SSL_CERT_FILE: {}", err));
+        Ok(Arc::new(config))
+    }
+}
+
+/// Reads every certificate (and the first private key) out of a PEM file
+fn read_pem(
+    path: &Path,
+    field_name: &'static str,
+    key: &'static str,
+    function_name: &'static str,
+) -> LuaResult<(Vec<Certificate<'static>>, Option<PrivateKey<'static>>)> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return wrap_err!("{}: unable to read {}.{} '{}' due to err: {}", function_name, field_name, key, path.display(), err);
+        }
+    };
+
+    let mut certs = Vec::new();
+    let mut private_key = None;
+    for item in ureq::tls::parse_pem(&bytes) {
+        match item {
+            Ok(PemItem::Certificate(cert)) => certs.push(cert),
+            Ok(PemItem::PrivateKey(found)) => {
+                if private_key.is_none() {
+                    private_key = Some(found);
                 }
+            },
+            Ok(_) => {},
+            Err(err) => {
+                return wrap_err!("{}: unable to parse {}.{} '{}' as PEM: {}", function_name, field_name, key, path.display(), err);
             }
+        }
+    }
+    Ok((certs, private_key))
+}
 
-            let provider = rustls::crypto::CryptoProvider::get_default()
-                .cloned()
-                .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()));
+/// `insecure_skip_verify = true`: accepts any certificate, but still checks the handshake's signatures
+/// are valid for the certificate the server sent
+#[derive(Debug)]
+struct SkipVerification(Arc<rustls::crypto::CryptoProvider>);
 
-            let config = rustls::ClientConfig::builder_with_provider(provider)
-                .with_safe_default_protocol_versions()
-                .expect("rustls default protocol versions are always valid")
-                .with_root_certificates(store)
-                .with_no_client_auth();
+impl ServerCertVerifier for SkipVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &rustls_pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls_pki_types::UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
 
-            Arc::new(config)
-        })
-        .clone()
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
 }
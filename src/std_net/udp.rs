@@ -0,0 +1,180 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use std::cell::RefCell;
+use std::io;
+use std::net::UdpSocket;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::std_process::stream::parse_timeout_value;
+use super::tcp::resolve_address;
+
+/// Big enough for any UDP datagram (the max payload over IPv4 is 65,507 bytes)
+const MAX_DATAGRAM_SIZE: usize = 65_536;
+
+/// A `UdpSocket` from `udp.bind`
+struct Socket {
+    /// `None` once closed
+    inner: Option<UdpSocket>,
+}
+
+impl Socket {
+    fn send_to(&self, mut multivalue: LuaMultiValue) -> LuaResult<usize> {
+        let function_name = "UdpSocket:send_to(data: string | buffer, address: string)";
+        pop_self(&mut multivalue, function_name)?;
+
+        let data = match multivalue.pop_front() {
+            Some(LuaValue::String(s)) => s.as_bytes().to_vec(),
+            Some(LuaValue::Buffer(buffy)) => buffy.to_vec(),
+            Some(LuaNil) | None => {
+                return wrap_err!("{} called without required argument 'data' (expected string or buffer)", function_name);
+            },
+            Some(other) => {
+                return wrap_err!("{} expected data to be a string or buffer, got: {:?}", function_name, other);
+            }
+        };
+
+        let address = match multivalue.pop_front() {
+            Some(LuaValue::String(address)) => address.to_string_lossy(),
+            Some(LuaNil) | None => {
+                return wrap_err!("{} called without required argument 'address' (expected \"host:port\" string)", function_name);
+            },
+            Some(other) => {
+                return wrap_err!("{}: expected address to be a \"host:port\" string, got: {:?}", function_name, other);
+            }
+        };
+
+        let Some(socket) = self.inner.as_ref() else {
+            return wrap_err!("{}: can't send from a closed UdpSocket", function_name);
+        };
+
+        let addresses = resolve_address(&address, function_name)?;
+        // an ipv4 socket can't send to an ipv6 address (and vice versa), so pick one it can
+        let is_ipv4 = socket.local_addr().map(|local| local.is_ipv4()).unwrap_or(true);
+        let target = addresses
+            .iter()
+            .find(|candidate| candidate.is_ipv4() == is_ipv4)
+            .unwrap_or(&addresses[0]);
+
+        match socket.send_to(&data, target) {
+            Ok(bytes_sent) => Ok(bytes_sent),
+            Err(err) => {
+                wrap_err!("{}: unable to send to '{}' due to err: {}", function_name, address, err)
+            }
+        }
+    }
+
+    fn recv_from(&self, luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
+        let function_name = "UdpSocket:recv_from(timeout: (number | Duration)?)";
+        pop_self(&mut multivalue, function_name)?;
+        let timeout = parse_timeout_value(multivalue.pop_front(), function_name)?;
+
+        let Some(socket) = self.inner.as_ref() else {
+            return wrap_err!("{}: can't receive on a closed UdpSocket", function_name);
+        };
+
+        // zero would mean no timeout to the os
+        if let Err(err) = socket.set_read_timeout(timeout.map(|timeout| timeout.max(Duration::from_millis(1)))) {
+            return wrap_err!("{}: unable to set read timeout due to err: {}", function_name, err);
+        }
+
+        let mut datagram = vec![0u8; MAX_DATAGRAM_SIZE];
+        loop {
+            match socket.recv_from(&mut datagram) {
+                Ok((bytes_received, from)) => {
+                    datagram.truncate(bytes_received);
+                    return ok_table(TableBuilder::create(luau)?
+                        .with_value("data", luau.create_string(&datagram)?)?
+                        .with_value("from", from.to_string())?
+                        .build_readonly()
+                    );
+                },
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                    return Ok(LuaNil);
+                },
+                Err(err) => {
+                    return wrap_err!("{}: unable to receive datagram due to err: {}", function_name, err);
+                }
+            }
+        }
+    }
+
+    fn create_handle(self, luau: &Lua, local_address: String) -> LuaResult<LuaTable> {
+        let socket_cell = Rc::new(RefCell::new(self));
+        TableBuilder::create(luau)?
+            .with_value("local_address", local_address)?
+            .with_function_and_signature("send_to", {
+                let socket_cell = Rc::clone(&socket_cell);
+                move | _luau: &Lua, multivalue: LuaMultiValue | -> LuaResult<usize> {
+                    let function_name = "UdpSocket:send_to(data: string | buffer, address: string)";
+                    match socket_cell.try_borrow() {
+                        Ok(socket) => socket.send_to(multivalue),
+                        Err(_) => wrap_err!("{}: socket already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_UDP_UDP_SOCKET_SEND_TO)?
+            .with_function_and_signature("recv_from", {
+                let socket_cell = Rc::clone(&socket_cell);
+                move | luau: &Lua, multivalue: LuaMultiValue | -> LuaValueResult {
+                    let function_name = "UdpSocket:recv_from(timeout: (number | Duration)?)";
+                    match socket_cell.try_borrow() {
+                        Ok(socket) => socket.recv_from(luau, multivalue),
+                        Err(_) => wrap_err!("{}: socket already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_UDP_UDP_SOCKET_RECV_FROM)?
+            .with_function_and_signature("close", {
+                let socket_cell = Rc::clone(&socket_cell);
+                move | _luau: &Lua, mut multivalue: LuaMultiValue | -> LuaEmptyResult {
+                    let function_name = "UdpSocket:close()";
+                    pop_self(&mut multivalue, function_name)?;
+                    match socket_cell.try_borrow_mut() {
+                        Ok(ref mut socket) => {
+                            socket.inner = None;
+                            Ok(())
+                        },
+                        Err(_) => wrap_err!("{}: socket already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_NET_UDP_UDP_SOCKET_CLOSE)?
+            .build_readonly()
+    }
+}
+
+fn udp_bind(luau: &Lua, value: LuaValue) -> LuaValueResult {
+    let function_name = "udp.bind(address: string)";
+    let address = match value {
+        LuaValue::String(address) => address.to_string_lossy(),
+        LuaNil => {
+            return wrap_err!("{} called without required argument 'address' (expected \"host:port\" string, like \"0.0.0.0:8125\")", function_name);
+        },
+        other => {
+            return wrap_err!("{}: expected address to be a \"host:port\" string, got: {:?}", function_name, other);
+        }
+    };
+
+    let addresses = resolve_address(&address, function_name)?;
+    let socket = match UdpSocket::bind(&addresses[..]) {
+        Ok(socket) => socket,
+        Err(err) => {
+            return wrap_err!("{}: unable to bind to '{}' due to err: {}", function_name, address, err);
+        }
+    };
+    // with port 0 the os picks one, so this is how users find out which
+    let local_address = match socket.local_addr() {
+        Ok(local_address) => local_address.to_string(),
+        Err(err) => {
+            return wrap_err!("{}: unable to get the socket's address due to err: {}", function_name, err);
+        }
+    };
+
+    ok_table(Socket { inner: Some(socket) }.create_handle(luau, local_address))
+}
+
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::create(luau)?
+        .with_function_and_signature("bind", udp_bind, signatures::STD_NET_UDP_BIND)?
+        .build_readonly()
+}
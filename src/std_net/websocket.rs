@@ -3,16 +3,23 @@ use mluau::prelude::*;
 
 use url::Url;
 
+use std::io::{self, Read, Write};
 use std::net::TcpStream;
+use std::time::{Duration, Instant};
 use tungstenite::{
     Message,
     Connector,
     client::IntoClientRequest,
-    protocol::{CloseFrame, frame::coding::CloseCode},
+    http::{HeaderName, HeaderValue},
+    protocol::{CloseFrame, WebSocketConfig, frame::coding::CloseCode},
     stream::MaybeTlsStream,
 };
 
-use super::tls_config;
+use crate::std_fs::file_size::FileSize;
+use crate::std_time::duration::TimeDuration;
+use super::proxy::{self, ProxySetting};
+use super::tcp;
+use super::tls_config::{self, TlsOptions};
 
 struct WebsocketMessage {
     inner: Message,
@@ -140,27 +147,127 @@ impl LuaUserData for WebsocketMessage {
 
 type TungsteniteWebSocket = tungstenite::WebSocket<MaybeTlsStream<TcpStream>>;
 
-struct WebsocketWrapper {
-    inner: TungsteniteWebSocket
+/// A stream a `Websocket` can run over; read timeouts, pings, and `try_next` work on the socket underneath it
+pub trait WebsocketStream: Read + Write {
+    /// `None` for streams we can't get the socket of, which just block like tungstenite normally does
+    fn socket(&self) -> Option<&TcpStream>;
 }
-impl WebsocketWrapper {
-    fn new(inner: TungsteniteWebSocket) -> Self {
+
+impl WebsocketStream for MaybeTlsStream<TcpStream> {
+    fn socket(&self) -> Option<&TcpStream> {
+        match self {
+            MaybeTlsStream::Plain(stream) => Some(stream),
+            MaybeTlsStream::Rustls(stream) => Some(stream.get_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The `Websocket` userdata; generic over the stream so connections accepted by
+/// `server.serve` (plain or TLS) get the same api as ones from `websocket.connect`
+pub struct WebsocketWrapper<S: WebsocketStream> {
+    inner: tungstenite::WebSocket<S>,
+    /// how long `read` waits for a message before erroring; `None` waits forever
+    read_timeout: Option<Duration>,
+    /// pings get sent this often while we're reading or sending
+    ping_interval: Option<Duration>,
+    last_ping: Instant,
+    /// the subprotocol the server picked from `WebsocketOptions.subprotocols`, if any
+    protocol: Option<String>,
+}
+impl<S: WebsocketStream> WebsocketWrapper<S> {
+    pub fn new(inner: tungstenite::WebSocket<S>) -> Self {
         Self {
-            inner
+            inner,
+            read_timeout: None,
+            ping_interval: None,
+            last_ping: Instant::now(),
+            protocol: None,
         }
     }
     fn read(&mut self) -> LuaResult<WebsocketMessage> {
-        // self.inner.read()
-        let message = match self.inner.read() {
-            Ok(message) => message,
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        match self.read_until(deadline) {
+            Ok(Some(message)) => Ok(WebsocketMessage::from(message)),
+            Ok(None) => {
+                wrap_err!(
+                    "no message came in within the read timeout ({:?}); consider increasing WebsocketOptions.timeout.read or use Websocket:try_next()",
+                    self.read_timeout.unwrap_or_default()
+                )
+            },
             Err(err) => {
-                return wrap_err!("unable to read message due to err: {}", err);
+                wrap_err!("unable to read message due to err: {}", err)
             },
-        };
-
-        Ok(WebsocketMessage::from(message))
+        }
+    }
+    /// Reads the next message, sending pings every `ping_interval` while we wait;
+    /// returns `None` if `deadline` passes first
+    fn read_until(&mut self, deadline: Option<Instant>) -> Result<Option<Message>, tungstenite::Error> {
+        // nothing to wake up for, so just block until something comes in
+        if deadline.is_none() && self.ping_interval.is_none() {
+            return self.inner.read().map(Some);
+        }
+        loop {
+            self.ping_if_due()?;
+            let now = Instant::now();
+            let until_deadline = match deadline {
+                Some(deadline) if now >= deadline => return Ok(None),
+                Some(deadline) => Some(deadline - now),
+                None => None,
+            };
+            let until_ping = self.ping_interval.map(|interval| (self.last_ping + interval).saturating_duration_since(now));
+            let wait = match (until_deadline, until_ping) {
+                (Some(until_deadline), Some(until_ping)) => Some(until_deadline.min(until_ping)),
+                (until_deadline, until_ping) => until_deadline.or(until_ping),
+            };
+            // zero would mean no timeout to the os
+            self.set_read_timeout(wait.map(|wait| wait.max(Duration::from_millis(1))))?;
+            match self.inner.read() {
+                Ok(message) => return Ok(Some(message)),
+                // tungstenite keeps any partially read frame around, so we can pick back up where we left off
+                Err(tungstenite::Error::Io(err)) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    /// Returns the next message if one's already come in, without waiting for one
+    fn try_next(&mut self) -> Result<Option<Message>, tungstenite::Error> {
+        self.ping_if_due()?;
+        self.set_nonblocking(true)?;
+        let result = self.inner.read();
+        self.set_nonblocking(false)?;
+        match result {
+            Ok(message) => Ok(Some(message)),
+            Err(tungstenite::Error::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+    fn ping_if_due(&mut self) -> Result<(), tungstenite::Error> {
+        if let Some(interval) = self.ping_interval
+            && self.last_ping.elapsed() >= interval
+            && self.inner.can_write()
+        {
+            self.inner.send(Message::Ping(Vec::new().into()))?;
+            self.last_ping = Instant::now();
+        }
+        Ok(())
+    }
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self.inner.get_ref().socket() {
+            Some(socket) => socket.set_read_timeout(timeout),
+            None => Ok(()),
+        }
+    }
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self.inner.get_ref().socket() {
+            Some(socket) => socket.set_nonblocking(nonblocking),
+            None => Ok(()),
+        }
     }
     fn send(&mut self, message: String) -> LuaEmptyResult {
+        if let Err(err) = self.ping_if_due() {
+            return wrap_err!("unable to send ping due to err: {}", err);
+        }
         match self.inner.send(Message::Text(message.into())) {
             Ok(_) => Ok(()),
             Err(err) => {
@@ -169,6 +276,9 @@ impl WebsocketWrapper {
         }
     }
     fn send_bytes(&mut self, message: Vec<u8>) -> LuaEmptyResult {
+        if let Err(err) = self.ping_if_due() {
+            return wrap_err!("unable to send ping due to err: {}", err);
+        }
         match self.inner.send(Message::Binary(message.into())) {
             Ok(_) => Ok(()),
             Err(err) => {
@@ -193,14 +303,34 @@ impl WebsocketWrapper {
             }
         }
     }
+    pub fn get_ref(&self) -> &S {
+        self.inner.get_ref()
+    }
+    /// Closes the connection normally (unless it's already closing) and waits up to `timeout` for the
+    /// other side's close frame, so the close handshake finishes before the stream gets dropped
+    pub fn shutdown(&mut self, timeout: Duration) {
+        if self.inner.can_write() {
+            let _ = self.inner.close(None);
+        }
+        // errors with ConnectionClosed once the handshake's done, or when the stream's read timeout elapses;
+        // the deadline's for peers that keep sending frames instead of answering our close
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline && self.inner.read().is_ok() {}
+    }
+}
+
+impl<S: WebsocketStream + 'static> WebsocketWrapper<S> {
     fn get_userdata(self, luau: &Lua) -> LuaValueResult {
         ok_userdata(self, luau)
     }
 }
 
-impl LuaUserData for WebsocketWrapper {
+impl<S: WebsocketStream + 'static> LuaUserData for WebsocketWrapper<S> {
     fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
         fields.add_meta_field("__type", "Websocket");
+        fields.add_field_method_get("protocol", |_luau, it| {
+            Ok(it.protocol.clone())
+        });
     }
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
         methods.add_method("readable", |_luau, it, _: LuaValue| {
@@ -213,7 +343,16 @@ impl LuaUserData for WebsocketWrapper {
             let message = it.read()?;
             message.get_userdata(luau)
         });
-        methods.add_method_mut("send", |luau: &Lua, it: &mut WebsocketWrapper, value: LuaValue| {
+        methods.add_method_mut("try_next", |luau, it, _: LuaValue| -> LuaValueResult {
+            match it.try_next() {
+                Ok(Some(message)) => WebsocketMessage::from(message).get_userdata(luau),
+                Ok(None) => Ok(LuaNil),
+                Err(err) => {
+                    wrap_err!("Websocket:try_next(): unable to read message due to err: {}", err)
+                }
+            }
+        });
+        methods.add_method_mut("send", |luau: &Lua, it: &mut WebsocketWrapper<S>, value: LuaValue| {
             let function_name = "Websocket:send(message: string)";
             match value {
                 LuaValue::String(s) => it.send(s.to_string_lossy()),
@@ -281,8 +420,166 @@ impl LuaUserData for WebsocketWrapper {
     }
 }
 
+/// `WebsocketOptions.timeout`, either one Duration for everything or a table with separate ones
+#[derive(Default, Clone, Copy)]
+struct WebsocketTimeouts {
+    /// connecting, including the proxy's CONNECT and the TLS + websocket handshakes
+    connect: Option<Duration>,
+    read: Option<Duration>,
+    write: Option<Duration>,
+}
+
+impl WebsocketTimeouts {
+    fn from_value(value: LuaValue, function_name: &'static str) -> LuaResult<Self> {
+        match value {
+            LuaValue::Table(timeouts) => {
+                let timeouts = Self {
+                    connect: get_duration(timeouts.raw_get("connect")?, "WebsocketOptions.timeout.connect", function_name)?,
+                    read: get_duration(timeouts.raw_get("read")?, "WebsocketOptions.timeout.read", function_name)?,
+                    write: get_duration(timeouts.raw_get("write")?, "WebsocketOptions.timeout.write", function_name)?,
+                };
+                if timeouts.connect.is_none() && timeouts.read.is_none() && timeouts.write.is_none() {
+                    return wrap_err!("{}: WebsocketOptions.timeout is a table but all the fields we expected (connect, read, write) are nil", function_name);
+                }
+                Ok(timeouts)
+            },
+            LuaNil => Ok(Self::default()),
+            other => {
+                let timeout = get_duration(other, "WebsocketOptions.timeout", function_name)?;
+                Ok(Self { connect: timeout, read: timeout, write: timeout })
+            }
+        }
+    }
+}
+
+fn get_duration(value: LuaValue, field_name: &'static str, function_name: &'static str) -> LuaResult<Option<Duration>> {
+    match value {
+        LuaValue::UserData(ud) if let Ok(duration) = ud.borrow::<TimeDuration>() => {
+            let duration = duration.inner;
+            if !duration.is_positive() {
+                return wrap_err!("{}: {} must be a positive Duration, got: {:#?}", function_name, field_name, duration);
+            }
+            Ok(Some(duration.unsigned_abs()))
+        },
+        LuaValue::Number(_) | LuaValue::Integer(_) => {
+            wrap_err!("{}: {} should be a Duration (from @std/time), not a regular number", function_name, field_name)
+        },
+        LuaNil => Ok(None),
+        other => {
+            wrap_err!("{}: expected {} to be a Duration or nil, got: {:?}", function_name, field_name, other)
+        }
+    }
+}
+
+/// `websocket.connect`'s `WebsocketOptions`
+#[derive(Default)]
+struct WebsocketOptions {
+    headers: Vec<(HeaderName, HeaderValue)>,
+    /// `Sec-WebSocket-Protocol`, from `subprotocols`
+    subprotocols: Option<HeaderValue>,
+    timeouts: WebsocketTimeouts,
+    ping_interval: Option<Duration>,
+    max_message_size: Option<usize>,
+    proxy: Option<ProxySetting>,
+    tls: Option<TlsOptions>,
+}
+
+impl WebsocketOptions {
+    fn from_value(value: Option<LuaValue>, function_name: &'static str) -> LuaResult<Self> {
+        let options = match value {
+            Some(LuaValue::Table(options)) => options,
+            Some(LuaNil) | None => return Ok(Self::default()),
+            Some(other) => {
+                return wrap_err!("{}: expected options to be a WebsocketOptions table or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let mut headers = Vec::new();
+        match options.raw_get("headers")? {
+            LuaValue::Table(header_map) => {
+                for pair in header_map.pairs::<LuaValue, LuaValue>() {
+                    let (key, value) = match pair? {
+                        (LuaValue::String(key), LuaValue::String(value)) => (key, value),
+                        (key, value) => {
+                            return wrap_err!("{}: WebsocketOptions.headers contains invalid key/value types, expected [string] = string, got: [{:?}] = {:?}", function_name, key, value);
+                        }
+                    };
+                    let name = match HeaderName::from_bytes(&key.as_bytes()) {
+                        Ok(name) => name,
+                        Err(err) => {
+                            return wrap_err!("{}: WebsocketOptions.headers: '{}' isn't a valid header name: {}", function_name, key.display(), err);
+                        }
+                    };
+                    let value = match HeaderValue::from_bytes(&value.as_bytes()) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            return wrap_err!("{}: WebsocketOptions.headers: value of header '{}' isn't valid: {}", function_name, key.display(), err);
+                        }
+                    };
+                    headers.push((name, value));
+                }
+            },
+            LuaNil => {},
+            other => {
+                return wrap_err!("{}: expected WebsocketOptions.headers to be {{ [string]: string }} or nil, got: {:?}", function_name, other);
+            }
+        }
+
+        let subprotocols = match options.raw_get("subprotocols")? {
+            LuaValue::Table(protocols) => {
+                let mut names = Vec::new();
+                for protocol in protocols.sequence_values::<LuaValue>() {
+                    match protocol? {
+                        LuaValue::String(protocol) => names.push(protocol.to_string_lossy()),
+                        other => {
+                            return wrap_err!("{}: expected WebsocketOptions.subprotocols to be an array of strings, got an element: {:?}", function_name, other);
+                        }
+                    }
+                }
+                if names.is_empty() {
+                    None
+                } else {
+                    match HeaderValue::from_str(&names.join(", ")) {
+                        Ok(value) => Some(value),
+                        Err(err) => {
+                            return wrap_err!("{}: WebsocketOptions.subprotocols contains invalid protocol names: {}", function_name, err);
+                        }
+                    }
+                }
+            },
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected WebsocketOptions.subprotocols to be an array of strings or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        let max_message_size = match options.raw_get("max_message_size")? {
+            LuaValue::UserData(ud) if let Ok(file_size) = ud.borrow::<FileSize>() => {
+                Some(file_size.as_bytes() as usize)
+            },
+            LuaValue::Number(_) | LuaValue::Integer(_) => {
+                return wrap_err!("{}: expected WebsocketOptions.max_message_size to be a FileSize (userdata) from std/fs/filesize, not a number of bytes", function_name);
+            },
+            LuaNil => None,
+            other => {
+                return wrap_err!("{}: expected WebsocketOptions.max_message_size to be a FileSize from std/fs/filesize or nil, got: {:?}", function_name, other);
+            }
+        };
+
+        Ok(Self {
+            headers,
+            subprotocols,
+            timeouts: WebsocketTimeouts::from_value(options.raw_get("timeout")?, function_name)?,
+            ping_interval: get_duration(options.raw_get("ping_interval")?, "WebsocketOptions.ping_interval", function_name)?,
+            max_message_size,
+            proxy: ProxySetting::from_value(options.raw_get("proxy")?, "WebsocketOptions.proxy", function_name)?,
+            tls: TlsOptions::from_value(options.raw_get("tls")?, "WebsocketOptions.tls", function_name)?,
+        })
+    }
+}
+
 fn websocket_connect(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
-    let function_name = "socket.connect(url: string)";
+    let function_name = "websocket.connect(url: string, options: WebsocketOptions?)";
 
     let url = match multivalue.pop_front() {
         Some(LuaValue::String(url)) => url.to_string_lossy(),
@@ -301,38 +598,99 @@ fn websocket_connect(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResul
         }
     };
 
-    let (websocket, _response) = match connect_with_shared_tls_config(&url) {
+    let options = WebsocketOptions::from_value(multivalue.pop_front(), function_name)?;
+
+    let tls_config = match &options.tls {
+        Some(tls) => match tls.rustls_client_config() {
+            Ok(config) => config,
+            Err(err) => {
+                return wrap_err!("{}: unable to set up WebsocketOptions.tls due to err: {}", function_name, err);
+            }
+        },
+        None => tls_config::rustls_client_config(),
+    };
+
+    let (websocket, response) = match connect_with_shared_tls_config(&url, tls_config, &options) {
         Ok(pair) => pair,
+        Err(tungstenite::Error::Io(err)) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+            return wrap_err!("{}: timed out connecting to websocket at '{}'; consider increasing WebsocketOptions.timeout.connect", function_name, &url);
+        },
         Err(err) => {
             return wrap_err!("{}: unable to connect to websocket at '{}' due to err: {}", function_name, &url, err);
         }
     };
 
-    WebsocketWrapper::new(websocket).get_userdata(luau)
+    // the handshake's done, so switch from the connect timeout to the ones for reading and writing
+    if let Some(socket) = websocket.get_ref().socket() {
+        let result = socket.set_read_timeout(None)
+            .and_then(|_| socket.set_write_timeout(options.timeouts.write));
+        if let Err(err) = result {
+            return wrap_err!("{}: unable to set websocket timeouts due to err: {}", function_name, err);
+        }
+    }
+
+    let protocol = response.headers()
+        .get("sec-websocket-protocol")
+        .and_then(|protocol| protocol.to_str().ok())
+        .map(String::from);
+
+    let mut wrapper = WebsocketWrapper::new(websocket);
+    wrapper.read_timeout = options.timeouts.read;
+    wrapper.ping_interval = options.ping_interval;
+    wrapper.protocol = protocol;
+    wrapper.get_userdata(luau)
 }
 
 /// Like `tungstenite::connect`, but always passes an explicit `rustls` connector built from
 /// `std_net::tls_config` so websockets respect `SSL_CERT_FILE`/`SEAL_SYSTEM_CERTS` the same
 /// way `@std/net/http` requests do (tungstenite's default connector has no way to plug in a
 /// custom CA bundle).
+///
+/// Tunnels through an http proxy (`WebsocketOptions.proxy`, or `HTTP_PROXY`/`HTTPS_PROXY`) with `CONNECT`
+/// when there is one.
 fn connect_with_shared_tls_config(
-    url: &Url
+    url: &Url,
+    tls_config: std::sync::Arc<rustls::ClientConfig>,
+    options: &WebsocketOptions,
 ) -> Result<(TungsteniteWebSocket, tungstenite::handshake::client::Response), tungstenite::Error> {
-    let request = url.as_str().into_client_request()?;
+    let mut request = url.as_str().into_client_request()?;
+    let headers = request.headers_mut();
+    for (name, value) in &options.headers {
+        headers.insert(name.clone(), value.clone());
+    }
+    if let Some(subprotocols) = &options.subprotocols {
+        headers.insert("sec-websocket-protocol", subprotocols.clone());
+    }
 
     let is_tls = url.scheme() == "wss";
     let host = url.host_str().ok_or(tungstenite::Error::Url(tungstenite::error::UrlError::NoHostName))?;
     let port = url.port_or_known_default().unwrap_or(if is_tls { 443 } else { 80 });
 
-    let stream = TcpStream::connect((host, port))?;
+    let connect_timeout = options.timeouts.connect;
+    let stream = match proxy::websocket_proxy(options.proxy.as_ref(), host) {
+        Some(proxy) => proxy::connect_tunnel(&proxy, host, port, connect_timeout)?,
+        None => tcp::connect_any(&url.socket_addrs(|| Some(port))?, connect_timeout)?,
+    };
     stream.set_nodelay(true)?;
+    // the handshake counts as connecting
+    stream.set_read_timeout(connect_timeout)?;
+    stream.set_write_timeout(connect_timeout)?;
 
-    let connector = Connector::Rustls(tls_config::rustls_client_config());
+    let connector = Connector::Rustls(tls_config);
 
-    match tungstenite::client_tls_with_config(request, stream, None, Some(connector)) {
+    let mut config = WebSocketConfig::default();
+    if let Some(max_message_size) = options.max_message_size {
+        config.max_message_size = Some(max_message_size);
+        config.max_frame_size = Some(max_message_size);
+    }
+
+    match tungstenite::client_tls_with_config(request, stream, Some(config), Some(connector)) {
         Ok(pair) => Ok(pair),
         Err(tungstenite::HandshakeError::Failure(err)) => Err(err),
-        Err(tungstenite::HandshakeError::Interrupted(_)) => unreachable!("blocking handshake should never be interrupted"),
+        // the stream only ever blocks, so this only happens when the connect timeout elapses mid-handshake
+        Err(tungstenite::HandshakeError::Interrupted(_)) => {
+            Err(tungstenite::Error::Io(io::Error::new(io::ErrorKind::TimedOut, "timed out during the websocket handshake")))
+        },
     }
 }
 
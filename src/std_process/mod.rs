@@ -1,17 +1,25 @@
 use core::str;
 use std::cell::RefCell;
-use std::io::{self, Write};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
 use std::process::{self, Command, Output};
 use std::rc::Rc;
+use std::time::Duration;
 
 use crate::{prelude::*, std_err};
 use crate::std_env;
 use mluau::prelude::*;
 
-mod stream;
+pub mod stream;
 mod options;
+mod pipeline;
+mod pty;
+mod signal;
+mod status;
 use stream::Stream;
-use options::{RunOptions, SpawnOptions, Shell};
+use options::{ExtraEnvs, RunOptions, SpawnOptions, Shell};
+use status::ChildState;
 
 fn run_result_unwrap_or(luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
     let function_name = "RunResult:unwrap_or(default: string | (result: RunResult) -> string)";
@@ -88,11 +96,14 @@ fn trim_end_or_return(vec: &[u8]) -> &[u8] {
 }
 
 fn create_run_result_table(luau: &Lua, output: Output) -> LuaValueResult {
-    let ok = output.status.success();
-    let stdout = output.stdout.clone();
-    let stderr = output.stderr.clone();
+    let run_result = run_result_builder(luau, output.status.success(), output.stdout, output.stderr)?
+        .build_readonly();
+    ok_table(run_result)
+}
 
-    let run_result = TableBuilder::create(luau)?
+/// The `RunResult` table for a process (or pipeline) that's finished; left unbuilt so pipelines can add their statuses.
+fn run_result_builder(luau: &Lua, ok: bool, stdout: Vec<u8>, stderr: Vec<u8>) -> LuaResult<TableBuilder<'_>> {
+    TableBuilder::create(luau)?
         .with_value("ok", ok)?
         .with_value("out", {
             if ok {
@@ -123,43 +134,47 @@ fn create_run_result_table(luau: &Lua, output: Output) -> LuaValueResult {
                 }
             }
         }, signatures::STD_PROCESS_RUN_RESULT_UNWRAP)?
-        .with_function_and_signature("unwrap_or", run_result_unwrap_or, signatures::STD_PROCESS_RUN_RESULT_UNWRAP_OR)?
-        .build_readonly();
-
-    ok_table(run_result)
+        .with_function_and_signature("unwrap_or", run_result_unwrap_or, signatures::STD_PROCESS_RUN_RESULT_UNWRAP_OR)
 }
 
-fn run_command(options: RunOptions) -> io::Result<Output> {
-    let shell_switches = match options.shell {
-        Some(ref shell) => shell.get_switches(),
-        None => Vec::new(),
-    };
-
-    let mut command = if let Some(ref shell) = options.shell {
+/// Builds the `Command` that runs `program` (through `shell` if there is one) for `process.run`,
+/// `process.spawn`, and pipelines; callers set up its stdio.
+fn build_command(
+    program: String,
+    args: Option<Vec<String>>,
+    shell: Option<&Shell>,
+    cwd: Option<PathBuf>,
+    extra_envs: Option<ExtraEnvs>,
+) -> Command {
+    let mut command = if let Some(shell) = shell {
         let mut command = Command::new(shell.program_name());
-        command.args(shell_switches).arg(options.program);
-        if let Some(args) = options.args {
+        command.args(shell.get_switches()).arg(program);
+        if let Some(args) = args {
             command.arg(args.join(" "));
         }
         command
     } else {
-        let mut command = Command::new(options.program);
-        if let Some(args) = options.args {
+        let mut command = Command::new(program);
+        if let Some(args) = args {
             command.args(args);
         }
         command
     };
 
-    if let Some(cwd) = options.cwd {
+    if let Some(cwd) = cwd {
         command.current_dir(cwd);
     }
 
-    if let Some(extra) = options.extra_envs {
+    if let Some(extra) = extra_envs {
         extra.apply(&mut command);
     }
 
-    options.stdio.apply(&mut command);
+    command
+}
 
+fn run_command(options: RunOptions) -> io::Result<Output> {
+    let mut command = build_command(options.program, options.args, options.shell.as_ref(), options.cwd, options.extra_envs);
+    options.stdio.apply(&mut command);
     command.output()
 }
 
@@ -215,6 +230,9 @@ fn process_shell(luau: &Lua, shell_command: LuaValue) -> LuaValueResult {
     }
 }
 
+/// how long `ChildProcess:terminate()` gives a child to exit after SIGTERM before it gets SIGKILLed
+const DEFAULT_TERMINATE_GRACE: Duration = Duration::from_secs(5);
+
 fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
     let function_name = "process.spawn(options: SpawnOptions)";
     let options = match spawn_options {
@@ -227,68 +245,188 @@ fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
         }
     };
 
-    let shell_switches = match options.shell {
-        Some(ref shell) => shell.get_switches(),
-        None => Vec::new(),
+    let spawned = spawn_child(luau, options, function_name, None)?;
+    ok_table(spawned.handle.build_readonly())
+}
+
+/// How a child's stdio gets wired up when it's a stage of `process.spawn_pipeline`.
+struct PipelineStage {
+    /// the previous stage's stdout, which becomes this stage's stdin
+    stdin: Option<process::Stdio>,
+    /// every stage but the last pipes its stdout into the next stage instead of a `ChildProcessStream`
+    pipe_stdout: bool,
+}
+
+struct SpawnedChild<'luau> {
+    /// the `ChildProcess` handle, left unbuilt so pipelines can add to it
+    handle: TableBuilder<'luau>,
+    state: Rc<RefCell<ChildState>>,
+    /// the child's stdout, if it's meant to be piped into the next stage of a pipeline
+    stdout: Option<process::ChildStdout>,
+}
+
+/// Writes all of `data` to the child's stdin and flushes it, leaving stdin open for later writes.
+///
+/// Errors if stdin was already closed; returns an `error` (instead of throwing) if the write itself fails.
+fn write_stdin(luau: &Lua, stdin_cell: &RefCell<Option<Box<dyn Write>>>, data: &[u8], function_name: &'static str) -> LuaValueResult {
+    let mut cell = match stdin_cell.try_borrow_mut() {
+        Ok(cell) => cell,
+        Err(_) => {
+            unreachable!("{}: stdin already borrowed; this shouldn't happen as Luau VM is single threaded and multithreaded code should never touch this???", function_name);
+        }
     };
+    // borrow stdin in place (don't take() it) so it stays open for subsequent writes
+    let stdin = match cell.as_mut() {
+        Some(stdin) => stdin,
+        None => {
+            return wrap_err!("{}: attempt to write to closed stdin", function_name);
+        }
+    };
+
+    match stdin.write_all(data).and_then(|_| stdin.flush()) {
+        Ok(_) => Ok(LuaNil),
+        Err(err) => {
+            std_err::WrappedError::from_message(format!("{} can't write to stdin due to err: {}", function_name, err)).get_userdata(luau)
+        }
+    }
+}
+
+fn spawn_child<'luau>(
+    luau: &'luau Lua,
+    options: SpawnOptions,
+    function_name: &'static str,
+    stage: Option<PipelineStage>,
+) -> LuaResult<SpawnedChild<'luau>> {
+    #[cfg(not(unix))]
+    if options.pty.is_some() {
+        return wrap_err!("{}: SpawnOptions.pty is only supported on unix-like platforms", function_name);
+    }
+    if stage.is_some() && options.pty.is_some() {
+        return wrap_err!("{}: pipeline stages can't use SpawnOptions.pty", function_name);
+    }
+
+    // signals (including kill) go to the child's whole process group if it has its own
+    let grouped = cfg!(unix) && (options.process_group || options.new_session || options.pty.is_some());
+    let pipe_stdout = stage.as_ref().is_some_and(|stage| stage.pipe_stdout);
+    // the master side of the child's pty, which is both its stdout and stdin
+    #[cfg_attr(not(unix), expect(unused_mut))]
+    let mut pty_master: Option<File> = None;
 
     let mut child = {
-        let mut command = if let Some(ref shell) = options.shell {
-            let mut command = Command::new(shell.program_name());
-            command.args(shell_switches).arg(options.program);
-            if let Some(args) = options.args {
-                command.arg(args.join(" "));
+        let mut command = build_command(options.program, options.args, options.shell.as_ref(), options.cwd, options.extra_envs);
+
+        options.stdio.apply(&mut command);
+        if let Some(stage) = stage {
+            if let Some(stdin) = stage.stdin {
+                command.stdin(stdin);
             }
-            command
-        } else {
-            let mut command = Command::new(options.program);
-            if let Some(args) = options.args {
-                command.args(args);
+            if stage.pipe_stdout {
+                command.stdout(process::Stdio::piped());
             }
-            command
-        };
-
-        if let Some(cwd) = options.cwd {
-            command.current_dir(cwd);
         }
 
-        if let Some(extra) = options.extra_envs {
-            extra.apply(&mut command);
-        }
+        #[cfg(unix)]
+        let pty = match options.pty {
+            Some(size) => {
+                let pty = match pty::Pty::open(size) {
+                    Ok(pty) => pty,
+                    Err(err) => {
+                        return wrap_err!("{}: unable to open a pty due to err: {}", function_name, err);
+                    }
+                };
+                if let Err(err) = pty.attach(&mut command) {
+                    return wrap_err!("{}: unable to attach pty to child due to err: {}", function_name, err);
+                }
+                Some(pty)
+            },
+            None => None,
+        };
 
-        options.stdio.apply(&mut command);
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            if options.pty.is_some() {
+                // attaching the pty already starts the child in a new session
+            } else if options.new_session {
+                // SAFETY: setsid is async-signal-safe, which is all pre_exec needs from us
+                unsafe {
+                    command.pre_exec(|| {
+                        if libc::setsid() == -1 {
+                            return Err(io::Error::last_os_error());
+                        }
+                        Ok(())
+                    });
+                }
+            } else if options.process_group {
+                command.process_group(0);
+            }
+        }
 
-        match command.spawn() {
+        let child = match command.spawn() {
             Ok(child) => child,
             Err(err) => {
-                return wrap_err!("process.spawn failed to execute process: {} (did you forget to set shell = true | string)", err);
+                return wrap_err!("{} failed to execute process: {} (did you forget to set shell = true | string)", function_name, err);
             }
+        };
+
+        #[cfg(unix)]
+        if let Some(pty) = pty {
+            pty_master = Some(pty.into_master());
         }
+
+        child
     };
 
     let child_id = child.id();
-    let stdout = child.stdout.take();
+    let (stdout, piped_stdout) = if pipe_stdout {
+        (None, child.stdout.take())
+    } else {
+        (child.stdout.take(), None)
+    };
     let stderr = child.stderr.take();
-    let stdin = child.stdin.take();
+    let is_pty = pty_master.is_some();
+    let stdin: Option<Box<dyn Write>> = match pty_master {
+        Some(ref master) => match master.try_clone() {
+            Ok(writer) => Some(Box::new(writer)),
+            Err(err) => {
+                return wrap_err!("{}: unable to clone pty for writing due to err: {}", function_name, err);
+            }
+        },
+        None => child.stdin.take().map(|stdin| Box::new(stdin) as Box<dyn Write>),
+    };
 
-    let child_cell = Rc::new(RefCell::new(child));
+    let child_cell = Rc::new(RefCell::new(ChildState::new(child, grouped)));
     // Shared owner of the child's stdin pipe. We keep it open across writes (so stdin is writable
     // multiple times), but drop it — closing the fd — as soon as the child is killed or observed to
     // have exited, so we don't retain a dead pipe fd per child until the handle is GC'd (Luau has no
     // __gc). `child.stdin:close()` and handle GC also drop it.
-    let stdin_cell: Rc<RefCell<Option<process::ChildStdin>>> = Rc::new(RefCell::new(stdin));
+    let stdin_cell: Rc<RefCell<Option<Box<dyn Write>>>> = Rc::new(RefCell::new(stdin));
 
     let child_process_handle = {
-        let stdout_handle = if let Some(stdout) = stdout {
-            let stdout_stream = Stream::new(
+        // a pty child's stdout and stderr both come out of the pty's master side
+        let stdout: Option<Box<dyn Read + Send>> = match stdout {
+            Some(stdout) => Some(Box::new(stdout)),
+            None => match pty::reader(pty_master.as_ref()) {
+                Ok(reader) => reader,
+                Err(err) => {
+                    return wrap_err!("{}: unable to clone pty for reading due to err: {}", function_name, err);
+                }
+            },
+        };
+
+        // ChildProcess:expect() reads from stdout too, so it needs to share stdout's Stream
+        let stdout_cell = match stdout {
+            Some(stdout) => Some(Rc::new(RefCell::new(Stream::new(
                 function_name,
                 stdout,
                 stream::StreamType::Stdout,
                 options.stdout_capacity,
                 options.stdout_truncate,
-            )?;
-            let stdout_cell = Rc::new(RefCell::new(stdout_stream));
-            Some(ok_table(Stream::create_handle(stdout_cell, luau))?)
+            )?))),
+            None => None,
+        };
+        let stdout_handle = if let Some(ref stdout_cell) = stdout_cell {
+            Some(ok_table(Stream::create_handle(Rc::clone(stdout_cell), luau))?)
         } else {
             None
         };
@@ -326,27 +464,7 @@ fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
                                 return wrap_err!("{} expected data to be string or buffer, unexpectedly got nothing (not even nil)", function_name);
                             }
                         };
-    
-                        let mut cell = match stdin_cell_write.try_borrow_mut() {
-                            Ok(cell) => cell,
-                            Err(_) => {
-                                unreachable!("{}: stdin already borrowed; this shouldn't happen as Luau VM is single threaded and multithreaded code should never touch this???", function_name);
-                            }
-                        };
-                        // borrow stdin in place (don't take() it) so it stays open for subsequent writes
-                        let stdin = match cell.as_mut() {
-                            Some(stdin) => stdin,
-                            None => {
-                                return wrap_err!("{}: attempt to write to closed stdin", function_name);
-                            }
-                        };
-
-                        match stdin.write_all(&data_to_write).and_then(|_| stdin.flush()) {
-                            Ok(_) => Ok(LuaNil),
-                            Err(err) => {
-                                std_err::WrappedError::from_message(format!("{} can't write to stdin due to err: {}", function_name, err)).get_userdata(luau)
-                            }
-                        }
+                        write_stdin(luau, &stdin_cell_write, &data_to_write, function_name)
                     }
                 })?
                 .with_function_mut("close", {
@@ -366,6 +484,11 @@ fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
                             }
                         };
     
+                        // a pty doesn't have a pipe to close, so send it the EOF character (Ctrl-D) instead
+                        if is_pty && stdin.write_all(b"\x04").is_err() {
+                            return wrap_err!("{}: unable to send EOF to pty", function_name);
+                        }
+
                         if stdin.flush().is_err() {
                             return wrap_err!("{}: unable to flush stdin", function_name);
                         }
@@ -384,6 +507,38 @@ fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
             .with_value("stdout", stdout_handle.unwrap_or(LuaNil))?
             .with_value("stderr", stderr_handle.unwrap_or(LuaNil))?
             .with_value("stdin", stdin_handle.unwrap_or(LuaNil))?
+            .with_function_and_signature("expect", {
+                move |luau: &Lua, mut multivalue: LuaMultiValue| -> LuaValueResult {
+                    let function_name = "ChildProcess:expect(pattern: string | { regex: string }, timeout: Duration | number?)";
+                    pop_self(&mut multivalue, function_name)?;
+                    let pattern = stream::ExpectPattern::from_value(multivalue.pop_front(), function_name)?;
+                    let timeout = stream::parse_timeout_value(multivalue.pop_front(), function_name)?;
+
+                    let Some(ref stdout_cell) = stdout_cell else {
+                        return wrap_err!("{}: child (pid {}) has no stdout to read from because its stdout isn't piped to seal", function_name, child_id);
+                    };
+                    match stdout_cell.try_borrow_mut() {
+                        Ok(ref mut stream) => stream.expect(luau, &pattern, timeout, function_name),
+                        Err(_) => wrap_err!("{}: stream already borrowed", function_name)
+                    }
+                }
+            }, signatures::STD_PROCESS_CHILD_PROCESS_EXPECT)?
+            .with_function_and_signature("sendline", {
+                let stdin_cell = Rc::clone(&stdin_cell);
+                move |luau: &Lua, mut multivalue: LuaMultiValue| -> LuaValueResult {
+                    let function_name = "ChildProcess:sendline(line: string?)";
+                    pop_self(&mut multivalue, function_name)?;
+                    let mut line = match multivalue.pop_front() {
+                        Some(LuaValue::String(line)) => line.as_bytes().to_vec(),
+                        Some(LuaNil) | None => Vec::new(),
+                        Some(other) => {
+                            return wrap_err!("{} expected line to be a string or nil, got: {:?}", function_name, other);
+                        }
+                    };
+                    line.push(b'\n');
+                    write_stdin(luau, &stdin_cell, &line, function_name)
+                }
+            }, signatures::STD_PROCESS_CHILD_PROCESS_SENDLINE)?
             .with_function_and_signature("alive", {
                 let child_cell = Rc::clone(&child_cell);
                 let stdin_cell = Rc::clone(&stdin_cell);
@@ -394,7 +549,7 @@ fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
                     };
 
                     match child.try_wait() {
-                        Ok(Some(_status)) => {
+                        Ok(Some(_exit)) => {
                             // child has exited; drop its stdin pipe (if still open) to free the fd
                             let _ = stdin_cell.borrow_mut().take();
                             Ok(LuaValue::Boolean(false))
@@ -429,10 +584,129 @@ fn process_spawn(luau: &Lua, spawn_options: LuaValue) -> LuaValueResult {
                     }
                 }
             }, signatures::STD_PROCESS_CHILD_PROCESS_KILL)?
-            .build_readonly()
+            .with_function_and_signature("wait", {
+                let child_cell = Rc::clone(&child_cell);
+                let stdin_cell = Rc::clone(&stdin_cell);
+                move |luau: &Lua, mut multivalue: LuaMultiValue| -> LuaValueResult {
+                    let function_name = "ChildProcess:wait(timeout: Duration | number?)";
+                    pop_self(&mut multivalue, function_name)?;
+                    let timeout = stream::parse_timeout_value(multivalue.pop_front(), function_name)?;
+
+                    let Ok(mut child) = child_cell.try_borrow_mut() else {
+                        unreachable!("{}: child (pid {}) already borrowed; this is likely a seal bug because there isn't anything multithreaded that should touch this.", function_name, child_id);
+                    };
+
+                    // like std's Child::wait, close stdin before waiting forever so a child that reads
+                    // until EOF doesn't wait on us while we wait on it
+                    if timeout.is_none() {
+                        let _ = stdin_cell.borrow_mut().take();
+                    }
+
+                    match child.wait(timeout) {
+                        Ok(Some(exit)) => {
+                            let _ = stdin_cell.borrow_mut().take();
+                            exit.to_table(luau)
+                        },
+                        Ok(None) => Ok(LuaNil),
+                        Err(err) => {
+                            wrap_err!("{}: unable to wait on child (pid {}) due to err: {}", function_name, child_id, err)
+                        }
+                    }
+                }
+            }, signatures::STD_PROCESS_CHILD_PROCESS_WAIT)?
+            .with_function_and_signature("try_wait", {
+                let child_cell = Rc::clone(&child_cell);
+                let stdin_cell = Rc::clone(&stdin_cell);
+                move |luau: &Lua, mut multivalue: LuaMultiValue| -> LuaValueResult {
+                    let function_name = "ChildProcess:try_wait()";
+                    pop_self(&mut multivalue, function_name)?;
+
+                    let Ok(mut child) = child_cell.try_borrow_mut() else {
+                        unreachable!("{}: child (pid {}) already borrowed; this is likely a seal bug because there isn't anything multithreaded that should touch this.", function_name, child_id);
+                    };
+
+                    match child.try_wait() {
+                        Ok(Some(exit)) => {
+                            let _ = stdin_cell.borrow_mut().take();
+                            exit.to_table(luau)
+                        },
+                        Ok(None) => Ok(LuaNil),
+                        Err(err) => {
+                            wrap_err!("{}: unable to check whether child (pid {}) exited due to err: {}", function_name, child_id, err)
+                        }
+                    }
+                }
+            }, signatures::STD_PROCESS_CHILD_PROCESS_TRY_WAIT)?
+            .with_function_and_signature("signal", {
+                let child_cell = Rc::clone(&child_cell);
+                move |_luau: &Lua, mut multivalue: LuaMultiValue| -> LuaEmptyResult {
+                    let function_name = "ChildProcess:signal(signal: Signal | number)";
+                    pop_self(&mut multivalue, function_name)?;
+                    let signal = signal::parse_signal(multivalue.pop_front(), function_name)?;
+
+                    let Ok(mut child) = child_cell.try_borrow_mut() else {
+                        unreachable!("{}: child (pid {}) already borrowed; this is likely a seal bug because there isn't anything multithreaded that should touch this.", function_name, child_id);
+                    };
+
+                    match child.signal(signal) {
+                        Ok(()) => Ok(()),
+                        Err(err) => {
+                            wrap_err!("{}: unable to send signal {} to child (pid {}) due to err: {}", function_name, signal, child_id, err)
+                        }
+                    }
+                }
+            }, signatures::STD_PROCESS_CHILD_PROCESS_SIGNAL)?
+            .with_function_and_signature("terminate", {
+                let child_cell = Rc::clone(&child_cell);
+                let stdin_cell = Rc::clone(&stdin_cell);
+                move |luau: &Lua, mut multivalue: LuaMultiValue| -> LuaValueResult {
+                    let function_name = "ChildProcess:terminate(grace: Duration | number?)";
+                    pop_self(&mut multivalue, function_name)?;
+                    let grace = stream::parse_timeout_value(multivalue.pop_front(), function_name)?
+                        .unwrap_or(DEFAULT_TERMINATE_GRACE);
+
+                    let Ok(mut child) = child_cell.try_borrow_mut() else {
+                        unreachable!("{}: child (pid {}) already borrowed; this is likely a seal bug because there isn't anything multithreaded that should touch this.", function_name, child_id);
+                    };
+
+                    match child.terminate(grace) {
+                        Ok(exit) => {
+                            let _ = stdin_cell.borrow_mut().take();
+                            exit.to_table(luau)
+                        },
+                        Err(err) => {
+                            wrap_err!("{}: unable to terminate child (pid {}) due to err: {}", function_name, child_id, err)
+                        }
+                    }
+                }
+            }, signatures::STD_PROCESS_CHILD_PROCESS_TERMINATE)?
+    };
+
+    #[cfg(unix)]
+    let child_process_handle = match pty_master {
+        Some(master) => child_process_handle.with_function_and_signature("resize", {
+            move |_luau: &Lua, mut multivalue: LuaMultiValue| -> LuaEmptyResult {
+                let function_name = "PtyChild:resize(rows: number, cols: number)";
+                pop_self(&mut multivalue, function_name)?;
+                let rows = multivalue.pop_front().unwrap_or(LuaNil);
+                let cols = multivalue.pop_front().unwrap_or(LuaNil);
+                let size = pty::PtySize::from_args(rows, cols, function_name)?;
+                match pty::resize(&master, size) {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        wrap_err!("{}: unable to resize pty of child (pid {}) due to err: {}", function_name, child_id, err)
+                    }
+                }
+            }
+        }, signatures::STD_PROCESS_PTY_CHILD_RESIZE)?,
+        None => child_process_handle,
     };
 
-    ok_table(child_process_handle)
+    Ok(SpawnedChild {
+        handle: child_process_handle,
+        state: child_cell,
+        stdout: piped_stdout,
+    })
 }
 
 fn set_exit_callback(luau: &Lua, f: Option<LuaValue>) -> LuaValueResult {
@@ -500,6 +774,8 @@ pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
     TableBuilder::create(luau)?
         .with_function_and_signature("run", process_run, signatures::STD_PROCESS_RUN)?
         .with_function_and_signature("spawn", process_spawn, signatures::STD_PROCESS_SPAWN)?
+        .with_function_and_signature("pipeline", pipeline::process_pipeline, signatures::STD_PROCESS_PIPELINE)?
+        .with_function_and_signature("spawn_pipeline", pipeline::process_spawn_pipeline, signatures::STD_PROCESS_SPAWN_PIPELINE)?
         .with_function_and_signature("shell", process_shell, signatures::STD_PROCESS_SHELL)?
         .with_function_and_signature("setexitcallback", set_exit_callback, c"process.setexitcallback(callback: (code: number) -> ())")?
         .with_function_and_signature("exit", exit, signatures::STD_PROCESS_EXIT)?
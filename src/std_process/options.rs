@@ -9,6 +9,7 @@ use crate::std_env::get_current_shell;
 use crate::std_fs::file_size::{FileSize, KILOBYTE};
 
 use super::stream::TruncateSide;
+use super::pty::PtySize;
 
 const DEFAULT_STDOUT_CAPACITY: usize = 200 * KILOBYTE as usize;
 const DEFAULT_STDERR_CAPACITY: usize = 100 * KILOBYTE as usize;
@@ -342,6 +343,13 @@ pub struct SpawnOptions {
     pub stderr_capacity: usize,
     pub stdout_truncate: TruncateSide,
     pub stderr_truncate: TruncateSide,
+
+    /// start the child in its own process group (unix only)
+    pub process_group: bool,
+    /// start the child in its own session, which also gives it its own process group (unix only)
+    pub new_session: bool,
+    /// run the child attached to a pseudo-terminal of this size (unix only)
+    pub pty: Option<PtySize>,
 }
 
 impl SpawnOptions {
@@ -408,6 +416,24 @@ impl SpawnOptions {
             stderr_truncate
         ) = Self::extract_stream_fields(spawn_options.raw_get("stream")?)?;
 
+        let process_group = match spawn_options.raw_get("process_group")? {
+            LuaValue::Boolean(b) => b,
+            LuaNil => false,
+            other => {
+                return wrap_err!("{} expected SpawnOptions.process_group to be a boolean or nil (default false), got: {:?}", function_name, other);
+            }
+        };
+
+        let new_session = match spawn_options.raw_get("new_session")? {
+            LuaValue::Boolean(b) => b,
+            LuaNil => false,
+            other => {
+                return wrap_err!("{} expected SpawnOptions.new_session to be a boolean or nil (default false), got: {:?}", function_name, other);
+            }
+        };
+
+        let pty = PtySize::from_value(spawn_options.raw_get("pty")?, function_name)?;
+
         // TODO: implement detached/daemon processes
         // let detached = match spawn_options.raw_get("detached")? {
         //     LuaValue::Boolean(b) => b,
@@ -428,6 +454,9 @@ impl SpawnOptions {
             stderr_capacity,
             stdout_truncate,
             stderr_truncate,
+            process_group,
+            new_session,
+            pty,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+use std::io::{self, Read};
+use std::process::{self, ChildStdout};
+use std::thread;
+
+use crate::prelude::*;
+use mluau::prelude::*;
+
+use super::options::{RunOptions, SpawnOptions};
+use super::status::{ChildState, ExitInfo};
+use super::{build_command, run_result_builder, spawn_child, PipelineStage};
+
+/// Collects each stage's options from a `{ RunOptions }` or `{ SpawnOptions }` table, erroring if there aren't any.
+fn stage_tables(stages: LuaValue, function_name: &'static str, what: &'static str) -> LuaResult<Vec<LuaTable>> {
+    let stages = match stages {
+        LuaValue::Table(stages) => stages,
+        LuaNil => {
+            return wrap_err!("{} expected a list of {}Options tables (one per stage), got nil", function_name, what);
+        },
+        other => {
+            return wrap_err!("{} expected a list of {}Options tables (one per stage), got: {:?}", function_name, what, other);
+        }
+    };
+
+    let mut tables = Vec::new();
+    for (index, stage) in stages.sequence_values::<LuaValue>().enumerate() {
+        match stage? {
+            LuaValue::Table(stage) => tables.push(stage),
+            other => {
+                return wrap_err!("{}: expected stage {} to be a {}Options table, got: {:?}", function_name, index + 1, what, other);
+            }
+        }
+    }
+
+    if tables.is_empty() {
+        return wrap_err!("{}: a pipeline needs at least one stage", function_name);
+    }
+    Ok(tables)
+}
+
+struct PipelineOutput {
+    /// the last stage's stdout
+    stdout: Vec<u8>,
+    /// every stage's stderr, in stage order
+    stderr: Vec<u8>,
+    exits: Vec<ExitInfo>,
+}
+
+fn read_all(mut reader: impl Read) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    // a stage that dies midway just means we got less output; its ExitStatus says what happened
+    let _ = reader.read_to_end(&mut bytes);
+    bytes
+}
+
+/// Kills and reaps stages that already started when a later one couldn't, so they don't linger waiting on a pipe.
+fn abandon(children: &mut [ChildState]) {
+    for child in children {
+        let _ = child.kill();
+        let _ = child.wait(None);
+    }
+}
+
+/// Runs every stage with each stage's stdout piped into the next stage's stdin, returning
+/// the index of the stage that went wrong if something does.
+fn run_pipeline(stages: Vec<RunOptions>) -> Result<PipelineOutput, (usize, io::Error)> {
+    let last = stages.len() - 1;
+    let mut children: Vec<ChildState> = Vec::with_capacity(stages.len());
+    let mut stderr_readers = Vec::with_capacity(stages.len());
+    let mut previous_stdout: Option<ChildStdout> = None;
+    let mut last_stdout = None;
+
+    for (index, options) in stages.into_iter().enumerate() {
+        let mut command = build_command(options.program, options.args, options.shell.as_ref(), options.cwd, options.extra_envs);
+        options.stdio.apply(&mut command);
+        if let Some(stdout) = previous_stdout.take() {
+            command.stdin(stdout);
+        }
+        if index != last {
+            command.stdout(process::Stdio::piped());
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                abandon(&mut children);
+                return Err((index, err));
+            }
+        };
+
+        // nothing writes to the first stage's stdin, so close it like process.run does
+        drop(child.stdin.take());
+        if index == last {
+            last_stdout = child.stdout.take();
+        } else {
+            previous_stdout = child.stdout.take();
+        }
+        if let Some(stderr) = child.stderr.take() {
+            stderr_readers.push(thread::spawn(move || read_all(stderr)));
+        }
+        children.push(ChildState::new(child, false));
+    }
+
+    let stdout = last_stdout.map(read_all).unwrap_or_default();
+    let mut stderr = Vec::new();
+    for reader in stderr_readers {
+        if let Ok(bytes) = reader.join() {
+            stderr.extend(bytes);
+        }
+    }
+
+    let mut exits = Vec::with_capacity(children.len());
+    for (index, child) in children.iter_mut().enumerate() {
+        match child.wait(None) {
+            Ok(Some(exit)) => exits.push(exit),
+            Ok(None) => unreachable!("waiting without a timeout always returns the child's exit"),
+            Err(err) => return Err((index, err)),
+        }
+    }
+
+    Ok(PipelineOutput { stdout, stderr, exits })
+}
+
+pub fn process_pipeline(luau: &Lua, stages: LuaValue) -> LuaValueResult {
+    let function_name = "process.pipeline(stages: { RunOptions })";
+    let mut stages_options = Vec::new();
+    for stage in stage_tables(stages, function_name, "Run")? {
+        stages_options.push(RunOptions::from_table(luau, &stage, function_name, "Run")?);
+    }
+    let programs: Vec<String> = stages_options.iter().map(|options| options.program.clone()).collect();
+
+    let output = match run_pipeline(stages_options) {
+        Ok(output) => output,
+        Err((index, err)) => {
+            return wrap_err!(
+                "{} was unable to run stage {} ('{}') due to err {}; did you forget to set shell = true?",
+                function_name, index + 1, programs[index], err
+            );
+        }
+    };
+
+    // pipefail: the pipeline only succeeded if every stage did
+    let ok = output.exits.iter().all(|exit| exit.success());
+    let statuses = {
+        let mut statuses = Vec::with_capacity(output.exits.len());
+        for exit in output.exits {
+            statuses.push(exit.to_table(luau)?);
+        }
+        luau.create_sequence_from(statuses)?
+    };
+    statuses.set_readonly(true);
+
+    let pipeline_result = run_result_builder(luau, ok, output.stdout, output.stderr)?
+        .with_value("statuses", statuses)?
+        .build_readonly();
+
+    ok_table(pipeline_result)
+}
+
+pub fn process_spawn_pipeline(luau: &Lua, stages: LuaValue) -> LuaValueResult {
+    let function_name = "process.spawn_pipeline(stages: { SpawnOptions })";
+    let mut stages_options = Vec::new();
+    for stage in stage_tables(stages, function_name, "Spawn")? {
+        stages_options.push(SpawnOptions::from_table(luau, stage, function_name)?);
+    }
+
+    let last = stages_options.len() - 1;
+    let stage_handles = luau.create_table_with_capacity(stages_options.len(), 0)?;
+    let mut states = Vec::with_capacity(stages_options.len());
+    let mut previous_stdout: Option<ChildStdout> = None;
+
+    for (index, options) in stages_options.into_iter().enumerate() {
+        let stage = PipelineStage {
+            stdin: previous_stdout.take().map(process::Stdio::from),
+            pipe_stdout: index != last,
+        };
+        let spawned = match spawn_child(luau, options, function_name, Some(stage)) {
+            Ok(spawned) => spawned,
+            Err(err) => {
+                for state in &states {
+                    let mut child = state.borrow_mut();
+                    let _ = child.kill();
+                    let _ = child.wait(None);
+                }
+                return Err(err);
+            }
+        };
+        previous_stdout = spawned.stdout;
+        states.push(spawned.state);
+
+        if index == last {
+            // the last stage's handle is what we return, so it lists every stage including itself
+            let handle = spawned.handle
+                .with_value("stages", stage_handles.clone())?
+                .build_readonly()?;
+            stage_handles.raw_push(handle.clone())?;
+            stage_handles.set_readonly(true);
+            return Ok(LuaValue::Table(handle));
+        }
+        stage_handles.raw_push(spawned.handle.build_readonly()?)?;
+    }
+
+    unreachable!("{}: the last stage always returns", function_name)
+}
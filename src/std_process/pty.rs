@@ -0,0 +1,206 @@
+use std::fs::File;
+use std::io::{self, Read};
+
+use mluau::prelude::*;
+
+use crate::prelude::*;
+
+const DEFAULT_ROWS: u16 = 24;
+const DEFAULT_COLS: u16 = 80;
+
+/// How big a child's pseudo-terminal is, in characters.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(unix), expect(dead_code))]
+pub struct PtySize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl Default for PtySize {
+    fn default() -> Self {
+        Self {
+            rows: DEFAULT_ROWS,
+            cols: DEFAULT_COLS,
+        }
+    }
+}
+
+fn dimension_from_value(value: LuaValue, function_name: &'static str, name: &'static str, default: u16) -> LuaResult<u16> {
+    match value {
+        LuaValue::Integer(i) => match u16::try_from(i) {
+            Ok(n) if n > 0 => Ok(n),
+            _ => wrap_err!("{}: {} must be between 1 and {}, got: {}", function_name, name, u16::MAX, i),
+        },
+        LuaValue::Number(f) => {
+            wrap_err!("{}: {} must be a whole number, got: {}", function_name, name, f)
+        },
+        LuaNil => Ok(default),
+        other => {
+            wrap_err!("{}: expected {} to be a number or nil (default {}), got: {:?}", function_name, name, default, other)
+        }
+    }
+}
+
+impl PtySize {
+    /// Parses `SpawnOptions.pty`, which is `true` for a default-sized pty or `{ rows: number?, cols: number? }`.
+    pub fn from_value(value: LuaValue, function_name: &'static str) -> LuaResult<Option<Self>> {
+        match value {
+            LuaValue::Boolean(true) => Ok(Some(Self::default())),
+            LuaValue::Boolean(false) | LuaNil => Ok(None),
+            LuaValue::Table(size) => Ok(Some(Self {
+                rows: dimension_from_value(size.raw_get("rows")?, function_name, "SpawnOptions.pty.rows", DEFAULT_ROWS)?,
+                cols: dimension_from_value(size.raw_get("cols")?, function_name, "SpawnOptions.pty.cols", DEFAULT_COLS)?,
+            })),
+            other => {
+                wrap_err!("{} expected SpawnOptions.pty to be a boolean, a {{ rows: number?, cols: number? }} table, or nil, got: {:?}", function_name, other)
+            }
+        }
+    }
+
+    /// Parses the `rows` and `cols` passed to `ChildProcess:resize()`.
+    #[cfg_attr(not(unix), expect(dead_code))]
+    pub fn from_args(rows: LuaValue, cols: LuaValue, function_name: &'static str) -> LuaResult<Self> {
+        if rows.is_nil() || cols.is_nil() {
+            return wrap_err!("{} expected both rows and cols, got: ({:?}, {:?})", function_name, rows, cols);
+        }
+        Ok(Self {
+            rows: dimension_from_value(rows, function_name, "rows", DEFAULT_ROWS)?,
+            cols: dimension_from_value(cols, function_name, "cols", DEFAULT_COLS)?,
+        })
+    }
+
+    #[cfg(unix)]
+    fn winsize(self) -> libc::winsize {
+        libc::winsize {
+            ws_row: self.rows,
+            ws_col: self.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::*;
+
+/// Something to read a pty child's output from, if there's a pty.
+#[cfg(unix)]
+pub fn reader(master: Option<&File>) -> io::Result<Option<Box<dyn Read + Send>>> {
+    match master {
+        Some(master) => Ok(Some(Box::new(PtyReader(master.try_clone()?)))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn reader(_master: Option<&File>) -> io::Result<Option<Box<dyn Read + Send>>> {
+    Ok(None)
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    use super::PtySize;
+
+    fn set_cloexec(fd: RawFd) -> io::Result<()> {
+        // SAFETY: fd is an open file descriptor we own; fcntl doesn't touch our memory
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+        // SAFETY: same as above
+        if flags == -1 || unsafe { libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Both sides of a freshly opened pseudo-terminal: the child gets the slave side as its stdio and
+    /// controlling terminal, and we read and write the master side.
+    pub struct Pty {
+        master: File,
+        slave: File,
+    }
+
+    impl Pty {
+        pub fn open(size: PtySize) -> io::Result<Self> {
+            let mut master: libc::c_int = -1;
+            let mut slave: libc::c_int = -1;
+            let mut winsize = size.winsize();
+            // SAFETY: master and slave are valid for writes, a null name and termios are allowed, and
+            // winsize lives for the whole call
+            let opened = unsafe {
+                libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null_mut(), &mut winsize)
+            };
+            if opened == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: openpty succeeded, so both are open fds that nothing else owns
+            let pty = unsafe {
+                Self {
+                    master: File::from_raw_fd(master),
+                    slave: File::from_raw_fd(slave),
+                }
+            };
+            // openpty doesn't set close-on-exec, and we don't want other children we spawn inheriting either side
+            set_cloexec(pty.master.as_raw_fd())?;
+            set_cloexec(pty.slave.as_raw_fd())?;
+            Ok(pty)
+        }
+
+        /// Makes the slave side the child's stdin, stdout, stderr, and controlling terminal; this starts the
+        /// child in a new session, so it leads its own process group too.
+        pub fn attach(&self, command: &mut Command) -> io::Result<()> {
+            command
+                .stdin(Stdio::from(self.slave.try_clone()?))
+                .stdout(Stdio::from(self.slave.try_clone()?))
+                .stderr(Stdio::from(self.slave.try_clone()?));
+
+            // SAFETY: setsid and ioctl are async-signal-safe, which is all pre_exec needs from us
+            unsafe {
+                command.pre_exec(|| {
+                    if libc::setsid() == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    // stdin is the slave side by now, so this makes it our controlling terminal
+                    if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+            Ok(())
+        }
+
+        /// Closes our copy of the slave side (only the child should have it open, or reading the master
+        /// side never ends) and returns the master side.
+        pub fn into_master(self) -> File {
+            drop(self.slave);
+            self.master
+        }
+    }
+
+    pub fn resize(master: &File, size: PtySize) -> io::Result<()> {
+        let winsize = size.winsize();
+        // SAFETY: master is an open pty master and winsize lives for the whole call
+        if unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ as _, &winsize) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Reads the master side of a pty, treating `EIO` as the end of output: Linux returns `EIO` instead
+    /// of EOF once every process with the slave side open has closed it.
+    pub struct PtyReader(pub File);
+
+    impl Read for PtyReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.read(buf) {
+                Err(err) if err.raw_os_error() == Some(libc::EIO) => Ok(0),
+                other => other,
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(unix)]
+use std::io;
+
+use mluau::prelude::*;
+
+use crate::prelude::*;
+
+#[cfg(unix)]
+pub const SIGKILL: i32 = libc::SIGKILL;
+#[cfg(unix)]
+pub const SIGTERM: i32 = libc::SIGTERM;
+
+// Windows doesn't have signals, but we still accept KILL (and TERM, which kills too) so
+// cross-platform scripts don't have to special case it.
+#[cfg(not(unix))]
+pub const SIGKILL: i32 = 9;
+#[cfg(not(unix))]
+pub const SIGTERM: i32 = 15;
+
+#[cfg(unix)]
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("WINCH", libc::SIGWINCH),
+];
+
+#[cfg(not(unix))]
+const SIGNALS: &[(&str, i32)] = &[
+    ("KILL", SIGKILL),
+    ("TERM", SIGTERM),
+];
+
+/// Parses a signal name like `"TERM"` or `"SIGTERM"` (case insensitive), or a signal number.
+pub fn parse_signal(value: Option<LuaValue>, function_name: &'static str) -> LuaResult<i32> {
+    match value {
+        Some(LuaValue::String(name)) => {
+            let name = name.to_string_lossy().to_ascii_uppercase();
+            let name = name.strip_prefix("SIG").unwrap_or(&name);
+            match SIGNALS.iter().find(|(signal_name, _)| *signal_name == name) {
+                Some((_, signal)) => Ok(*signal),
+                None => {
+                    let supported: Vec<&str> = SIGNALS.iter().map(|(signal_name, _)| *signal_name).collect();
+                    wrap_err!("{}: unsupported signal '{}' on this platform; expected one of: {}", function_name, name, supported.join(", "))
+                }
+            }
+        },
+        Some(LuaValue::Integer(i)) => match i32::try_from(i) {
+            Ok(signal) if signal > 0 && (cfg!(unix) || SIGNALS.iter().any(|(_, s)| *s == signal)) => Ok(signal),
+            _ => wrap_err!("{}: {} isn't a valid signal number on this platform", function_name, i),
+        },
+        Some(LuaValue::Number(f)) => {
+            wrap_err!("{}: signal number must be an integer, got: {}", function_name, f)
+        },
+        Some(other) => {
+            wrap_err!("{} expected signal to be a signal name (like \"TERM\") or number, got: {:?}", function_name, other)
+        },
+        None => {
+            wrap_err!("{} expected signal to be a signal name (like \"TERM\") or number, got nothing", function_name)
+        }
+    }
+}
+
+/// Sends `signal` to the process `pid`, or to every process in its process group if `group` is set.
+///
+/// A group that's already gone isn't an error; we're usually just making sure it's dead.
+#[cfg(unix)]
+pub fn send(pid: u32, group: bool, signal: i32) -> io::Result<()> {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return Err(io::Error::other(format!("pid {} doesn't fit in a pid_t", pid)));
+    };
+    let target = if group { -pid } else { pid };
+    // SAFETY: kill doesn't touch our memory; the caller makes sure pid is our child and hasn't been
+    // reaped (or is the id of a process group we created), so we can't signal some unrelated process
+    if unsafe { libc::kill(target, signal) } == -1 {
+        let err = io::Error::last_os_error();
+        if group && err.raw_os_error() == Some(libc::ESRCH) {
+            return Ok(());
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Whether any process in process group `pgid` is still around.
+#[cfg(unix)]
+pub fn group_alive(pgid: u32) -> bool {
+    let Ok(pgid) = libc::pid_t::try_from(pgid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks whether the group exists and whether we're allowed to signal it
+    unsafe { libc::kill(-pgid, 0) == 0 }
+}
@@ -0,0 +1,265 @@
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use jiff::SignedDuration;
+use mluau::prelude::*;
+
+use crate::prelude::*;
+use crate::std_time::duration::TimeDuration;
+use super::signal::{SIGKILL, SIGTERM};
+#[cfg(unix)]
+use super::signal;
+
+/// How long `ChildState::wait` sleeps between polls at first; doubles each time up to `MAX_POLL_INTERVAL`
+/// so short-lived children are noticed quickly without spinning on long-running ones.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A spawned child and, once it's been reaped, how it exited.
+///
+/// Everything that asks the OS about the child goes through here: on Linux we reap it ourselves with
+/// `wait4` to get its resource usage, which std's `Child` doesn't know about, so calling `Child::try_wait`
+/// or `Child::kill` after that would ask about a pid that might already belong to another process.
+pub struct ChildState {
+    child: Child,
+    started: Instant,
+    exit: Option<ExitInfo>,
+    /// whether the child leads its own process group, so signals go to the whole group
+    group: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct ExitInfo {
+    status: ExitStatus,
+    elapsed: Duration,
+    usage: Option<ResourceUsage>,
+}
+
+#[derive(Clone, Copy)]
+struct ResourceUsage {
+    /// in bytes
+    peak_rss: u64,
+    user_time: Duration,
+    system_time: Duration,
+}
+
+impl ChildState {
+    pub fn new(child: Child, group: bool) -> Self {
+        Self {
+            child,
+            started: Instant::now(),
+            exit: None,
+            group,
+        }
+    }
+
+    pub fn exited(&self) -> bool {
+        self.exit.is_some()
+    }
+
+    /// Reaps the child if it's exited, without blocking.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitInfo>> {
+        if let Some(exit) = self.exit {
+            return Ok(Some(exit));
+        }
+        self.reap(false)
+    }
+
+    /// Blocks until the child exits or `timeout` passes, returning `None` in the latter case.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Option<ExitInfo>> {
+        if let Some(exit) = self.exit {
+            return Ok(Some(exit));
+        }
+        let Some(timeout) = timeout else {
+            return self.reap(true);
+        };
+
+        let deadline = Instant::now() + timeout;
+        let mut interval = MIN_POLL_INTERVAL;
+        loop {
+            if let Some(exit) = self.reap(false)? {
+                return Ok(Some(exit));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(interval.min(deadline - now));
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        }
+    }
+
+    /// Kills the child (and its process group, if it has one) unless there's nothing left to kill.
+    pub fn kill(&mut self) -> io::Result<()> {
+        if self.group {
+            return self.signal(SIGKILL);
+        }
+        if self.exited() {
+            return Ok(());
+        }
+        self.child.kill()
+    }
+
+    /// Sends `signal` to the child, or to its whole process group if it has one.
+    ///
+    /// Once the child's been reaped we only signal its group (whatever's left of it) since its pid could've been reused.
+    #[cfg(unix)]
+    pub fn signal(&mut self, signal: i32) -> io::Result<()> {
+        if !self.group && self.exited() {
+            return Ok(());
+        }
+        signal::send(self.child.id(), self.group, signal)
+    }
+
+    #[cfg(not(unix))]
+    pub fn signal(&mut self, signal: i32) -> io::Result<()> {
+        // TERM and KILL are the only signals we accept on windows, and both just kill the child
+        debug_assert!(signal == SIGKILL || signal == SIGTERM);
+        if self.exited() {
+            return Ok(());
+        }
+        self.child.kill()
+    }
+
+    /// Asks the child to exit with SIGTERM, then SIGKILLs it if it's still running after `grace`.
+    ///
+    /// If the child has its own process group, anything left in the group after the child exits gets the same treatment.
+    pub fn terminate(&mut self, grace: Duration) -> io::Result<ExitInfo> {
+        let deadline = Instant::now() + grace;
+        self.signal(SIGTERM)?;
+
+        let exit = match self.wait(Some(grace))? {
+            Some(exit) => exit,
+            None => {
+                self.signal(SIGKILL)?;
+                match self.wait(None)? {
+                    Some(exit) => exit,
+                    None => unreachable!("waiting without a timeout always returns the child's exit"),
+                }
+            }
+        };
+
+        #[cfg(unix)]
+        if self.group {
+            let pgid = self.child.id();
+            let mut interval = MIN_POLL_INTERVAL;
+            while signal::group_alive(pgid) && Instant::now() < deadline {
+                thread::sleep(interval.min(deadline.saturating_duration_since(Instant::now())));
+                interval = (interval * 2).min(MAX_POLL_INTERVAL);
+            }
+            if signal::group_alive(pgid) {
+                self.signal(SIGKILL)?;
+            }
+        }
+
+        Ok(exit)
+    }
+
+    fn record_exit(&mut self, status: ExitStatus, usage: Option<ResourceUsage>) -> ExitInfo {
+        let exit = ExitInfo {
+            status,
+            elapsed: self.started.elapsed(),
+            usage,
+        };
+        self.exit = Some(exit);
+        exit
+    }
+
+    #[cfg(target_os = "linux")]
+    fn reap(&mut self, block: bool) -> io::Result<Option<ExitInfo>> {
+        use std::os::unix::process::ExitStatusExt;
+
+        let Ok(pid) = libc::pid_t::try_from(self.child.id()) else {
+            return Err(io::Error::other(format!("pid {} doesn't fit in a pid_t", self.child.id())));
+        };
+        let flags = if block { 0 } else { libc::WNOHANG };
+        let mut status: libc::c_int = 0;
+        // SAFETY: rusage is a plain C struct of integers, so all zeroes is a valid value
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+
+        loop {
+            // SAFETY: status and rusage are valid for writes for the whole call, and pid is our own child,
+            // which hasn't been reaped yet because we'd have cached its exit and returned early otherwise
+            let reaped = unsafe { libc::wait4(pid, &mut status, flags, &mut rusage) };
+            match reaped {
+                0 => return Ok(None),
+                -1 => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(err);
+                },
+                _ => break,
+            }
+        }
+
+        let usage = ResourceUsage {
+            // linux reports ru_maxrss in kilobytes
+            peak_rss: u64::try_from(rusage.ru_maxrss).unwrap_or(0).saturating_mul(1024),
+            user_time: timeval_to_duration(rusage.ru_utime),
+            system_time: timeval_to_duration(rusage.ru_stime),
+        };
+        Ok(Some(self.record_exit(ExitStatus::from_raw(status), Some(usage))))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn reap(&mut self, block: bool) -> io::Result<Option<ExitInfo>> {
+        let status = if block {
+            Some(self.child.wait()?)
+        } else {
+            self.child.try_wait()?
+        };
+        Ok(status.map(|status| self.record_exit(status, None)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn timeval_to_duration(timeval: libc::timeval) -> Duration {
+    let secs = u64::try_from(timeval.tv_sec).unwrap_or(0);
+    let micros = u64::try_from(timeval.tv_usec).unwrap_or(0);
+    Duration::from_secs(secs) + Duration::from_micros(micros)
+}
+
+fn duration_userdata(luau: &Lua, duration: Duration) -> LuaValueResult {
+    let signed = SignedDuration::try_from(duration).unwrap_or(SignedDuration::MAX);
+    TimeDuration::new(signed).get_userdata(luau)
+}
+
+impl ExitInfo {
+    #[cfg(unix)]
+    fn signal(&self) -> Option<i32> {
+        use std::os::unix::process::ExitStatusExt;
+        self.status.signal()
+    }
+
+    #[cfg(not(unix))]
+    fn signal(&self) -> Option<i32> {
+        None
+    }
+
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// The `ExitStatus` table returned by `ChildProcess:wait()` and `ChildProcess:try_wait()`.
+    pub fn to_table(self, luau: &Lua) -> LuaValueResult {
+        let mut builder = TableBuilder::create(luau)?
+            .with_value("ok", self.status.success())?
+            .with_value("code", self.status.code())?
+            .with_value("signal", self.signal())?
+            .with_value("elapsed", duration_userdata(luau, self.elapsed)?)?;
+
+        if let Some(usage) = self.usage {
+            builder = builder
+                .with_value("peak_rss", usage.peak_rss)?
+                .with_value("user_time", duration_userdata(luau, usage.user_time)?)?
+                .with_value("system_time", duration_userdata(luau, usage.system_time)?)?
+                .with_value("cpu_time", duration_userdata(luau, usage.user_time + usage.system_time)?)?;
+        }
+
+        ok_table(builder.build_readonly())
+    }
+}
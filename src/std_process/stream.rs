@@ -14,7 +14,7 @@ use crate::std_time::duration::TimeDuration;
 
 /// Parses a `timeout` argument that may be a number (in seconds) or a `Duration` (from `@std/time`)
 /// into an optional std Duration. A nil/absent value means no timeout (`None`).
-fn parse_timeout_value(value: Option<LuaValue>, function_name: &'static str) -> LuaResult<Option<Duration>> {
+pub fn parse_timeout_value(value: Option<LuaValue>, function_name: &'static str) -> LuaResult<Option<Duration>> {
     let seconds = match value {
         Some(LuaValue::Number(f)) => f,
         Some(LuaValue::Integer(i)) => i as f64,
@@ -79,6 +79,93 @@ impl TruncateSide {
     }
 }
 
+/// What `ChildProcess:expect()` waits for: a literal string, or a `{ regex: string }` table.
+pub enum ExpectPattern {
+    Literal(Vec<u8>),
+    Regex(regex::bytes::Regex),
+}
+
+/// A regex match's capture groups, copied out of the stream's buffer so it can be drained.
+#[derive(Default)]
+pub struct ExpectCaptures {
+    /// numbered groups (without the whole match at 0); `None` for groups that didn't participate
+    groups: Vec<Option<Vec<u8>>>,
+    named: Vec<(String, Vec<u8>)>,
+}
+
+impl ExpectCaptures {
+    fn into_table(self, luau: &Lua) -> LuaResult<LuaTable> {
+        let captures = luau.create_table_with_capacity(self.groups.len(), self.named.len())?;
+        for (index, group) in self.groups.into_iter().enumerate() {
+            if let Some(group) = group {
+                captures.raw_set(index + 1, luau.create_string(group)?)?;
+            }
+        }
+        for (name, group) in self.named {
+            captures.raw_set(name, luau.create_string(group)?)?;
+        }
+        captures.set_readonly(true);
+        Ok(captures)
+    }
+}
+
+impl ExpectPattern {
+    pub fn from_value(value: Option<LuaValue>, function_name: &'static str) -> LuaResult<Self> {
+        match value {
+            Some(LuaValue::String(literal)) => {
+                let literal = literal.as_bytes().to_vec();
+                if literal.is_empty() {
+                    return wrap_err!("{} expected pattern to be a non-empty string", function_name);
+                }
+                Ok(Self::Literal(literal))
+            },
+            Some(LuaValue::Table(table)) => match table.raw_get::<LuaValue>("regex")? {
+                LuaValue::String(source) => {
+                    let source = source.to_str()?;
+                    match regex::bytes::Regex::new(&source) {
+                        Ok(regex) => Ok(Self::Regex(regex)),
+                        Err(err) => {
+                            wrap_err!("{}: invalid regex {:?}: {}", function_name, &*source, err)
+                        }
+                    }
+                },
+                other => {
+                    wrap_err!("{} expected pattern.regex to be a string, got: {:?}", function_name, other)
+                }
+            },
+            Some(other) => {
+                wrap_err!("{} expected pattern to be a string or a {{ regex: string }} table, got: {:?}", function_name, other)
+            },
+            None => {
+                wrap_err!("{} expected pattern (string or {{ regex: string }}), but was incorrectly called with zero arguments", function_name)
+            }
+        }
+    }
+
+    /// Finds the first match in `haystack`, returning where it starts and ends along with any capture groups.
+    fn find(&self, haystack: &[u8]) -> Option<(usize, usize, ExpectCaptures)> {
+        match self {
+            Self::Literal(needle) => {
+                let start = memchr::memmem::find(haystack, needle)?;
+                Some((start, start + needle.len(), ExpectCaptures::default()))
+            },
+            Self::Regex(regex) => {
+                let found = regex.captures(haystack)?;
+                let whole = found.get(0)?;
+                let groups = found.iter()
+                    .skip(1)
+                    .map(|group| group.map(|m| m.as_bytes().to_vec()))
+                    .collect();
+                let named = regex.capture_names()
+                    .flatten()
+                    .filter_map(|name| found.name(name).map(|m| (name.to_string(), m.as_bytes().to_vec())))
+                    .collect();
+                Some((whole.start(), whole.end(), ExpectCaptures { groups, named }))
+            }
+        }
+    }
+}
+
 /// Multithreaded wrapper type that abstracts reading from a child process' stdout or stderr.
 ///
 /// This is a cross-platform compatible solution that makes sure reading from stdout/stderr is nonblocking;
@@ -467,6 +554,47 @@ impl Stream {
         }
     }
 
+    /// Waits for `pattern` to show up in the stream, consuming everything up to and including the match.
+    ///
+    /// Returns an `ExpectMatch` table, or nil if the timeout elapses or the stream ends before anything matches.
+    pub fn expect(&mut self, luau: &Lua, pattern: &ExpectPattern, timeout: Option<Duration>, function_name: &'static str) -> LuaValueResult {
+        self.alive(function_name)?;
+        let start_time = Instant::now();
+
+        loop {
+            // check before searching so whatever the reader thread added right before it stopped still gets searched
+            let still_reading = self.still_reading.load(Ordering::Relaxed);
+
+            let mut inner = match self.inner.lock() {
+                Ok(guard) => guard,
+                Err(err) => {
+                    unreachable!("{}: callers of this function shouldn't panic and cause this mutex to become poisoned; if they do, that's a bug we should propagate: {}", function_name, err);
+                }
+            };
+
+            inner.make_contiguous();
+            let found = pattern.find(inner.as_slices().0);
+            if let Some((start, end, captures)) = found {
+                let mut drained: Vec<u8> = inner.drain(..end).collect();
+                drop(inner);
+                let matched = drained.split_off(start);
+                return ok_table(
+                    TableBuilder::create(luau)?
+                        .with_value("before", luau.create_string(&drained)?)?
+                        .with_value("match", luau.create_string(&matched)?)?
+                        .with_value("captures", captures.into_table(luau)?)?
+                        .build_readonly()
+                );
+            }
+            drop(inner);
+
+            if !still_reading || timeout.is_some_and(|timeout| start_time.elapsed() >= timeout) {
+                return Ok(LuaNil);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
     pub fn fill(&mut self, _luau: &Lua, mut multivalue: LuaMultiValue) -> LuaValueResult {
         let function_name = match self.stream_type {
             StreamType::Stdout => "ChildProcess.stdout:fill(target: buffer, target_offset: number?, timeout: number?)",
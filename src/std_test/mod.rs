@@ -0,0 +1,34 @@
+use mluau::prelude::*;
+use crate::prelude::*;
+
+pub mod runner;
+
+const TEST_DOT_LUAU_SRC: &str = include_str!("./test.luau");
+/// where a VM's `@std/test` table lives once something's required it
+pub const REGISTRY_KEY: &str = "test.library";
+/// where a VM's function that actually runs its registered tests lives, for `seal test` to call
+pub const RUN_KEY: &str = "test.run";
+/// set by `seal test` before loading a file so the file's own `test.run()` leaves running its tests to `seal test`
+pub const DEFERRED_KEY: &str = "test.deferred";
+
+/// Every `require("@std/test")` in a VM gets the same table since that's where tests are registered,
+/// and `seal test` needs to find the tests a file registered after running it.
+pub fn create(luau: &Lua) -> LuaResult<LuaTable> {
+    if let Some(test) = luau.named_registry_value::<Option<LuaTable>>(REGISTRY_KEY)? {
+        return Ok(test);
+    }
+
+    let deferred = luau.named_registry_value::<Option<bool>>(DEFERRED_KEY)?.unwrap_or(false);
+    let chunk = Chunk::src(TEST_DOT_LUAU_SRC);
+    let (test, run) = match luau.load(chunk).set_name("@std/test").call::<(LuaTable, LuaFunction)>(deferred) {
+        Ok(t) => t,
+        Err(err) => {
+            panic!("std/test's test.luau did a bad: {}", err);
+        }
+    };
+    test.set_readonly(true);
+
+    luau.set_named_registry_value(REGISTRY_KEY, &test)?;
+    luau.set_named_registry_value(RUN_KEY, run)?;
+    Ok(test)
+}
@@ -0,0 +1,390 @@
+//! `seal test`: finds `*.test.luau` and `*.spec.luau` files, runs each in its own Luau VM (in parallel threads
+//! with `--jobs`), and prints what `@std/test` reports for them followed by a summary.
+
+use mluau::prelude::*;
+use crate::prelude::*;
+
+use crate::{Args, LuauLoadInfo};
+use crate::std_env;
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const TEST_FILE_SUFFIXES: [&str; 2] = [".test.luau", ".spec.luau"];
+/// directories that never have tests worth running in them
+const SKIPPED_DIRECTORIES: [&str; 2] = ["node_modules", "target"];
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+pub fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| TEST_FILE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)))
+}
+
+struct TestOptions {
+    /// files and directories to look for tests in; the project's `test_path` (or the cwd) if empty
+    paths: Vec<PathBuf>,
+    /// only run tests whose full name (`describe > it`) contains this
+    filter: Option<String>,
+    jobs: usize,
+    watch: bool,
+}
+
+impl TestOptions {
+    fn from_args(mut args: Args, function_name: &'static str) -> LuaResult<Self> {
+        let mut paths = Vec::new();
+        let mut filter = None;
+        let mut jobs = 1;
+        let mut watch = false;
+
+        while let Some(arg) = args.pop_front() {
+            let Some(arg) = arg.to_str() else {
+                return wrap_err!("{}: arguments must be valid utf-8", function_name);
+            };
+            match arg {
+                "--filter" | "-f" => {
+                    filter = match args.pop_front().and_then(|value| value.into_string().ok()) {
+                        Some(value) => Some(value),
+                        None => {
+                            return wrap_err!("{}: expected part of a test's name after {}", function_name, arg);
+                        }
+                    };
+                },
+                "--jobs" | "-j" => {
+                    let value = args.pop_front().and_then(|value| value.into_string().ok());
+                    jobs = match value.as_deref().map(str::parse::<usize>) {
+                        Some(Ok(jobs)) if jobs > 0 => jobs,
+                        _ => {
+                            return wrap_err!("{}: expected a number of threads (at least 1) after {}, got: {:?}", function_name, arg, value);
+                        }
+                    };
+                },
+                "--parallel" | "-p" => {
+                    jobs = thread::available_parallelism().map(|jobs| jobs.get()).unwrap_or(1);
+                },
+                "--watch" | "-w" => watch = true,
+                flag if flag.starts_with('-') => {
+                    return wrap_err!("{}: unknown flag '{}'; see seal test --help", function_name, flag);
+                },
+                path => paths.push(PathBuf::from(path)),
+            }
+        }
+
+        Ok(Self { paths, filter, jobs, watch })
+    }
+}
+
+/// Adds every test file in `directory` (recursively) to `found`, skipping hidden directories
+fn discover_in(directory: &Path, found: &mut Vec<PathBuf>, function_name: &'static str) -> LuaEmptyResult {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            return wrap_err!("{}: unable to look for tests in '{}' due to err: {}", function_name, directory.display(), err);
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_ref()) {
+                discover_in(&path, found, function_name)?;
+            }
+        } else if is_test_file(&path) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Test files in `roots`; files passed directly are run even if they aren't named like tests
+fn discover(roots: &[PathBuf], function_name: &'static str) -> LuaResult<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for root in roots {
+        if root.is_file() {
+            found.push(root.clone());
+        } else if root.is_dir() {
+            let start = found.len();
+            discover_in(root, &mut found, function_name)?;
+            found[start..].sort();
+        } else {
+            return wrap_err!("{}: '{}' doesn't exist", function_name, root.display());
+        }
+    }
+    found.dedup();
+    Ok(found)
+}
+
+/// How one file went: `@std/test`'s counts and report, or why it couldn't be run at all
+struct FileResult {
+    path: PathBuf,
+    passed: u32,
+    failed: u32,
+    skipped: u32,
+    todo: u32,
+    report: String,
+    /// the file errored before its tests could run (syntax error, error at the top level, etc.)
+    error: Option<String>,
+    /// the file never used `@std/test`
+    no_tests: bool,
+    duration: Duration,
+}
+
+impl FileResult {
+    fn new(path: PathBuf, duration: Duration) -> Self {
+        Self {
+            path,
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            todo: 0,
+            report: String::new(),
+            error: None,
+            no_tests: false,
+            duration,
+        }
+    }
+
+    fn errored(path: PathBuf, error: String, duration: Duration) -> Self {
+        Self { error: Some(error), ..Self::new(path, duration) }
+    }
+
+    fn failed(&self) -> bool {
+        self.error.is_some() || self.failed > 0
+    }
+}
+
+fn count(results: &LuaTable, key: &'static str) -> LuaResult<u32> {
+    Ok(results.raw_get::<Option<u32>>(key)?.unwrap_or_default())
+}
+
+/// Runs the file at `path` in a fresh VM so files can't see each other's globals or required modules
+fn run_file(path: PathBuf, filter: Option<&str>) -> FileResult {
+    let function_name = "seal test";
+    let started = Instant::now();
+
+    let LuauLoadInfo { luau, code, chunk_name } = match crate::resolve_file(path.to_string_lossy().into_owned(), function_name) {
+        Ok(Some(info)) => info,
+        Ok(None) => return FileResult::errored(path, String::from("unable to load file"), started.elapsed()),
+        Err(err) => return FileResult::errored(path, err.to_string(), started.elapsed()),
+    };
+
+    // files end with test.run() so they can run on their own, but we run their tests ourselves below
+    if let Err(err) = luau.set_named_registry_value(super::DEFERRED_KEY, true) {
+        return FileResult::errored(path, err.to_string(), started.elapsed());
+    }
+    if let Err(err) = luau.load(code).set_name(chunk_name).exec() {
+        return FileResult::errored(path, err.to_string(), started.elapsed());
+    }
+
+    let results = match luau.named_registry_value::<Option<LuaFunction>>(super::RUN_KEY) {
+        Ok(Some(run)) => {
+            let options = TableBuilder::create(&luau)
+                .and_then(|options| options.with_value("filter", filter)?.with_value("report", false)?.build());
+            options.and_then(|options| run.call::<LuaTable>(options))
+        },
+        Ok(None) => {
+            return FileResult { no_tests: true, ..FileResult::new(path, started.elapsed()) };
+        },
+        Err(err) => Err(err),
+    };
+
+    let results = results.and_then(|results| {
+        Ok(FileResult {
+            passed: count(&results, "passed")?,
+            failed: count(&results, "failed")?,
+            skipped: count(&results, "skipped")?,
+            todo: count(&results, "todo")?,
+            report: results.raw_get::<Option<String>>("report")?.unwrap_or_default(),
+            ..FileResult::new(path.clone(), started.elapsed())
+        })
+    });
+    match results {
+        Ok(results) => results,
+        Err(err) => FileResult::errored(path, err.to_string(), started.elapsed()),
+    }
+}
+
+fn paint(color: &'static str, text: &str) -> String {
+    if colors::are_disabled() {
+        text.to_string()
+    } else {
+        format!("{}{}{}", color, text, colors::RESET)
+    }
+}
+
+fn print_file(result: &FileResult) -> LuaEmptyResult {
+    let elapsed = format!("({}ms)", result.duration.as_millis());
+    let path = result.path.display();
+    if let Some(error) = &result.error {
+        puts!("{} {}\n\n{}\n", paint(colors::RED_BG, " FAIL "), path, error)?;
+    } else if result.no_tests {
+        puts!("{} {} {}", paint(colors::YELLOW_BG, " NONE "), path, paint(colors::BRIGHT_BLACK, "(never required @std/test)"))?;
+    } else if result.failed > 0 {
+        puts!("{} {} {}\n{}\n", paint(colors::RED_BG, " FAIL "), path, paint(colors::BRIGHT_BLACK, &elapsed), result.report)?;
+    } else {
+        puts!("{} {} {}", paint(colors::GREEN_BG, " PASS "), path, paint(colors::BRIGHT_BLACK, &elapsed))?;
+    }
+    Ok(())
+}
+
+fn print_summary(results: &[FileResult], elapsed: Duration) -> LuaEmptyResult {
+    let sum = |field: fn(&FileResult) -> u32| results.iter().map(field).sum::<u32>();
+    let (passed, failed, skipped, todo) = (sum(|r| r.passed), sum(|r| r.failed), sum(|r| r.skipped), sum(|r| r.todo));
+    let failed_files = results.iter().filter(|result| result.failed()).count();
+
+    let mut tests = Vec::new();
+    if failed > 0 {
+        tests.push(paint(colors::BOLD_RED, &format!("{} failed", failed)));
+    }
+    tests.push(paint(colors::BOLD_GREEN, &format!("{} passed", passed)));
+    if skipped > 0 {
+        tests.push(paint(colors::YELLOW, &format!("{} skipped", skipped)));
+    }
+    if todo > 0 {
+        tests.push(paint(colors::MAGENTA, &format!("{} todo", todo)));
+    }
+
+    let mut files = Vec::new();
+    if failed_files > 0 {
+        files.push(paint(colors::BOLD_RED, &format!("{} failed", failed_files)));
+    }
+    files.push(paint(colors::BOLD_GREEN, &format!("{} passed", results.len() - failed_files)));
+
+    puts!(
+        "\nTests: {}, {} total\nFiles: {}, {} total\nTime:  {:.2}s",
+        tests.join(", "), passed + failed + skipped + todo,
+        files.join(", "), results.len(),
+        elapsed.as_secs_f64()
+    )
+}
+
+/// Runs every file, `jobs` at a time, printing each file's results as soon as it's done; returns
+/// whether everything passed
+fn run_files(files: Vec<PathBuf>, options: &TestOptions) -> LuaResult<bool> {
+    let started = Instant::now();
+    let filter = options.filter.as_deref();
+    let mut results = Vec::with_capacity(files.len());
+
+    if options.jobs <= 1 || files.len() <= 1 {
+        for file in files {
+            let result = run_file(file, filter);
+            print_file(&result)?;
+            results.push(result);
+        }
+    } else {
+        let queue = Mutex::new(files.into_iter().collect::<VecDeque<_>>());
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| -> LuaEmptyResult {
+            for _ in 0..options.jobs {
+                let sender = sender.clone();
+                let queue = &queue;
+                scope.spawn(move || loop {
+                    let next = queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                    let Some(file) = next else {
+                        break;
+                    };
+                    if sender.send(run_file(file, filter)).is_err() {
+                        break;
+                    }
+                });
+            }
+            // so the loop below ends once every worker's done
+            drop(sender);
+            for result in receiver {
+                print_file(&result)?;
+                results.push(result);
+            }
+            Ok(())
+        })?;
+    }
+
+    print_summary(&results, started.elapsed())?;
+    Ok(!results.iter().any(FileResult::failed))
+}
+
+/// Modification times of every `.luau` file under `directory`, so `--watch` can tell when something changed
+fn snapshot(directory: &Path, times: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_ref()) {
+                snapshot(&path, times);
+            }
+        } else if path.extension().is_some_and(|extension| extension == "luau")
+            && let Ok(modified) = metadata.modified()
+        {
+            times.insert(path, modified);
+        }
+    }
+}
+
+fn run_once(roots: &[PathBuf], options: &TestOptions, function_name: &'static str) -> LuaResult<bool> {
+    let files = discover(roots, function_name)?;
+    if files.is_empty() {
+        let roots: Vec<String> = roots.iter().map(|root| root.display().to_string()).collect();
+        puts!("{}: no test files (*.test.luau or *.spec.luau) found in {}", function_name, roots.join(", "))?;
+        return Ok(true);
+    }
+    run_files(files, options)
+}
+
+/// `test_path` from `.seal/config.luau` is where tests are looked for when no paths are passed
+pub fn run(args: Args, test_path: Option<String>) -> LuaEmptyResult {
+    let function_name = "seal test";
+    let options = TestOptions::from_args(args, function_name)?;
+
+    let roots = if !options.paths.is_empty() {
+        options.paths.clone()
+    } else if let Some(test_path) = test_path {
+        vec![PathBuf::from(test_path)]
+    } else {
+        vec![PathBuf::from(".")]
+    };
+
+    if !options.watch {
+        if !run_once(&roots, &options, function_name)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // changes to the code being tested should rerun tests too, not just changes to the tests
+    let cwd = std_env::get_cwd(function_name)?;
+    let mut last = HashMap::new();
+    snapshot(&cwd, &mut last);
+    loop {
+        // a test file with a syntax error shouldn't stop us from watching
+        if let Err(err) = run_once(&roots, &options, function_name) {
+            eputs!("{}", err)?;
+        }
+        puts!("{}", paint(colors::BRIGHT_BLACK, "\nwatching for changes (ctrl+c to stop)..."))?;
+
+        loop {
+            thread::sleep(WATCH_POLL_INTERVAL);
+            let mut current = HashMap::new();
+            snapshot(&cwd, &mut current);
+            if current != last {
+                last = current;
+                break;
+            }
+        }
+        // clear the screen so only the latest run's output is showing
+        put!("\x1b[2J\x1b[H")?;
+    }
+}
@@ -13,6 +13,17 @@ mod thread_spawn_options;
 use thread_spawn_options::ThreadSpawnOptions;
 use channel::Channel;
 
+pub(crate) use thread_spawn_options::path_relative_to_script;
+
+/// Creates the fresh sandboxed Luau VM that code running on another Rust thread gets
+/// (also used by `server.serve`'s workers)
+pub(crate) fn create_sandboxed_vm(chunk_name: &str) -> LuaResult<Lua> {
+    let new_luau = Lua::default();
+    new_luau.sandbox(true)?;
+    globals::set_globals(&new_luau, chunk_name.to_string())?;
+    Ok(new_luau)
+}
+
 fn thread_sleep(_luau: &Lua, duration: LuaNumber) -> LuaValueResult {
     let dur = Duration::from_millis(duration as u64);
     thread::sleep(dur);
@@ -62,14 +73,12 @@ fn thread_spawn(luau: &Lua, value: LuaValue) -> LuaValueResult {
         .name(options.name.clone());
 
     let join_handle_result = thread_builder.spawn(move || -> LuaEmptyResult {
-        let new_luau = Lua::default();
-        new_luau.sandbox(true)?;
+        let new_luau = create_sandboxed_vm(&options.chunk_name)?;
         let data = match options.data {
             Some(data) => deserialize_data_from_transit(&new_luau, data)?,
             None => LuaNil,
         };
 
-        globals::set_globals(&new_luau, options.chunk_name.clone())?;
         // must use globals.set() due to safeenv
         new_luau.globals().set("channel", TableBuilder::create(&new_luau)?
             .with_function_and_signature("read", {
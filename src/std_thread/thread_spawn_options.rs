@@ -6,6 +6,26 @@ use petname::Generator;
 /// helper struct for ThreadSpawnOptions tables so we don't crowd std_thread
 use std::{fs, io, path::PathBuf};
 
+/// Resolves `path` relative to the calling script's parent directory
+pub fn path_relative_to_script(luau: &Lua, path: &str, function_name: &'static str) -> LuaResult<PathBuf> {
+    let LuaValue::String(parent_path) = globals::get_script_parent(luau, LuaMultiValue::new())? else {
+        return wrap_err!("{}: unable to get script's parent path", function_name);
+    };
+    let path = PathBuf::from(path);
+    let parent_path = {
+        let parent = PathBuf::from(parent_path.to_string_lossy());
+        if path.starts_with("..")
+            && let Some(parent_parent) = parent.parent()
+        {
+            parent_parent.to_path_buf()
+        } else {
+            parent
+        }
+    };
+    #[allow(clippy::disallowed_methods, reason = "if path is an absolute path here it's most likely intentional and clobbering here would lead to desired behavior; keeping this unchecked for now")]
+    Ok(parent_path.join(path))
+}
+
 pub struct ChannelCapacity {
     pub regular: usize,
     pub bytes: usize,
@@ -57,25 +77,7 @@ impl ThreadSpawnOptions {
             }
         };
         let path = match t.raw_get::<LuaValue>("path")? {
-            LuaValue::String(path) => {
-                // need to make sure path is relative to the calling script's path
-                let LuaValue::String(parent_path) = globals::get_script_parent(luau, LuaMultiValue::new())? else {
-                    return wrap_err!("{}: unable to get script's parent path", function_name);
-                };
-                let path = PathBuf::from(path.to_string_lossy());
-                let parent_path = {
-                    let parent = PathBuf::from(parent_path.to_string_lossy());
-                    if path.starts_with("..")
-                        && let Some(parent_parent) = parent.parent()
-                    {
-                        parent_parent.to_path_buf()
-                    } else {
-                        parent
-                    }
-                };
-                #[allow(clippy::disallowed_methods, reason = "if path is an absolute path here it's most likely intentional and clobbering here would lead to desired behavior; keeping this unchecked for now")]
-                Some(parent_path.join(path))
-            }
+            LuaValue::String(path) => Some(path_relative_to_script(luau, &path.to_string_lossy(), function_name)?),
             LuaNil => None,
             other => {
                 return wrap_err!("{}: ThreadSpawnOptions.path expected to be a string, got: {:?}", function_name, other);